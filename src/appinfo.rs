@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use ed25519_dalek::VerifyingKey;
+use hex::FromHex;
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{extract::SlashCommand, interact::InteractError, AppState};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "refresh-appinfo",
+    desc = "Fetch the bot's application info from Discord again",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct RefreshAppInfoCommand;
+
+impl RefreshAppInfoCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+/// Parse the hex-encoded public key Discord gives for an application.
+pub fn parse_verify_key(hex: &str) -> Result<VerifyingKey, KeyError> {
+    let bytes = <[u8; 32]>::from_hex(hex).map_err(|_| KeyError::Hex)?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| KeyError::Bytes)
+}
+
+pub async fn refresh_appinfo(
+    State(state): State<AppState>,
+    SlashCommand(RefreshAppInfoCommand): SlashCommand<RefreshAppInfoCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let info = state
+        .client
+        .current_user_application()
+        .await?
+        .model()
+        .await?;
+    let key = parse_verify_key(&info.verify_key)?;
+    let changed = !state.keys.load().contains(&key);
+    state.keys.store(Arc::new(vec![key]));
+
+    let content = if changed {
+        tracing::warn!(
+            "Verify key changed, now using {}",
+            hex::encode(key.as_bytes())
+        );
+        "Refreshed the application info. The verify key changed, and the new one is now in use."
+    } else {
+        "Refreshed the application info. The verify key is unchanged."
+    };
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(content)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub enum KeyError {
+    #[error("Discord sent a verify key which isn't valid hex")]
+    Hex,
+    #[error("Discord sent a verify key which isn't a valid public key")]
+    Bytes,
+}
@@ -0,0 +1,200 @@
+use twilight_model::{
+    channel::Message,
+    id::{
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
+};
+
+use crate::{config::FormConfig, AppState};
+
+/// Put in front of the content of reports a moderator claimed, followed by
+/// their mention.
+pub const CLAIMED: &str = "👋 Claimed by ";
+
+/// The moderator whose turn it is to get a report posted to
+/// `target_channel`, if its form has an `assign_rotation`. Moderators who are
+/// unavailable in `guild_id` are skipped, and lose their turn. Whose turn it
+/// is lives in the store, so without one, or while it's down, or when nobody
+/// is available, reports ping `ping_role` like usual instead.
+pub async fn next(
+    state: &AppState,
+    form: &FormConfig,
+    guild_id: Option<Id<GuildMarker>>,
+    target_channel: Id<ChannelMarker>,
+) -> Option<Id<UserMarker>> {
+    let (Some(store), Some(guild_id)) = (&state.store, guild_id) else {
+        return None;
+    };
+    let len = u64::try_from(form.assign_rotation.len()).ok()?;
+    for _ in 0..len {
+        let turn = store
+            .next_turn(target_channel)
+            .await
+            .inspect_err(|e| {
+                tracing::error!("Failed to take a turn of the rotation of {target_channel}: {e:?}");
+            })
+            .ok()?;
+        let index = usize::try_from(turn % len).ok()?;
+        let moderator = form.assign_rotation.get(index).copied()?;
+        if state.availability.is_available(guild_id, moderator) {
+            return Some(moderator);
+        }
+    }
+    tracing::warn!("Nobody in the rotation of {target_channel} is available");
+    None
+}
+
+/// Remember who a posted report is assigned to. The report says who claimed
+/// it already, so failing here only gets logged.
+pub async fn assign(state: &AppState, posted: &Message, moderator: Id<UserMarker>) {
+    let Some(store) = &state.store else {
+        return;
+    };
+    if let Err(e) = store
+        .assign_report(posted.channel_id, posted.id, moderator)
+        .await
+    {
+        tracing::error!(
+            "Failed to remember that report {} is assigned to {moderator}: {e:?}",
+            posted.id
+        );
+    }
+}
+
+/// Forget who a report was assigned to once it's resolved.
+pub async fn unassign(state: &AppState, message: &Message) {
+    let Some(store) = &state.store else {
+        return;
+    };
+    if let Err(e) = store.unassign_report(message.channel_id, message.id).await {
+        tracing::warn!(
+            "Failed to forget who report {} was assigned to: {e:?}",
+            message.id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        interact::{post_report, ModmailFormModal},
+        testing::{self, MockDiscord},
+    };
+
+    const MODMAIL: u64 = 60;
+    const MOD_ROLE: u64 = 7;
+
+    async fn discord() -> MockDiscord {
+        MockDiscord::start(|request| match request.path.as_str() {
+            "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await
+    }
+
+    fn form() -> FormConfig {
+        FormConfig {
+            resolve_button: true,
+            ping_role: Some(Id::new(MOD_ROLE)),
+            escalate_after_secs: Some(600),
+            assign_rotation: vec![Id::new(50), Id::new(51)],
+            ..FormConfig::default()
+        }
+    }
+
+    /// Post a report to [`MODMAIL`] and return the body it was posted with.
+    async fn post(state: &AppState, discord: &MockDiscord) -> Value {
+        let report = ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: None,
+            channel: None,
+            reason: "Being rude".to_string(),
+            custom: BTreeMap::new(),
+        };
+        post_report(
+            state,
+            Some(Id::new(testing::GUILD)),
+            Id::new(30),
+            Id::new(MODMAIL),
+            report,
+        )
+        .await
+        .unwrap();
+        let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+        posted.last().unwrap().body.clone()
+    }
+
+    #[test]
+    fn assigns_reports_in_turn() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let store = testing::store().await;
+            let state = AppState {
+                store: Some(store.clone()),
+                forms: testing::forms(Id::new(MODMAIL), form()),
+                ..discord.state()
+            };
+            for moderator in [50, 51, 50] {
+                let body = post(&state, &discord).await;
+                assert_eq!(
+                    body["content"],
+                    format!("👋 Claimed by <@{moderator}>\nReport from <@30>")
+                );
+                // Only the moderator whose turn it is gets pinged.
+                assert_eq!(
+                    body["allowed_mentions"]["users"],
+                    json!([moderator.to_string()])
+                );
+                assert!(body["allowed_mentions"]["roles"]
+                    .as_array()
+                    .is_none_or(Vec::is_empty));
+            }
+            // They're handling it, so nobody else needs to be pinged later.
+            assert!(store.overdue_reports(u64::MAX).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn skips_unavailable_moderators() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let state = AppState {
+                store: Some(testing::store().await),
+                forms: testing::forms(Id::new(MODMAIL), form()),
+                ..discord.state()
+            };
+            let guild = Id::new(testing::GUILD);
+            state.availability.set(guild, Id::new(51), false);
+            for _ in 0..2 {
+                let body = post(&state, &discord).await;
+                assert_eq!(body["content"], "👋 Claimed by <@50>\nReport from <@30>");
+            }
+
+            // Nobody is left to assign it to.
+            state.availability.set(guild, Id::new(50), false);
+            let body = post(&state, &discord).await;
+            assert_eq!(body["content"], "<@&7> Report from <@30>");
+        });
+    }
+
+    #[test]
+    fn pings_the_role_without_a_store() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let state = AppState {
+                forms: testing::forms(Id::new(MODMAIL), form()),
+                ..discord.state()
+            };
+            let body = post(&state, &discord).await;
+            assert_eq!(body["content"], "<@&7> Report from <@30>");
+            assert_eq!(body["allowed_mentions"]["roles"], json!(["7"]));
+        });
+    }
+}
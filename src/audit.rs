@@ -0,0 +1,288 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, SyncSender, TrySendError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::body::Bytes;
+use sha2::{Digest, Sha256};
+
+/// Append-only log of every verified interaction, configured with
+/// `AGHAST_AUDIT_LOG`.
+///
+/// Each line is a JSON object with the Unix time, the interaction without its
+/// token, and `prev`, the hex-encoded SHA-256 of the line before it. Editing
+/// or removing a line breaks the chain from there on. When the file grows past
+/// the size limit, it is moved to `<path>.1` and the chain continues in a new
+/// file. Older files move on to `<path>.2` and so on, and the oldest one is
+/// deleted once there are more than the limit.
+#[derive(Debug, Clone)]
+pub struct AuditLog(SyncSender<Bytes>);
+
+/// How many interactions may wait for the writer before new ones are dropped,
+/// so a slow disk never holds up a response.
+const BUFFERED: usize = 1024;
+
+impl AuditLog {
+    /// Keeps at most `max_files` rotated files next to the one at `path`,
+    /// but always at least one.
+    pub fn start(path: PathBuf, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        let writer = Writer::open(path, max_bytes, max_files.max(1))?;
+        let (tx, rx) = mpsc::sync_channel(BUFFERED);
+        std::thread::Builder::new()
+            .name("aghast-audit".to_string())
+            .spawn(move || writer.run(&rx))?;
+        Ok(Self(tx))
+    }
+
+    /// Queue the raw body of an interaction for the log.
+    pub fn record(&self, body: Bytes) {
+        match self.0.try_send(body) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("Audit log is falling behind, dropped an interaction");
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::error!("Audit log writer stopped, dropped an interaction");
+            }
+        }
+    }
+}
+
+struct Writer {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: BufWriter<File>,
+    size: u64,
+    prev: [u8; 32],
+}
+
+impl Writer {
+    /// Continue an existing log, picking the chain up from its last line.
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        let size = file.metadata()?.len();
+        let prev = last_line(&mut file, size)?.map_or([0; 32], |last| Sha256::digest(last).into());
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file: BufWriter::new(file),
+            size,
+            prev,
+        })
+    }
+
+    fn run(mut self, rx: &Receiver<Bytes>) {
+        while let Ok(body) = rx.recv() {
+            self.write(&body);
+            // Flush once the burst is written, not after every line.
+            while let Ok(body) = rx.try_recv() {
+                self.write(&body);
+            }
+            if let Err(e) = self.file.flush() {
+                tracing::error!("Failed to write audit log: {e:?}");
+            }
+        }
+    }
+
+    fn write(&mut self, body: &[u8]) {
+        let mut interaction: serde_json::Value = match serde_json::from_slice(body) {
+            Ok(interaction) => interaction,
+            Err(e) => {
+                tracing::warn!("Not logging unparseable interaction: {e:?}");
+                return;
+            }
+        };
+        // Anyone with the token can respond as the bot for 15 minutes.
+        if let Some(interaction) = interaction.as_object_mut() {
+            interaction.remove("token");
+        }
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let entry = serde_json::json!({
+            "time": time,
+            "prev": hex::encode(self.prev),
+            "interaction": interaction,
+        });
+        let line = entry.to_string();
+
+        if self.size + line.len() as u64 >= self.max_bytes {
+            if let Err(e) = self.rotate() {
+                tracing::error!("Failed to rotate audit log: {e:?}");
+            }
+        }
+        if let Err(e) = writeln!(self.file, "{line}") {
+            tracing::error!("Failed to write audit log: {e:?}");
+            return;
+        }
+        self.size += line.len() as u64 + 1;
+        self.prev = Sha256::digest(&line).into();
+    }
+
+    /// Shift `<path>.1` to `<path>.2` and so on, dropping the oldest file,
+    /// and continue in a new file after moving this one to `<path>.1`.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        match std::fs::remove_file(self.rotated(self.max_files)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        for n in (1..self.max_files).rev() {
+            match std::fs::rename(self.rotated(n), self.rotated(n + 1)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        std::fs::rename(&self.path, self.rotated(1))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.file = BufWriter::new(file);
+        self.size = 0;
+        Ok(())
+    }
+
+    /// The path of the `n`th newest rotated file.
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{n}"));
+        rotated.into()
+    }
+}
+
+/// How much of the end of the log is read at a time to find its last line.
+const CHUNK: u64 = 64 * 1024;
+
+/// The last line of a log of `size` bytes, without its newline. Only the end
+/// of the file is read, since logs can be large.
+fn last_line(file: &mut File, size: u64) -> std::io::Result<Option<Vec<u8>>> {
+    let mut tail = Vec::new();
+    let mut start = size;
+    while start > 0 {
+        let read = CHUNK.min(start);
+        start -= read;
+        let mut chunk = vec![0; usize::try_from(read).unwrap_or(usize::MAX)];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.append(&mut tail);
+        tail = chunk;
+        let lines = tail.strip_suffix(b"\n").unwrap_or(&tail);
+        if let Some(newline) = lines.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(Some(lines[newline + 1..].to_vec()));
+        }
+    }
+    let lines = tail.strip_suffix(b"\n").unwrap_or(&tail);
+    Ok((!lines.is_empty()).then(|| lines.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// A fresh log path in the temporary directory, with any files from an
+    /// earlier run removed.
+    fn log_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("aghast-audit-{}-{name}", std::process::id()));
+        remove_logs(&path);
+        path
+    }
+
+    fn remove_logs(path: &Path) {
+        let _ = std::fs::remove_file(path);
+        for n in 1..10 {
+            let _ = std::fs::remove_file(format!("{}.{n}", path.display()));
+        }
+    }
+
+    fn lines(path: impl AsRef<Path>) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn interaction(id: u64) -> String {
+        format!(r#"{{"id":"{id}","token":"secret"}}"#)
+    }
+
+    #[test]
+    fn keeps_the_newest_rotated_files() {
+        let path = log_path("rotation");
+        // Small enough that every line gets a file of its own.
+        let mut writer = Writer::open(path.clone(), 10, 2).unwrap();
+        for id in 1..=4 {
+            writer.write(interaction(id).as_bytes());
+        }
+        writer.file.flush().unwrap();
+
+        let ids = |path: &str| lines(path)[0]["interaction"]["id"].clone();
+        let display = path.display();
+        assert_eq!(ids(&display.to_string()), "4");
+        assert_eq!(ids(&format!("{display}.1")), "3");
+        assert_eq!(ids(&format!("{display}.2")), "2");
+        assert!(!Path::new(&format!("{display}.3")).exists());
+        // The chain carries on across files.
+        let newest = std::fs::read_to_string(format!("{display}.1")).unwrap();
+        assert_eq!(
+            lines(&path)[0]["prev"],
+            hex::encode(Sha256::digest(newest.trim_end()))
+        );
+        remove_logs(&path);
+    }
+
+    #[test]
+    fn reopening_continues_the_chain() {
+        let path = log_path("reopen");
+        let mut writer = Writer::open(path.clone(), u64::MAX, 1).unwrap();
+        writer.write(interaction(1).as_bytes());
+        writer.write(interaction(2).as_bytes());
+        writer.file.flush().unwrap();
+        let prev = writer.prev;
+        drop(writer);
+
+        let mut writer = Writer::open(path.clone(), u64::MAX, 1).unwrap();
+        assert_eq!(writer.prev, prev);
+        assert_eq!(writer.size, std::fs::metadata(&path).unwrap().len());
+        writer.write(interaction(3).as_bytes());
+        writer.file.flush().unwrap();
+
+        let lines = lines(&path);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2]["prev"], hex::encode(prev));
+        assert_eq!(lines[0]["prev"], hex::encode([0; 32]));
+        assert!(lines[0]["interaction"].get("token").is_none());
+        remove_logs(&path);
+    }
+
+    #[test]
+    fn finds_the_last_line_past_the_first_chunk() {
+        let path = log_path("long");
+        let long = "x".repeat(usize::try_from(CHUNK).unwrap() * 2);
+        std::fs::write(&path, format!("first\n{long}\nlast\n")).unwrap();
+        let mut file = File::open(&path).unwrap();
+        let size = file.metadata().unwrap().len();
+        assert_eq!(last_line(&mut file, size).unwrap().unwrap(), b"last");
+
+        std::fs::write(&path, format!("first\n{long}\n")).unwrap();
+        let size = file.metadata().unwrap().len();
+        assert_eq!(
+            last_line(&mut file, size).unwrap().unwrap(),
+            long.as_bytes()
+        );
+        remove_logs(&path);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{GuildMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{ExtractGuildId, ExtractMember, SlashCommand},
+    interact::InteractError,
+    store::Store,
+    AppState,
+};
+
+/// Moderators who don't want reports assigned to them right now, by server.
+/// Everyone else is available. Changes are saved in the store if there is
+/// one, otherwise they only last until a restart.
+#[derive(Debug, Default)]
+pub struct Availability(Mutex<HashMap<Id<GuildMarker>, HashSet<Id<UserMarker>>>>);
+
+impl Availability {
+    pub async fn load(store: Option<&Store>) -> Result<Self, sqlx::Error> {
+        let availability = Self::default();
+        if let Some(store) = store {
+            for (guild, user) in store.unavailable_moderators().await? {
+                availability.set(guild, user, false);
+            }
+        }
+        Ok(availability)
+    }
+
+    pub fn is_available(&self, guild: Id<GuildMarker>, user: Id<UserMarker>) -> bool {
+        !self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&guild)
+            .is_some_and(|users| users.contains(&user))
+    }
+
+    /// Returns whether the moderator's availability changed.
+    pub fn set(&self, guild: Id<GuildMarker>, user: Id<UserMarker>, available: bool) -> bool {
+        let mut unavailable = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let users = unavailable.entry(guild).or_default();
+        let changed = if available {
+            users.remove(&user)
+        } else {
+            users.insert(user)
+        };
+        drop(unavailable);
+        changed
+    }
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "available",
+    desc = "Get reports assigned to you in this server again",
+    dm_permission = false,
+    default_permissions = "permissions"
+)]
+pub struct AvailableCommand;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "unavailable",
+    desc = "Stop getting reports assigned to you in this server for now",
+    dm_permission = false,
+    default_permissions = "permissions"
+)]
+pub struct UnavailableCommand;
+
+/// Only moderators get reports assigned.
+const fn permissions() -> Permissions {
+    Permissions::MANAGE_MESSAGES
+}
+
+pub async fn available(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    SlashCommand(AvailableCommand): SlashCommand<AvailableCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let user = member.user.ok_or(InteractError::NoUser)?.id;
+    Ok(set_available(&state, guild_id, user, true).await)
+}
+
+pub async fn unavailable(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    SlashCommand(UnavailableCommand): SlashCommand<UnavailableCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let user = member.user.ok_or(InteractError::NoUser)?.id;
+    Ok(set_available(&state, guild_id, user, false).await)
+}
+
+/// Availability is changed in memory even if the store is down, like the
+/// blocklist, so the change applies right away either way.
+async fn set_available(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    user: Id<UserMarker>,
+    available: bool,
+) -> InteractionResponse {
+    let saved = match &state.store {
+        Some(store) => store.set_available(guild_id, user, available).await,
+        None => Ok(()),
+    };
+    let changed = state.availability.set(guild_id, user, available);
+
+    let mut content = match (available, changed) {
+        (true, true) => "You're available again, so reports can be assigned to you.",
+        (true, false) => "You're already available, so reports can be assigned to you.",
+        (false, true) => {
+            "You're unavailable now, so no reports are assigned to you until you use \
+             `/available`."
+        }
+        (false, false) => {
+            "You're already unavailable, so no reports are assigned to you until you use \
+             `/available`."
+        }
+    }
+    .to_string();
+    if state.store.is_none() {
+        content.push_str(
+            "\nAvailability isn't saved because `AGHAST_DB_PATH` isn't set, \
+             so this change is lost when the bot restarts.",
+        );
+    } else if let Err(e) = saved {
+        tracing::error!(
+            "Failed to save the availability of {user} in {guild_id}, the change only lasts \
+             until a restart: {e:?}"
+        );
+        content.push_str(
+            "\nSaving availability is temporarily unavailable, \
+             so this change is lost if the bot restarts before you make it again.",
+        );
+    }
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(content)
+        .build();
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing;
+
+    const GUILD: Id<GuildMarker> = Id::new(testing::GUILD);
+    const MODERATOR: Id<UserMarker> = Id::new(30);
+
+    #[test]
+    fn everyone_is_available_by_default() {
+        let availability = Availability::default();
+        assert!(availability.is_available(GUILD, MODERATOR));
+        assert!(availability.set(GUILD, MODERATOR, false));
+        assert!(!availability.is_available(GUILD, MODERATOR));
+        // Only in that server.
+        assert!(availability.is_available(Id::new(11), MODERATOR));
+        assert!(!availability.set(GUILD, MODERATOR, false));
+        assert!(availability.set(GUILD, MODERATOR, true));
+        assert!(availability.is_available(GUILD, MODERATOR));
+    }
+
+    #[test]
+    fn toggles_and_saves_availability() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let state = AppState {
+                store: Some(store.clone()),
+                ..testing::offline_state()
+            };
+            let response = niloecl::make_handler(unavailable)(
+                testing::command("unavailable", &json!([])),
+                state.clone(),
+            )
+            .await;
+            assert_eq!(
+                testing::content(&response),
+                Some(
+                    "You're unavailable now, so no reports are assigned to you until you use \
+                     `/available`."
+                )
+            );
+            assert!(!state.availability.is_available(GUILD, MODERATOR));
+            let reloaded = Availability::load(Some(&store)).await.unwrap();
+            assert!(!reloaded.is_available(GUILD, MODERATOR));
+
+            let response = niloecl::make_handler(available)(
+                testing::command("available", &json!([])),
+                state.clone(),
+            )
+            .await;
+            assert_eq!(
+                testing::content(&response),
+                Some("You're available again, so reports can be assigned to you.")
+            );
+            let reloaded = Availability::load(Some(&store)).await.unwrap();
+            assert!(reloaded.is_available(GUILD, MODERATOR));
+        });
+    }
+
+    #[test]
+    fn changes_are_kept_in_memory_while_the_store_is_down() {
+        testing::runtime().block_on(async {
+            let state = AppState {
+                store: Some(testing::broken_store().await),
+                ..testing::offline_state()
+            };
+            let response = niloecl::make_handler(unavailable)(
+                testing::command("unavailable", &json!([])),
+                state.clone(),
+            )
+            .await;
+            let content = testing::content(&response).unwrap();
+            assert!(content.contains("temporarily unavailable"), "{content}");
+            assert!(!state.availability.is_available(GUILD, MODERATOR));
+        });
+    }
+}
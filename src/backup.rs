@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::store::{self, Store};
+
+/// The format of backups this version writes, and the only one it restores.
+/// Bump it whenever a table changes, so older backups are refused instead of
+/// restored into the wrong columns.
+pub const VERSION: u32 = 1;
+
+/// Everything in the store, as written by `aghast backup` and read by
+/// `aghast restore`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    version: u32,
+    /// Unix time in seconds.
+    created_at: u64,
+    /// Hex-encoded SHA-256 of `tables` as JSON, so backups which were edited
+    /// or cut off aren't restored.
+    checksum: String,
+    tables: BTreeMap<String, Table>,
+}
+
+/// The rows of a table, with their values in the order of `columns`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<Cell>>>,
+}
+
+/// A value which isn't null. The schema only has integers and text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Cell {
+    Integer(i64),
+    Text(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("The backup isn't valid JSON, or has values the store can't hold: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("The backup is version {0}, but only version {VERSION} can be restored")]
+    Version(u32),
+    #[error("The backup doesn't match its checksum, it was changed or cut off")]
+    Checksum,
+    #[error("The backup has no {0} table")]
+    MissingTable(String),
+    #[error("The backup has a {0} table, which the store doesn't")]
+    UnknownTable(String),
+    #[error("The {0} table of the backup has other columns than the store")]
+    Columns(String),
+    #[error("Row {1} of the {0} table of the backup has the wrong number of values")]
+    Row(String, usize),
+    #[error("Store error: {0}")]
+    Store(#[from] sqlx::Error),
+}
+
+impl Backup {
+    /// Copy everything in `store`, all of it as of the same moment.
+    pub async fn create(store: &Store) -> Result<Self, sqlx::Error> {
+        let tables = store.dump().await?;
+        Ok(Self {
+            version: VERSION,
+            created_at: store::now(),
+            checksum: checksum(&tables),
+            tables,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec_pretty(self)
+    }
+
+    /// Read a backup, refusing ones of other versions or which don't match
+    /// their checksum.
+    pub fn from_json(json: &[u8]) -> Result<Self, BackupError> {
+        // Only the version is read first, since the rest of another version
+        // may not parse at all.
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        let Versioned { version } = serde_json::from_slice(json)?;
+        if version != VERSION {
+            return Err(BackupError::Version(version));
+        }
+        let backup: Self = serde_json::from_slice(json)?;
+        if backup.checksum != checksum(&backup.tables) {
+            return Err(BackupError::Checksum);
+        }
+        Ok(backup)
+    }
+
+    /// Replace everything in `store` with the backup, returning how many rows
+    /// it had. The backup has to have exactly the tables and columns of the
+    /// store, and nothing is changed unless all of it can be restored.
+    pub async fn restore(&self, store: &Store) -> Result<usize, BackupError> {
+        let schema = store.schema().await?;
+        if let Some(missing) = schema.keys().find(|name| !self.tables.contains_key(*name)) {
+            return Err(BackupError::MissingTable(missing.clone()));
+        }
+        for (name, table) in &self.tables {
+            let Some(columns) = schema.get(name) else {
+                return Err(BackupError::UnknownTable(name.clone()));
+            };
+            if &table.columns != columns {
+                return Err(BackupError::Columns(name.clone()));
+            }
+            if let Some(row) = table.rows.iter().position(|row| row.len() != columns.len()) {
+                return Err(BackupError::Row(name.clone(), row));
+            }
+        }
+        store.replace_all(&self.tables).await?;
+        Ok(self.tables.values().map(|table| table.rows.len()).sum())
+    }
+}
+
+fn checksum(tables: &BTreeMap<String, Table>) -> String {
+    // Maps are ordered and rows are lists, so the same tables always make
+    // the same JSON.
+    let json = serde_json::to_vec(tables).unwrap_or_default();
+    hex::encode(Sha256::digest(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use twilight_model::id::Id;
+
+    use super::*;
+    use crate::{interact::ModmailFormModal, testing};
+
+    /// A store with something in every table.
+    async fn filled_store() -> Store {
+        let store = testing::store().await;
+        let (guild, user, channel) = (Id::new(10), Id::new(30), Id::new(60));
+        let report = ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: None,
+            channel: None,
+            reason: "Being rude".to_string(),
+            custom: BTreeMap::new(),
+        };
+        store.record_report(user, channel, &report).await.unwrap();
+        store.block_user(guild, Id::new(u64::MAX)).await.unwrap();
+        store.next_report_number(guild).await.unwrap();
+        store
+            .add_to_outbox(guild, user, channel, &report, 0)
+            .await
+            .unwrap();
+        store
+            .set_message(guild, "form_title", "Report")
+            .await
+            .unwrap();
+        store.set_feature(guild, "tickets", true).await.unwrap();
+        store
+            .track_report(channel, Id::new(70), channel, 100)
+            .await
+            .unwrap();
+        store.set_cooldown(guild, 300).await.unwrap();
+        store.next_turn(channel).await.unwrap();
+        store
+            .assign_report(channel, Id::new(70), Id::new(50))
+            .await
+            .unwrap();
+        store
+            .set_available(guild, Id::new(50), false)
+            .await
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn restores_every_table() {
+        testing::runtime().block_on(async {
+            let backup = Backup::create(&filled_store().await).await.unwrap();
+            let tables: BTreeSet<&str> = backup.tables.keys().map(String::as_str).collect();
+            let expected = [
+                "assignments",
+                "blocked_users",
+                "cooldowns",
+                "feature_flags",
+                "message_overrides",
+                "open_reports",
+                "outbox",
+                "report_numbers",
+                "reports",
+                "rotations",
+                "unavailable_moderators",
+            ];
+            assert_eq!(tables, BTreeSet::from(expected));
+            for (name, table) in &backup.tables {
+                assert_eq!(table.rows.len(), 1, "{name}");
+            }
+
+            let json = backup.to_json().unwrap();
+            let restored = testing::store().await;
+            let rows = Backup::from_json(&json)
+                .unwrap()
+                .restore(&restored)
+                .await
+                .unwrap();
+            assert_eq!(rows, expected.len());
+            assert_eq!(restored.dump().await.unwrap(), backup.tables);
+            // Including IDs which only fit in a signed integer as their bits.
+            let blocked = restored.blocked_users().await.unwrap();
+            assert_eq!(blocked, [(Id::new(10), Id::new(u64::MAX))]);
+        });
+    }
+
+    #[test]
+    fn replaces_what_was_there() {
+        testing::runtime().block_on(async {
+            let empty = Backup::create(&testing::store().await).await.unwrap();
+            let store = filled_store().await;
+            empty.restore(&store).await.unwrap();
+            for (name, table) in store.dump().await.unwrap() {
+                assert!(table.rows.is_empty(), "{name}");
+            }
+        });
+    }
+
+    #[test]
+    fn refuses_other_versions_and_changed_backups() {
+        testing::runtime().block_on(async {
+            let backup = Backup::create(&filled_store().await).await.unwrap();
+            let mut json: serde_json::Value =
+                serde_json::from_slice(&backup.to_json().unwrap()).unwrap();
+
+            let mut newer = json.clone();
+            newer["version"] = (VERSION + 1).into();
+            newer["tables"] = serde_json::json!("a format from the future");
+            assert!(matches!(
+                Backup::from_json(newer.to_string().as_bytes()),
+                Err(BackupError::Version(version)) if version == VERSION + 1
+            ));
+
+            json["tables"]["cooldowns"]["rows"][0][1] = 10.into();
+            assert!(matches!(
+                Backup::from_json(json.to_string().as_bytes()),
+                Err(BackupError::Checksum)
+            ));
+
+            json["tables"]["cooldowns"]["rows"][0][1] = 1.5.into();
+            assert!(matches!(
+                Backup::from_json(json.to_string().as_bytes()),
+                Err(BackupError::Json(_))
+            ));
+            let json = backup.to_json().unwrap();
+            assert!(matches!(
+                Backup::from_json(&json[..json.len() / 2]),
+                Err(BackupError::Json(_))
+            ));
+        });
+    }
+
+    /// A backup of `store` with `change` applied to its tables, and a
+    /// checksum which matches again.
+    async fn changed(store: &Store, change: impl FnOnce(&mut BTreeMap<String, Table>)) -> Backup {
+        let mut backup = Backup::create(store).await.unwrap();
+        change(&mut backup.tables);
+        backup.checksum = checksum(&backup.tables);
+        backup
+    }
+
+    #[test]
+    fn refuses_backups_which_dont_fit_the_store() {
+        testing::runtime().block_on(async {
+            let store = filled_store().await;
+            let before = store.dump().await.unwrap();
+
+            let backup = changed(&store, |tables| {
+                tables.remove("rotations");
+            })
+            .await;
+            assert!(matches!(
+                backup.restore(&store).await,
+                Err(BackupError::MissingTable(table)) if table == "rotations"
+            ));
+
+            let backup = changed(&store, |tables| {
+                let table = tables["cooldowns"].clone();
+                tables.insert("tickets".to_string(), table);
+            })
+            .await;
+            assert!(matches!(
+                backup.restore(&store).await,
+                Err(BackupError::UnknownTable(table)) if table == "tickets"
+            ));
+
+            let backup = changed(&store, |tables| {
+                let cooldowns = tables.get_mut("cooldowns").unwrap();
+                cooldowns.columns[1] = "minutes".to_string();
+            })
+            .await;
+            assert!(matches!(
+                backup.restore(&store).await,
+                Err(BackupError::Columns(table)) if table == "cooldowns"
+            ));
+
+            let backup = changed(&store, |tables| {
+                tables.get_mut("cooldowns").unwrap().rows[0].pop();
+            })
+            .await;
+            assert!(matches!(
+                backup.restore(&store).await,
+                Err(BackupError::Row(table, 0)) if table == "cooldowns"
+            ));
+
+            assert_eq!(store.dump().await.unwrap(), before);
+        });
+    }
+
+    #[test]
+    fn changes_nothing_unless_everything_is_restored() {
+        testing::runtime().block_on(async {
+            let store = filled_store().await;
+            let before = store.dump().await.unwrap();
+            // Every table fits, but the last one breaks a constraint.
+            let backup = changed(&store, |tables| {
+                let table = tables.get_mut("unavailable_moderators").unwrap();
+                table.rows[0][1] = None;
+            })
+            .await;
+            assert!(matches!(
+                backup.restore(&store).await,
+                Err(BackupError::Store(_))
+            ));
+            assert_eq!(store.dump().await.unwrap(), before);
+        });
+    }
+}
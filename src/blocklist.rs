@@ -0,0 +1,428 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::{AllowedMentions, Component, MessageFlags},
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{GuildMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{CidArgs, CustomIdTooLong, ExtractGuildId, SlashCommand},
+    interact::InteractError,
+    paginate::Page,
+    store::Store,
+    AppState,
+};
+
+/// Users who may not send reports, by server. Changes are saved in the store
+/// if there is one, otherwise they only last until a restart.
+#[derive(Debug, Default)]
+pub struct Blocklist(Mutex<HashMap<Id<GuildMarker>, BTreeSet<Id<UserMarker>>>>);
+
+impl Blocklist {
+    pub async fn load(store: Option<&Store>) -> Result<Self, sqlx::Error> {
+        let blocklist = Self::default();
+        if let Some(store) = store {
+            for (guild, user) in store.blocked_users().await? {
+                blocklist.add(guild, user);
+            }
+        }
+        Ok(blocklist)
+    }
+
+    pub fn is_blocked(&self, guild: Id<GuildMarker>, user: Id<UserMarker>) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&guild)
+            .is_some_and(|users| users.contains(&user))
+    }
+
+    /// Returns whether the user wasn't blocked already.
+    fn add(&self, guild: Id<GuildMarker>, user: Id<UserMarker>) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .entry(guild)
+            .or_default()
+            .insert(user)
+    }
+
+    /// Returns whether the user was blocked.
+    fn remove(&self, guild: Id<GuildMarker>, user: Id<UserMarker>) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get_mut(&guild)
+            .is_some_and(|users| users.remove(&user))
+    }
+
+    fn list(&self, guild: Id<GuildMarker>) -> Vec<Id<UserMarker>> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&guild)
+            .map(|users| users.iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "blocklist",
+    desc = "Manage who can't send reports in this server",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub enum BlocklistCommand {
+    #[command(name = "add")]
+    Add(BlocklistAdd),
+    #[command(name = "remove")]
+    Remove(BlocklistRemove),
+    #[command(name = "list")]
+    List(BlocklistList),
+}
+
+impl BlocklistCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "add", desc = "Stop a user from sending reports")]
+pub struct BlocklistAdd {
+    /// The user to block
+    user: Id<UserMarker>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "remove", desc = "Let a blocked user send reports again")]
+pub struct BlocklistRemove {
+    /// The user to unblock
+    user: Id<UserMarker>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "list", desc = "List the users who can't send reports")]
+pub struct BlocklistList;
+
+/// How many users a page of `/blocklist list` shows, to stay within the
+/// message limit.
+const LISTED_PER_PAGE: usize = 50;
+
+/// Custom ID name of the Previous and Next buttons of `/blocklist list`.
+pub const BLOCKLIST_PAGE: &str = "blocklist_page";
+
+pub async fn blocklist(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    SlashCommand(cmd): SlashCommand<BlocklistCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let mut saved = true;
+    let mut components = Vec::new();
+    let mut content = match cmd {
+        BlocklistCommand::Add(BlocklistAdd { user }) => {
+            if let Some(store) = &state.store {
+                saved = was_saved(guild_id, store.block_user(guild_id, user).await);
+            }
+            if state.blocklist.add(guild_id, user) {
+                format!("Blocked <@{user}> from sending reports.")
+            } else {
+                format!("<@{user}> is blocked already.")
+            }
+        }
+        BlocklistCommand::Remove(BlocklistRemove { user }) => {
+            if let Some(store) = &state.store {
+                saved = was_saved(guild_id, store.unblock_user(guild_id, user).await);
+            }
+            if state.blocklist.remove(guild_id, user) {
+                format!("<@{user}> can send reports again.")
+            } else {
+                format!("<@{user}> isn't blocked.")
+            }
+        }
+        BlocklistCommand::List(BlocklistList) => {
+            let (content, buttons) = list(&state, guild_id, 0)?;
+            components = buttons;
+            content
+        }
+    };
+    if state.store.is_none() {
+        content.push_str(
+            "\nThe blocklist isn't saved because `AGHAST_DB_PATH` isn't set, \
+             so it's lost when the bot restarts.",
+        );
+    } else if !saved {
+        content.push_str(
+            "\nSaving the blocklist is temporarily unavailable, \
+             so this change is lost if the bot restarts before you make it again.",
+        );
+    }
+
+    // Listing users shouldn't ping them.
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .allowed_mentions(AllowedMentions::default())
+        .content(content)
+        .components(components)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+/// Show another page of `/blocklist list`.
+pub async fn blocklist_page(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    CidArgs((index,)): CidArgs<(usize,)>,
+) -> Result<InteractionResponse, InteractError> {
+    let (content, components) = list(&state, guild_id, index)?;
+    let data = InteractionResponseDataBuilder::new()
+        .allowed_mentions(AllowedMentions::default())
+        .content(content)
+        .components(components)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(data),
+    })
+}
+
+/// Changes are made in memory even if the store is down, so the blocklist keeps
+/// working until the next restart.
+fn was_saved(guild_id: Id<GuildMarker>, result: Result<(), sqlx::Error>) -> bool {
+    if let Err(e) = result {
+        tracing::error!(
+            "Failed to save the blocklist of {guild_id}, the change only lasts until a restart: \
+             {e:?}"
+        );
+        return false;
+    }
+    true
+}
+
+/// Page `index` of the blocked users, with buttons for the other pages if
+/// there are any.
+fn list(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    index: usize,
+) -> Result<(String, Vec<Component>), CustomIdTooLong> {
+    let users = state.blocklist.list(guild_id);
+    if users.is_empty() {
+        return Ok((
+            "Nobody is blocked from sending reports.".to_string(),
+            Vec::new(),
+        ));
+    }
+    let page = Page::new(&users, LISTED_PER_PAGE, index);
+    let lines: Vec<_> = page.items.iter().map(|user| format!("<@{user}>")).collect();
+    let content = format!("Blocked from sending reports:\n{}", lines.join("\n"));
+    if page.count == 1 {
+        return Ok((content, Vec::new()));
+    }
+    Ok((
+        format!("{content}\n{}", page.footer()),
+        vec![page.buttons(BLOCKLIST_PAGE)?],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::{
+        interact::handle_interaction,
+        testing::{self, MockDiscord},
+    };
+
+    const USER: u64 = 40;
+
+    fn blocklist_command(subcommand: &str) -> Interaction {
+        testing::command(
+            "blocklist",
+            &json!([{
+                "name": subcommand,
+                "type": 1,
+                "options": [{ "name": "user", "type": 6, "value": USER.to_string() }],
+            }]),
+        )
+    }
+
+    fn guild() -> Id<GuildMarker> {
+        Id::new(testing::GUILD)
+    }
+
+    /// Reports from [`USER`], who is blocked.
+    fn blocked_reports() -> [Interaction; 3] {
+        let report = testing::interaction(json!({
+            "type": 2,
+            "data": {
+                "id": "50",
+                "name": "report",
+                "type": 1,
+                "options": [
+                    { "name": "reason", "type": 3, "value": "Being rude" },
+                    { "name": "user", "type": 6, "value": "31" },
+                    { "name": "channel", "type": 7, "value": "20" },
+                ],
+            },
+        }));
+        let open = testing::button("open_form:60");
+        let submit = testing::modal(
+            "form_submit:60",
+            &[
+                ("user", "wumpus"),
+                ("channel", "#general"),
+                ("message_link", ""),
+                ("reason", "Being rude"),
+            ],
+        );
+        [report, open, submit].map(|mut interaction| {
+            interaction.member = serde_json::from_value(testing::member(USER, &[])).unwrap();
+            interaction
+        })
+    }
+
+    #[test]
+    fn blocked_users_cant_report_in_any_way() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(60, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = AppState {
+                report_channels: Arc::new(HashMap::from([(guild(), Id::new(60))])),
+                ..discord.state()
+            };
+            state.blocklist.add(guild(), Id::new(USER));
+            for interaction in blocked_reports() {
+                let response = Box::pin(handle_interaction(state.clone(), interaction)).await;
+                assert_eq!(
+                    testing::error_message(&response),
+                    Some("You can't send reports in this server.")
+                );
+            }
+            assert!(discord.requests().is_empty());
+
+            // Only in the server they're blocked in.
+            state.blocklist.remove(guild(), Id::new(USER));
+            state.blocklist.add(Id::new(11), Id::new(USER));
+            for interaction in blocked_reports() {
+                let response = Box::pin(handle_interaction(state.clone(), interaction)).await;
+                assert_eq!(testing::error_message(&response), None);
+            }
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages")
+                    .len(),
+                2
+            );
+        });
+    }
+
+    #[test]
+    fn changes_are_saved_in_the_store() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let state = AppState {
+                store: Some(store.clone()),
+                ..testing::offline_state()
+            };
+            let handler = niloecl::make_handler(blocklist);
+
+            let response = handler(blocklist_command("add"), state.clone()).await;
+            assert_eq!(
+                testing::content(&response),
+                Some("Blocked <@40> from sending reports.")
+            );
+            let reloaded = Blocklist::load(Some(&store)).await.unwrap();
+            assert!(reloaded.is_blocked(guild(), Id::new(USER)));
+            assert!(!reloaded.is_blocked(Id::new(11), Id::new(USER)));
+
+            handler(blocklist_command("remove"), state).await;
+            let reloaded = Blocklist::load(Some(&store)).await.unwrap();
+            assert!(!reloaded.is_blocked(guild(), Id::new(USER)));
+        });
+    }
+
+    #[test]
+    fn failing_to_load_is_an_error() {
+        testing::runtime().block_on(async {
+            let store = testing::broken_store().await;
+            assert!(Blocklist::load(Some(&store)).await.is_err());
+        });
+    }
+
+    #[test]
+    fn changes_are_kept_in_memory_while_the_store_is_down() {
+        testing::runtime().block_on(async {
+            let state = AppState {
+                store: Some(testing::broken_store().await),
+                ..testing::offline_state()
+            };
+            let handler = niloecl::make_handler(blocklist);
+
+            let response = handler(blocklist_command("add"), state.clone()).await;
+            let content = testing::content(&response).unwrap();
+            assert!(content.starts_with("Blocked <@40>"), "{content}");
+            assert!(content.contains("temporarily unavailable"), "{content}");
+            assert!(state.blocklist.is_blocked(guild(), Id::new(USER)));
+
+            let response = handler(blocklist_command("remove"), state.clone()).await;
+            let content = testing::content(&response).unwrap();
+            assert!(
+                content.starts_with("<@40> can send reports again."),
+                "{content}"
+            );
+            assert!(content.contains("temporarily unavailable"), "{content}");
+            assert!(!state.blocklist.is_blocked(guild(), Id::new(USER)));
+        });
+    }
+
+    #[test]
+    fn lists_long_blocklists_in_pages() {
+        let state = testing::offline_state();
+        for user in 1..=120 {
+            state.blocklist.add(guild(), Id::new(user));
+        }
+        let response = testing::runtime().block_on(niloecl::make_handler(blocklist)(
+            testing::command("blocklist", &json!([{ "name": "list", "type": 1 }])),
+            state.clone(),
+        ));
+        let content = testing::content(&response).unwrap();
+        assert!(content.contains("<@1>\n"), "{content}");
+        assert!(content.contains("<@50>\n"), "{content}");
+        assert!(!content.contains("<@51>"), "{content}");
+        assert!(content.contains("Page 1 of 3"), "{content}");
+
+        let response = testing::runtime().block_on(niloecl::make_handler(blocklist_page)(
+            testing::button("blocklist_page:2"),
+            state,
+        ));
+        assert_eq!(response.kind, InteractionResponseType::UpdateMessage);
+        let content = testing::content(&response).unwrap();
+        assert!(!content.contains("<@100>"), "{content}");
+        assert!(content.contains("<@101>\n"), "{content}");
+        assert!(content.contains("<@120>\nPage 3 of 3"), "{content}");
+    }
+}
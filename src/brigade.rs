@@ -0,0 +1,161 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use twilight_model::id::{
+    marker::{ChannelMarker, UserMarker},
+    Id,
+};
+
+use crate::config::BrigadeConfig;
+
+/// Recent reporters of each reported user, per modmail channel, for setups
+/// with `form.brigade` configured.
+#[derive(Debug, Default)]
+pub struct Brigades(Mutex<HashMap<(Id<ChannelMarker>, String), Reports>>);
+
+#[derive(Debug, Default)]
+struct Reports {
+    /// The window of the form the reports were sent with, since other forms
+    /// may have a different one.
+    window: Duration,
+    reports: Vec<(Id<UserMarker>, Instant)>,
+}
+
+pub enum Verdict {
+    /// Nothing unusual, post the report.
+    Post,
+    /// This report reached the threshold. Post an alert instead, naming
+    /// everyone who reported the user within the window.
+    Alert(Vec<Id<UserMarker>>),
+    /// An alert was already posted for this user within the window, so
+    /// the report is posted marked as held, without pinging anyone.
+    Hold,
+}
+
+impl Brigades {
+    pub fn check(
+        &self,
+        config: &BrigadeConfig,
+        channel: Id<ChannelMarker>,
+        target: &str,
+        reporter: Id<UserMarker>,
+    ) -> Verdict {
+        let window = Duration::from_secs(config.window_secs);
+        // Users are typed in by hand, so "Wumpus " and "wumpus" are the same.
+        let target = target.trim().to_lowercase();
+        if target.is_empty() {
+            return Verdict::Post;
+        }
+
+        let mut brigades = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // Each entry is pruned by the window of the form it was checked
+        // with, as other forms may have a different one.
+        brigades.retain(|_, reports| {
+            reports
+                .reports
+                .retain(|(_, at)| at.elapsed() < reports.window);
+            !reports.reports.is_empty()
+        });
+        let reports = brigades.entry((channel, target)).or_default();
+        reports.window = window;
+        // The same person reporting again doesn't make it more of a brigade.
+        let is_new = !reports.reports.iter().any(|(user, _)| *user == reporter);
+        if is_new {
+            reports.reports.push((reporter, Instant::now()));
+        }
+        let reporters = distinct(&reports.reports);
+        drop(brigades);
+
+        match reporters.len().cmp(&config.reporters) {
+            Ordering::Less => Verdict::Post,
+            Ordering::Equal if is_new => Verdict::Alert(reporters),
+            Ordering::Equal | Ordering::Greater => Verdict::Hold,
+        }
+    }
+}
+
+fn distinct(reports: &[(Id<UserMarker>, Instant)]) -> Vec<Id<UserMarker>> {
+    let mut reporters: Vec<_> = reports.iter().map(|(user, _)| *user).collect();
+    reporters.sort_unstable();
+    reporters.dedup();
+    reporters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: Id<ChannelMarker> = Id::new(60);
+
+    fn config(reporters: usize, window_secs: u64) -> BrigadeConfig {
+        BrigadeConfig {
+            reporters,
+            window_secs,
+        }
+    }
+
+    #[test]
+    fn posts_alerts_and_holds() {
+        let brigades = Brigades::default();
+        let config = config(2, 3600);
+        let check = |reporter| brigades.check(&config, CHANNEL, "Wumpus ", Id::new(reporter));
+        assert!(matches!(check(1), Verdict::Post));
+        // Reporting again doesn't count twice.
+        assert!(matches!(check(1), Verdict::Post));
+        assert!(
+            matches!(check(2), Verdict::Alert(reporters) if reporters == [Id::new(1), Id::new(2)])
+        );
+        assert!(matches!(check(3), Verdict::Hold));
+        assert!(matches!(check(1), Verdict::Hold));
+    }
+
+    #[test]
+    fn targets_are_counted_apart() {
+        let brigades = Brigades::default();
+        let config = config(2, 3600);
+        brigades.check(&config, CHANNEL, "wumpus", Id::new(1));
+        assert!(matches!(
+            brigades.check(&config, CHANNEL, "clyde", Id::new(2)),
+            Verdict::Post
+        ));
+        assert!(matches!(
+            brigades.check(&config, Id::new(61), "wumpus", Id::new(2)),
+            Verdict::Post
+        ));
+        assert!(matches!(
+            brigades.check(&config, CHANNEL, "WUMPUS", Id::new(2)),
+            Verdict::Alert(_)
+        ));
+    }
+
+    #[test]
+    fn reports_expire_after_the_window() {
+        let brigades = Brigades::default();
+        let config = config(2, 0);
+        brigades.check(&config, CHANNEL, "wumpus", Id::new(1));
+        assert!(matches!(
+            brigades.check(&config, CHANNEL, "wumpus", Id::new(2)),
+            Verdict::Post
+        ));
+    }
+
+    #[test]
+    fn other_windows_do_not_prune_reports() {
+        let brigades = Brigades::default();
+        let long = config(2, 3600);
+        brigades.check(&long, CHANNEL, "wumpus", Id::new(1));
+        // A form with a window that has passed already, in another channel.
+        brigades.check(&config(2, 0), Id::new(61), "clyde", Id::new(1));
+        assert!(matches!(
+            brigades.check(&long, CHANNEL, "wumpus", Id::new(2)),
+            Verdict::Alert(_)
+        ));
+    }
+}
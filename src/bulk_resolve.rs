@@ -0,0 +1,832 @@
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::{
+        message::{
+            component::{ActionRow, Button, ButtonStyle},
+            Component, Embed, MessageFlags,
+        },
+        Channel, ChannelType, Message,
+    },
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::{embed::EmbedBuilder, InteractionResponseDataBuilder};
+
+use crate::{
+    config::ReportField,
+    extract::{
+        custom_id, CidNamed, CustomIdTooLong, ExtractChannel, ExtractGuildId, ExtractMember,
+        FromCidNamed, FromCidNamedError, InteractionToken, NamedArgs, SlashCommand,
+    },
+    interact::{fetch_channel, InteractError},
+    progress::{deferred_update, Progress},
+    resolve::{can_resolve, mark_resolved, open_report},
+    AppState,
+};
+
+/// Custom ID name of the button confirming `/bulk-resolve`.
+pub const BULK_RESOLVE: &str = "bulk_resolve";
+
+/// Discord returns at most this many messages at once.
+const PAGE_SIZE: u16 = 100;
+
+/// How many of the latest messages in a channel are looked at. Every page of
+/// [`PAGE_SIZE`] messages is a request, so older reports are left alone
+/// rather than reading through a channel's whole history.
+const MAX_SCANNED_MESSAGES: usize = 1000;
+
+/// How many posts in a forum are looked at. Reading the report a post
+/// starts with is a request for each one.
+const MAX_SCANNED_POSTS: usize = 200;
+
+/// How often resolving reports updates its progress.
+const PROGRESS_EVERY: usize = 10;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "bulk-resolve",
+    desc = "Resolve every open report in this channel matching a filter",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct BulkResolveCommand {
+    /// Only reports about this user
+    target: Option<Id<UserMarker>>,
+    /// Only reports older than this many days
+    #[command(min_value = 0, max_value = 3650)]
+    older_than_days: Option<i64>,
+}
+
+impl BulkResolveCommand {
+    const fn permissions() -> Permissions {
+        Permissions::MANAGE_MESSAGES
+    }
+}
+
+/// Which reports `/bulk-resolve` resolves. Carried to the confirm button as
+/// named custom ID arguments, of which only the target may be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkFilter {
+    target: Option<Id<UserMarker>>,
+    /// Unix time in seconds. Reports posted at or after it are left alone,
+    /// so reports which came in after the moderator saw the count are too.
+    before: u64,
+}
+
+impl FromCidNamed for BulkFilter {
+    fn from_named(args: &NamedArgs<'_>) -> Result<Self, FromCidNamedError> {
+        Ok(Self {
+            target: args.optional("target")?,
+            before: args.required("before")?,
+        })
+    }
+}
+
+impl BulkFilter {
+    fn custom_id(&self) -> Result<String, CustomIdTooLong> {
+        let target = self.target.map(|target| format!("target={target}"));
+        let before = format!("before={}", self.before);
+        let args: Vec<&dyn Display> = target
+            .iter()
+            .chain([&before])
+            .map(|arg| arg as &dyn Display)
+            .collect();
+        custom_id(BULK_RESOLVE, &args)
+    }
+
+    /// The reporter of `message`, if it's an open report matching the
+    /// filter.
+    fn matches(&self, message: &Message) -> Option<Id<UserMarker>> {
+        let reporter = open_report(message)?;
+        if message.timestamp.as_secs() >= self.before.cast_signed() {
+            return None;
+        }
+        if let Some(target) = self.target {
+            // Reporters type the user themselves, so look for their mention
+            // or ID in the "User" field. Forms with a `description_template`
+            // have no fields, so look for the mention in the description
+            // instead, leaving out the reporter's own mention.
+            let embed = message.embeds.first()?;
+            let user = embed
+                .fields
+                .iter()
+                .find(|field| field.name == ReportField::User.name());
+            let found = user.map_or_else(
+                || {
+                    embed.description.as_deref().is_some_and(|description| {
+                        mentions(&description.replace(&format!("<@{reporter}>"), ""), target)
+                    })
+                },
+                |user| mentions(&user.value, target),
+            );
+            if !found {
+                return None;
+            }
+        }
+        Some(reporter)
+    }
+}
+
+/// Like "1 report" or "3 reports".
+fn reports(count: usize) -> String {
+    if count == 1 {
+        "1 report".to_string()
+    } else {
+        format!("{count} reports")
+    }
+}
+
+fn mentions(text: &str, user: Id<UserMarker>) -> bool {
+    text.trim() == user.to_string()
+        || text.contains(&format!("<@{user}>"))
+        || text.contains(&format!("<@!{user}>"))
+}
+
+/// Ask before resolving the reports matching the filter, showing how many
+/// there are.
+pub async fn bulk_resolve(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    ExtractChannel(channel): ExtractChannel,
+    SlashCommand(cmd): SlashCommand<BulkResolveCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    if !can_resolve(&state, &member) {
+        return Err(InteractError::CannotResolve);
+    }
+    if cmd.target.is_none() && cmd.older_than_days.is_none() {
+        return Err(InteractError::NoBulkFilter);
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = cmd.older_than_days.unwrap_or_default().unsigned_abs();
+    let before = now.saturating_sub(days * SECS_PER_DAY);
+    let filter = BulkFilter {
+        target: cmd.target,
+        before,
+    };
+    let count = matching_reports(&state, guild_id, &channel, &filter)
+        .await?
+        .len();
+
+    let mut data = InteractionResponseDataBuilder::new().flags(MessageFlags::EPHEMERAL);
+    if count == 0 {
+        data = data.content("No open reports in this channel match the filter.");
+    } else {
+        let button = Component::Button(Button {
+            custom_id: Some(filter.custom_id()?),
+            disabled: false,
+            emoji: None,
+            label: Some(format!("Resolve {}", reports(count))),
+            style: ButtonStyle::Danger,
+            url: None,
+            sku_id: None,
+        });
+        data = data
+            .content(format!(
+                "Found {} in this channel matching the filter. Resolve all of them?",
+                reports(count)
+            ))
+            .components([Component::ActionRow(ActionRow {
+                components: vec![button],
+            })]);
+    }
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data.build()),
+    })
+}
+
+/// Resolve the reports confirmed with the button from [`bulk_resolve`].
+/// Reports are looked up again, so ones resolved in the meantime are
+/// skipped. That can take many requests, so this always answers right away
+/// and shows its progress in place of the confirmation.
+pub async fn confirm_bulk_resolve(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    ExtractChannel(channel): ExtractChannel,
+    InteractionToken(token): InteractionToken,
+    CidNamed(filter): CidNamed<BulkFilter>,
+) -> Result<InteractionResponse, InteractError> {
+    let moderator = member.user.as_ref().ok_or(InteractError::NoUser)?.id;
+    if !can_resolve(&state, &member) {
+        return Err(InteractError::CannotResolve);
+    }
+    tokio::spawn(async move {
+        let progress = Progress::for_component(&state, token);
+        let result = Box::pin(run(
+            &state, &progress, guild_id, moderator, &channel, &filter,
+        ))
+        .await;
+        progress.finish(result).await;
+    });
+    Ok(deferred_update())
+}
+
+async fn run(
+    state: &AppState,
+    progress: &Progress,
+    guild_id: Id<GuildMarker>,
+    moderator: Id<UserMarker>,
+    channel: &Channel,
+    filter: &BulkFilter,
+) -> Result<Embed, InteractError> {
+    progress.update("Looking for reports...").await;
+    let found = matching_reports(state, guild_id, channel, filter).await?;
+    let mut resolved = 0;
+    for (done, report) in found.iter().enumerate() {
+        if done % PROGRESS_EVERY == 0 {
+            let status = format!("Resolving {} of {}...", done + 1, reports(found.len()));
+            progress.update(&status).await;
+        }
+        let channel = report.post.as_ref().unwrap_or(channel);
+        let result = mark_resolved(
+            state,
+            moderator,
+            channel,
+            &report.message,
+            report.reporter,
+            None,
+        )
+        .await;
+        match result {
+            Ok(()) => resolved += 1,
+            Err(e) => tracing::warn!("Failed to resolve report {}: {e:?}", report.message.id),
+        }
+    }
+    Ok(EmbedBuilder::new()
+        .description(format!("Resolved {}.", reports(resolved)))
+        .build())
+}
+
+/// An open report found by [`matching_reports`].
+struct FoundReport {
+    /// The forum post the report started, for reports in forums.
+    post: Option<Channel>,
+    message: Message,
+    reporter: Id<UserMarker>,
+}
+
+/// The bot's open reports in `channel` matching `filter`, with who sent them.
+/// In forums, and posts in them, that's the reports starting each post.
+async fn matching_reports(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    channel: &Channel,
+    filter: &BulkFilter,
+) -> Result<Vec<FoundReport>, InteractError> {
+    if let Some(forum) = forum(state, channel).await {
+        return forum_reports(state, guild_id, forum, filter).await;
+    }
+    let mut found = Vec::new();
+    let mut scanned = 0;
+    let mut before = None;
+    while scanned < MAX_SCANNED_MESSAGES {
+        let request = state.client.channel_messages(channel.id);
+        let response = match before {
+            Some(before) => request.before(before).limit(PAGE_SIZE).await?,
+            None => request.limit(PAGE_SIZE).await?,
+        };
+        let messages = response.models().await?;
+        let last_page = messages.len() < usize::from(PAGE_SIZE);
+        scanned += messages.len();
+        before = messages.last().map(|message| message.id);
+        found.extend(
+            messages
+                .into_iter()
+                .filter_map(|message| report(state, None, message, filter)),
+        );
+        if last_page {
+            break;
+        }
+    }
+    Ok(found)
+}
+
+/// The open reports starting posts in `forum`. Posts are archived after a
+/// while without messages, so archived ones are looked at too.
+async fn forum_reports(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    forum: Id<ChannelMarker>,
+    filter: &BulkFilter,
+) -> Result<Vec<FoundReport>, InteractError> {
+    let mut posts: Vec<Channel> = state
+        .client
+        .active_threads(guild_id)
+        .await?
+        .model()
+        .await?
+        .threads
+        .into_iter()
+        .filter(|thread| thread.parent_id == Some(forum))
+        .collect();
+    let mut before: Option<String> = None;
+    while posts.len() < MAX_SCANNED_POSTS {
+        let request = state.client.public_archived_threads(forum);
+        let request = match &before {
+            Some(before) => request.before(before),
+            None => request,
+        };
+        let listing = request.await?.model().await?;
+        before = listing
+            .threads
+            .last()
+            .and_then(|thread| thread.thread_metadata.as_ref())
+            .map(|metadata| metadata.archive_timestamp.iso_8601().to_string());
+        posts.extend(listing.threads);
+        if !listing.has_more.unwrap_or_default() || before.is_none() {
+            break;
+        }
+    }
+    posts.truncate(MAX_SCANNED_POSTS);
+
+    let mut found = Vec::new();
+    for post in posts {
+        // The first message of a post has the ID of the post.
+        let message = match state.client.message(post.id, post.id.cast()).await {
+            Ok(response) => response.model().await?,
+            // Posts whose first message was deleted have no report left.
+            Err(e) => {
+                tracing::debug!("Skipping forum post {} without a report: {e:?}", post.id);
+                continue;
+            }
+        };
+        found.extend(report(state, Some(post), message, filter));
+    }
+    Ok(found)
+}
+
+/// `message` as a found report, if the bot posted it and it matches `filter`.
+fn report(
+    state: &AppState,
+    post: Option<Channel>,
+    message: Message,
+    filter: &BulkFilter,
+) -> Option<FoundReport> {
+    if message.author.id != state.bot_id {
+        return None;
+    }
+    let reporter = filter.matches(&message)?;
+    Some(FoundReport {
+        post,
+        message,
+        reporter,
+    })
+}
+
+/// The forum reports are posted in, if `channel` is one or a post in one.
+async fn forum(state: &AppState, channel: &Channel) -> Option<Id<ChannelMarker>> {
+    let is_forum = |kind| matches!(kind, ChannelType::GuildForum | ChannelType::GuildMedia);
+    if is_forum(channel.kind) {
+        return Some(channel.id);
+    }
+    let parent = channel.parent_id.filter(|_| channel.kind.is_thread())?;
+    fetch_channel(state, parent)
+        .await
+        .filter(|parent| is_forum(parent.kind))
+        .map(|parent| parent.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::testing::{self, MockDiscord};
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    /// A report about `user` posted `age` seconds ago, by someone other than
+    /// the bot if `author` isn't 2.
+    fn report(id: u64, author: u64, user: &str, age: i64, resolved: bool) -> Value {
+        let posted =
+            twilight_model::util::Timestamp::from_secs(i64::try_from(now()).unwrap() - age)
+                .unwrap();
+        let mut message = testing::message(testing::CHANNEL, id);
+        message["author"] = testing::user_json(author);
+        message["timestamp"] = json!(posted.iso_8601().to_string());
+        message["embeds"] = json!([{
+            "type": "rich",
+            "fields": [{ "name": "User", "value": user, "inline": false }],
+        }]);
+        message["components"] = json!([{
+            "type": 1,
+            "components": [{
+                "type": 2,
+                "style": 3,
+                "custom_id": "resolve_report:40",
+                "label": "Resolve",
+                "disabled": resolved,
+            }],
+        }]);
+        message
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    /// Discord with a channel of reports: about <@50> from two days ago,
+    /// about <@60> from just now, about <@50> but resolved already, and a
+    /// message by someone else which looks like a report.
+    async fn discord() -> MockDiscord {
+        MockDiscord::start(|request| match (&request.method, request.path.as_str()) {
+            (&Method::GET, "/channels/20/messages") => (
+                StatusCode::OK,
+                json!([
+                    report(71, 2, "<@50>", 2 * DAY, false),
+                    report(72, 2, "60", 0, false),
+                    report(73, 2, "<@50>", 0, true),
+                    report(74, 30, "<@50>", 0, false),
+                ]),
+            ),
+            (&Method::PATCH, path) => edited(path),
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await
+    }
+
+    /// Discord's answer to editing the message at `path`, either a report or
+    /// the interaction's response.
+    fn edited(path: &str) -> (StatusCode, Value) {
+        let id = path.rsplit('/').next().unwrap().parse().unwrap_or(90);
+        (StatusCode::OK, testing::message(testing::CHANNEL, id))
+    }
+
+    fn text_channel() -> Channel {
+        serde_json::from_value(testing::channel(
+            testing::CHANNEL,
+            Some(Id::new(testing::GUILD)),
+            0,
+        ))
+        .unwrap()
+    }
+
+    /// Resolve the reports in `channel` matching `filter` like the confirm
+    /// button would, and return what the confirmation ended up saying.
+    async fn confirm(discord: &MockDiscord, channel: &Channel, filter: BulkFilter) -> Value {
+        let state = discord.state();
+        let progress = Progress::for_component(&state, "token".to_string());
+        let result = run(
+            &state,
+            &progress,
+            Id::new(testing::GUILD),
+            Id::new(30),
+            channel,
+            &filter,
+        )
+        .await;
+        progress.finish(result).await;
+        let updates = discord.requests_to(&Method::PATCH, "/webhooks/1/token/messages/@original");
+        let last = updates.last().unwrap();
+        assert_eq!(last.body["components"], json!([]));
+        last.body["embeds"][0]["description"].clone()
+    }
+
+    const fn filter(target: Option<u64>, before: u64) -> BulkFilter {
+        BulkFilter {
+            target: match target {
+                Some(target) => Some(Id::new(target)),
+                None => None,
+            },
+            before,
+        }
+    }
+
+    fn command(options: &Value) -> Interaction {
+        testing::command("bulk-resolve", options)
+    }
+
+    fn resolved(discord: &MockDiscord) -> Vec<String> {
+        discord
+            .requests_to(&Method::PATCH, "/channels/20/messages/")
+            .into_iter()
+            .map(|request| request.path)
+            .collect()
+    }
+
+    #[test]
+    fn counts_the_matching_reports_before_resolving() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let interaction = command(&json!([{ "name": "target", "type": 6, "value": "50" }]));
+            let response = Box::pin(niloecl::make_handler(bulk_resolve)(
+                interaction,
+                discord.state(),
+            ))
+            .await;
+            assert_eq!(
+                testing::content(&response),
+                Some("Found 1 report in this channel matching the filter. Resolve all of them?")
+            );
+            let Some(Component::ActionRow(row)) = response.data.unwrap().components.unwrap().pop()
+            else {
+                panic!("no confirm button");
+            };
+            let Component::Button(button) = &row.components[0] else {
+                panic!("no confirm button");
+            };
+            let custom_id = button.custom_id.as_deref().unwrap();
+            let before = custom_id
+                .strip_prefix("bulk_resolve:target=50:before=")
+                .unwrap();
+            // Reports which come in after the count aren't resolved.
+            assert!(before.parse::<u64>().unwrap().abs_diff(now()) <= 1);
+            assert!(resolved(&discord).is_empty());
+        });
+    }
+
+    #[test]
+    fn resolves_the_matching_reports_once_confirmed() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let before = now() - 1;
+            let resolved_text = confirm(&discord, &text_channel(), filter(None, before)).await;
+            assert_eq!(resolved_text, "Resolved 1 report.");
+            assert_eq!(resolved(&discord), ["/channels/20/messages/71"]);
+        });
+    }
+
+    #[test]
+    fn combines_filters() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            // Far enough ahead that reports posted while the test runs are
+            // still before it.
+            let before = now() + 60;
+            let resolved_text = confirm(&discord, &text_channel(), filter(Some(60), before)).await;
+            assert_eq!(resolved_text, "Resolved 1 report.");
+            assert_eq!(resolved(&discord), ["/channels/20/messages/72"]);
+        });
+    }
+
+    #[test]
+    fn answers_right_away_and_shows_progress() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let response = Box::pin(niloecl::make_handler(confirm_bulk_resolve)(
+                testing::button(&format!("bulk_resolve:target=50:before={}", now() + 60)),
+                discord.state(),
+            ))
+            .await;
+            assert_eq!(
+                response.kind,
+                InteractionResponseType::DeferredUpdateMessage
+            );
+
+            let mut updates = Vec::new();
+            for _ in 0..500 {
+                updates = discord.requests_to(&Method::PATCH, "/webhooks/");
+                if updates
+                    .iter()
+                    .any(|update| update.body["embeds"].is_array())
+                {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(updates[0].body["content"], "Looking for reports...");
+            assert!(updates
+                .iter()
+                .all(|update| update.body["components"] == json!([])));
+            assert_eq!(
+                updates.last().unwrap().body["embeds"][0]["description"],
+                "Resolved 1 report."
+            );
+            assert_eq!(resolved(&discord), ["/channels/20/messages/71"]);
+        });
+    }
+
+    #[test]
+    fn finds_users_in_description_templates() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.method {
+                Method::GET => {
+                    // Reported by <@40>, who the resolve button belongs to.
+                    let mut message = report(71, 2, "", 0, false);
+                    message["embeds"] = json!([{
+                        "type": "rich",
+                        "description": "<@40> reported <@50>",
+                        "fields": [],
+                    }]);
+                    (StatusCode::OK, json!([message]))
+                }
+                _ => edited(&request.path),
+            })
+            .await;
+            let channel = text_channel();
+            assert_eq!(
+                confirm(&discord, &channel, filter(Some(40), now() + 60)).await,
+                "Resolved 0 reports."
+            );
+            assert_eq!(
+                confirm(&discord, &channel, filter(Some(50), now() + 60)).await,
+                "Resolved 1 report."
+            );
+        });
+    }
+
+    /// Messages by someone else, so none of them are reports.
+    fn chatter(first: u64, count: u64) -> Value {
+        (first..first + count)
+            .map(|id| {
+                let mut message = testing::message(testing::CHANNEL, id);
+                message["author"] = testing::user_json(30);
+                message
+            })
+            .collect()
+    }
+
+    #[test]
+    fn looks_further_back_than_one_page() {
+        testing::runtime().block_on(async {
+            let pages = Arc::new(AtomicUsize::new(0));
+            let discord = MockDiscord::start({
+                let pages = pages.clone();
+                move |request| match request.method {
+                    Method::GET => match pages.fetch_add(1, Ordering::Relaxed) {
+                        0 => (StatusCode::OK, chatter(1000, 100)),
+                        _ => (StatusCode::OK, json!([report(71, 2, "<@50>", 0, false)])),
+                    },
+                    _ => edited(&request.path),
+                }
+            })
+            .await;
+            let resolved_text =
+                confirm(&discord, &text_channel(), filter(Some(50), now() + 60)).await;
+            assert_eq!(resolved_text, "Resolved 1 report.");
+            assert_eq!(pages.load(Ordering::Relaxed), 2);
+        });
+    }
+
+    #[test]
+    fn stops_looking_after_the_cap() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.method {
+                Method::GET => (StatusCode::OK, chatter(1000, 100)),
+                _ => edited(&request.path),
+            })
+            .await;
+            let resolved_text =
+                confirm(&discord, &text_channel(), filter(Some(50), now() + 60)).await;
+            assert_eq!(resolved_text, "Resolved 0 reports.");
+            let pages = discord.requests_to(&Method::GET, "/channels/20/messages");
+            assert_eq!(pages.len(), MAX_SCANNED_MESSAGES / usize::from(PAGE_SIZE));
+        });
+    }
+
+    /// A post in the forum `parent`, archived if `archived`.
+    fn post(id: u64, parent: u64, archived: bool) -> Value {
+        let mut post = testing::channel(id, Some(Id::new(testing::GUILD)), 11);
+        post["parent_id"] = json!(parent.to_string());
+        post["thread_metadata"] = json!({
+            "archived": archived,
+            "auto_archive_duration": 1440,
+            "archive_timestamp": "2025-01-01T00:00:00.000000+00:00",
+            "locked": false,
+        });
+        post
+    }
+
+    #[test]
+    fn resolves_reports_in_forum_posts() {
+        testing::runtime().block_on(async {
+            let discord =
+                MockDiscord::start(|request| match (&request.method, request.path.as_str()) {
+                    (&Method::GET, "/channels/80") => (
+                        StatusCode::OK,
+                        testing::channel(80, Some(Id::new(testing::GUILD)), 15),
+                    ),
+                    (&Method::GET, "/guilds/10/threads/active") => (
+                        StatusCode::OK,
+                        json!({
+                            "threads": [post(81, 80, false), post(82, 99, false)],
+                            "members": [],
+                        }),
+                    ),
+                    (&Method::GET, "/channels/80/threads/archived/public") => (
+                        StatusCode::OK,
+                        json!({
+                            "threads": [post(83, 80, true)],
+                            "members": [],
+                            "has_more": false,
+                        }),
+                    ),
+                    (&Method::GET, path) => {
+                        let id: u64 = path.rsplit('/').next().unwrap().parse().unwrap();
+                        let mut message = report(id, 2, "<@50>", 0, false);
+                        message["channel_id"] = json!(id.to_string());
+                        (StatusCode::OK, message)
+                    }
+                    _ => edited(&request.path),
+                })
+                .await;
+            // Used in one of the posts.
+            let channel = serde_json::from_value(post(81, 80, false)).unwrap();
+            let resolved_text = confirm(&discord, &channel, filter(Some(50), now() + 60)).await;
+            assert_eq!(resolved_text, "Resolved 2 reports.");
+            let edited: Vec<_> = discord
+                .requests_to(&Method::PATCH, "/channels/")
+                .into_iter()
+                .map(|request| request.path)
+                .collect();
+            assert_eq!(
+                edited,
+                ["/channels/81/messages/81", "/channels/83/messages/83"]
+            );
+        });
+    }
+
+    #[test]
+    fn needs_a_filter() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let response = Box::pin(niloecl::make_handler(bulk_resolve)(
+                command(&json!([])),
+                discord.state(),
+            ))
+            .await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some(InteractError::NoBulkFilter.to_string().as_str())
+            );
+            assert!(discord.requests().is_empty());
+        });
+    }
+
+    #[test]
+    fn rejects_buttons_without_a_time() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let response = Box::pin(niloecl::make_handler(confirm_bulk_resolve)(
+                testing::button("bulk_resolve:target=50"),
+                discord.state(),
+            ))
+            .await;
+            assert!(testing::error_message(&response).is_some());
+            assert!(discord.requests().is_empty());
+        });
+    }
+
+    #[test]
+    fn needs_permission_to_resolve() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let mut click =
+                testing::button(&format!("bulk_resolve:target=50:before={}", now() + 60));
+            if let Some(member) = &mut click.member {
+                member.permissions = Some(Permissions::empty());
+            }
+            let response = Box::pin(niloecl::make_handler(confirm_bulk_resolve)(
+                click,
+                discord.state(),
+            ))
+            .await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some(InteractError::CannotResolve.to_string().as_str())
+            );
+            assert!(discord.requests().is_empty());
+        });
+    }
+
+    #[test]
+    fn finds_mentions_and_ids() {
+        let user = Id::new(50);
+        assert!(mentions("<@50>", user));
+        assert!(mentions("<@!50>", user));
+        assert!(mentions(" 50 ", user));
+        assert!(mentions("<@50> and <@60>", user));
+        assert!(!mentions("<@500>", user));
+        assert!(!mentions("150", user));
+    }
+}
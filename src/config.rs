@@ -0,0 +1,1319 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    path::Path,
+};
+
+use twilight_http::{response::DeserializeBodyError, Client};
+use twilight_interactions::command::{CommandOption, CreateOption};
+use twilight_model::{
+    channel::{
+        message::{Component, MessageFlags},
+        ChannelType, Message,
+    },
+    guild::Permissions,
+    id::{
+        marker::{ChannelMarker, GuildMarker, RoleMarker, TagMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::embed::{image_source::ImageSourceUrlError, ImageSource};
+
+use crate::{
+    emoji::{parse_emoji, EmojiError},
+    extract::{get_custom_id_rpc, CustomIdTooLong},
+    interact::{setup_message, try_pin, OPEN_FORM, OPEN_FORM_USER},
+    messages::Messages,
+    template::placeholders,
+};
+
+/// Declarative configuration, read from `AGHAST_CONFIG_FILE` at startup.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Setups which should exist, as if `/setup` had been run for each.
+    #[serde(default, rename = "setup")]
+    pub setups: Vec<SetupConfig>,
+    /// Text for everyone without a translation in their language.
+    #[serde(default)]
+    pub messages: Messages,
+    /// `messages` in other languages, by Discord locale like `de` or `pt-BR`.
+    /// Users get the one for their own locale.
+    #[serde(default)]
+    pub translations: HashMap<String, Messages>,
+    /// The language to answer in for each server, whatever the locale of its
+    /// users. Has to be one of `translations`.
+    #[serde(default)]
+    pub guild_languages: HashMap<Id<GuildMarker>, String>,
+    /// Where `/report` sends reports from each guild. `/report` is only
+    /// registered if this isn't empty.
+    #[serde(default)]
+    pub report_channels: HashMap<Id<GuildMarker>, Id<ChannelMarker>>,
+    /// Ping roles by category, for setups whose modmail channel is in one
+    /// and which have no `form.ping_role` of their own. Saves repeating the
+    /// role of each moderator team for every setup in its category.
+    #[serde(default)]
+    pub category_ping_roles: HashMap<Id<ChannelMarker>, Id<RoleMarker>>,
+}
+
+/// The file equivalent of the `/setup` command.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetupConfig {
+    pub message: String,
+    pub select_placeholder: String,
+    pub button_msg: String,
+    #[serde(default)]
+    pub button_emoji: Option<String>,
+    pub button_channel: Id<ChannelMarker>,
+    pub modmail_channel: Id<ChannelMarker>,
+    #[serde(default)]
+    pub pin: bool,
+    #[serde(default)]
+    pub entry_points: EntryPoints,
+    /// Only members with this role may send reports through the setup.
+    #[serde(default)]
+    pub required_role: Option<Id<RoleMarker>>,
+    #[serde(default)]
+    pub form: FormConfig,
+}
+
+/// Which ways to open the form the setup message offers. There is always at
+/// least one.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, CommandOption, CreateOption,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryPoints {
+    /// A user select, which fills in the user field, and a button.
+    #[default]
+    #[option(name = "User select and button", value = "both")]
+    Both,
+    #[option(name = "Only the user select", value = "select")]
+    Select,
+    #[option(name = "Only the button", value = "button")]
+    Button,
+}
+
+impl EntryPoints {
+    pub const fn has_select(self) -> bool {
+        matches!(self, Self::Both | Self::Select)
+    }
+
+    pub const fn has_button(self) -> bool {
+        matches!(self, Self::Both | Self::Button)
+    }
+}
+
+/// Per-setup settings for the report form. Setups created with `/setup`, and
+/// modmail channels that aren't in the config file, use the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)] // Independent switches from the config file.
+pub struct FormConfig {
+    /// Fields to leave out of the form. `reason` can't be disabled, so there
+    /// is always at least one field left.
+    pub disabled_fields: Vec<ReportField>,
+    /// Order of the fields in the report embed. Fields not listed here follow
+    /// in their usual order.
+    pub field_order: Vec<ReportField>,
+    /// Show reporters a preview with a "Confirm and submit" button, and only
+    /// post the report once they click it.
+    pub confirm_before_submit: bool,
+    /// Allow reasons longer than fit in the report embed, attaching the full
+    /// text as `reason.txt`.
+    pub long_reason_attachment: bool,
+    /// Emoji to react to new reports with, like 🆕, so moderators can see at a
+    /// glance which ones nobody has handled yet by removing it.
+    pub new_report_reaction: Option<String>,
+    /// Fields shown side by side in the report embed. Defaults to `user` and
+    /// `channel`.
+    pub inline_fields: Option<Vec<ReportField>>,
+    /// Report embed description, replacing the fields. `{user}`, `{channel}`,
+    /// `{link}`, `{reason}` and `{reporter}` are replaced with the report's
+    /// values.
+    pub description_template: Option<String>,
+    /// Image URL shown in the corner of every report embed, like a logo.
+    pub thumbnail_url: Option<String>,
+    /// Image URL shown below the fields of every report embed.
+    pub image_url: Option<String>,
+    /// Role to mention with every report, so the right moderators are notified.
+    pub ping_role: Option<Id<RoleMarker>>,
+    /// Mention `ping_role` at most once per this many seconds, with links to
+    /// every report since, instead of with every report. Reports themselves
+    /// are still posted right away. Forums can't take the ping, so this is
+    /// only allowed for text channels.
+    pub ping_batch_secs: Option<u64>,
+    /// Post a "possible brigade" alert when many different users report the
+    /// same user in a short time. Reports after it are marked as held and
+    /// don't ping anyone.
+    pub brigade: Option<BrigadeConfig>,
+    /// Show a "Report another" button after a report was submitted.
+    pub report_another_button: bool,
+    /// What to do when the channel field isn't a channel of this server.
+    pub channel_policy: ChannelPolicy,
+    /// Categories the channel field has to be in, if any. Only checked when
+    /// the field refers to an actual channel.
+    pub allowed_categories: Vec<Id<ChannelMarker>>,
+    /// How many forms one user may have open at once.
+    pub max_open_forms: Option<usize>,
+    /// Tags to put on reports posted to a forum channel, like "unresolved".
+    pub forum_tags: Vec<Id<TagMarker>>,
+    /// Tag which replaces `forum_tags` on a forum post once its report is
+    /// resolved.
+    pub resolved_forum_tag: Option<Id<TagMarker>>,
+    /// Ping again about reports which are still open after this many
+    /// seconds, and mark them as "⏰ Overdue". Needs `resolve_button`, so
+    /// there's a way to tell which reports are open, and `AGHAST_DB_PATH` to
+    /// keep track of them.
+    pub escalate_after_secs: Option<u64>,
+    /// Role to ping about overdue reports, like senior moderators. Defaults to
+    /// `ping_role`.
+    pub escalation_role: Option<Id<RoleMarker>>,
+    /// Moderators to assign new reports to in turn. The report is marked as
+    /// claimed by the moderator whose turn it is, and pings only them instead
+    /// of `ping_role`. Moderators who used `/unavailable` are skipped. Needs
+    /// `AGHAST_DB_PATH` to keep track of whose turn it is.
+    pub assign_rotation: Vec<Id<UserMarker>>,
+    /// Mark the channel field when the reporter can't see that channel, since
+    /// such reports are often made up.
+    pub warn_hidden_channels: bool,
+    /// Accept reports from bot accounts, which are turned away by default.
+    pub allow_bot_reporters: bool,
+    /// Flags for the "report received" message, on top of being ephemeral.
+    pub response_flags: Vec<ResponseFlag>,
+    /// Start a thread on every report, so moderators can discuss it there.
+    /// Reports in forum channels are posts of their own already.
+    pub report_threads: bool,
+    /// Add a "Resolve" button to every report, for moderators with
+    /// `AGHAST_RESOLVE_PERMISSIONS` to mark it as handled.
+    pub resolve_button: bool,
+    /// Ask moderators for a short reason when they click "Resolve", shown on
+    /// the resolved report.
+    pub resolve_reason: bool,
+    /// DM reporters a copy of their report once it's posted.
+    pub dm_copy: bool,
+    /// What to do when a reporter can't get a DM, like when their DMs are
+    /// closed. Failures are logged either way.
+    pub dm_failure: DmFailure,
+    /// Extra text inputs after the usual ones, like a severity. Their values
+    /// are shown as fields of their own, or as `{id}` in the
+    /// `description_template`.
+    pub custom_fields: Vec<CustomField>,
+    /// Changes to the usual text inputs, like `[form.fields.reason]` with
+    /// `label = "Your suggestion"`.
+    pub fields: HashMap<ReportField, FieldOverride>,
+}
+
+/// Replaces parts of one of the usual text inputs of the form. Reports still
+/// show the field under its usual name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldOverride {
+    pub label: Option<String>,
+    pub placeholder: Option<String>,
+    /// Whether the field has to be filled in. Even `reason` can be made
+    /// optional, though `/report` always asks for one.
+    pub required: Option<bool>,
+}
+
+/// A text input of the report form on top of the usual ones.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomField {
+    /// Lowercase letters, digits and `_`, unique within the form.
+    pub id: String,
+    /// Shown above the input, and as the name of the field in reports.
+    pub label: String,
+    #[serde(default)]
+    pub placeholder: Option<String>,
+    #[serde(default = "default_true")]
+    pub required: bool,
+    #[serde(default)]
+    pub style: FieldStyle,
+    /// At most [`CUSTOM_FIELD_MAX_CHARS`], which is also the default.
+    #[serde(default)]
+    pub max_length: Option<u16>,
+    /// At most `max_length`. Only checked if the field isn't left empty.
+    #[serde(default)]
+    pub min_length: Option<u16>,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// Custom fields are shown as embed fields, so they can't be longer than one.
+pub const CUSTOM_FIELD_MAX_CHARS: u16 = 1024;
+
+/// Modals can't have more inputs than this.
+const MAX_FORM_FIELDS: usize = 5;
+
+/// IDs the usual fields and template placeholders already use.
+const RESERVED_FIELD_IDS: [&str; 6] = [
+    "user",
+    "channel",
+    "message_link",
+    "reason",
+    "link",
+    "reporter",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldStyle {
+    /// A single line.
+    #[default]
+    Short,
+    /// Multiple lines.
+    Paragraph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFlag {
+    /// Hide link previews, like those of message links in the report.
+    SuppressEmbeds,
+    /// Send without a notification sound.
+    SuppressNotifications,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DmFailure {
+    /// Only log it.
+    #[default]
+    Log,
+    /// Let moderators know under the report that the reporter didn't get it.
+    Notify,
+    /// Post what the reporter would have gotten under the report instead, so
+    /// moderators can pass it on.
+    Fallback,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelPolicy {
+    /// Accept anything, like `#minecraft` typed as text.
+    #[default]
+    Lenient,
+    /// Accept anything, but mark channels which aren't allowed in the report.
+    Warn,
+    /// Only accept allowed channels of this server.
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BrigadeConfig {
+    /// How many different reporters it takes.
+    pub reporters: usize,
+    /// How close together their reports have to be.
+    pub window_secs: u64,
+}
+
+impl FormConfig {
+    pub fn is_enabled(&self, field: ReportField) -> bool {
+        !self.disabled_fields.contains(&field)
+    }
+
+    pub fn is_inline(&self, field: ReportField) -> bool {
+        self.inline_fields.as_deref().map_or(
+            matches!(field, ReportField::User | ReportField::Channel),
+            |inline| inline.contains(&field),
+        )
+    }
+
+    /// Flags for the ephemeral "report received" response.
+    pub fn response_flags(&self) -> MessageFlags {
+        self.response_flags
+            .iter()
+            .fold(MessageFlags::EPHEMERAL, |flags, flag| {
+                flags
+                    | match flag {
+                        ResponseFlag::SuppressEmbeds => MessageFlags::SUPPRESS_EMBEDS,
+                        ResponseFlag::SuppressNotifications => MessageFlags::SUPPRESS_NOTIFICATIONS,
+                    }
+            })
+    }
+
+    pub fn field_order(&self) -> impl Iterator<Item = ReportField> + '_ {
+        let rest = ReportField::ALL
+            .into_iter()
+            .filter(|field| !self.field_order.contains(field));
+        self.field_order.iter().copied().chain(rest)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportField {
+    User,
+    Channel,
+    MessageLink,
+    Reason,
+}
+
+impl ReportField {
+    pub const ALL: [Self; 4] = [Self::User, Self::Channel, Self::MessageLink, Self::Reason];
+
+    /// How the field is labelled in reports.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::User => "User",
+            Self::Channel => "Channel",
+            Self::MessageLink => "Message link",
+            Self::Reason => "Reason",
+        }
+    }
+}
+
+/// Form settings for every configured modmail channel.
+#[derive(Debug, Default)]
+pub struct Forms {
+    by_channel: HashMap<Id<ChannelMarker>, FormConfig>,
+    required_roles: HashMap<Id<ChannelMarker>, Id<RoleMarker>>,
+    default: FormConfig,
+}
+
+impl Forms {
+    pub fn new(config: &Config) -> Self {
+        let by_channel = config
+            .setups
+            .iter()
+            .map(|setup| (setup.modmail_channel, setup.form.clone()))
+            .collect();
+        let required_roles = config
+            .setups
+            .iter()
+            .filter_map(|setup| Some((setup.modmail_channel, setup.required_role?)))
+            .collect();
+        Self {
+            by_channel,
+            required_roles,
+            default: FormConfig::default(),
+        }
+    }
+
+    pub fn get(&self, modmail_channel: Id<ChannelMarker>) -> &FormConfig {
+        self.by_channel
+            .get(&modmail_channel)
+            .unwrap_or(&self.default)
+    }
+
+    /// The role the config file's setup for `modmail_channel` requires.
+    /// Setups made with `/setup` carry theirs in their custom IDs instead.
+    pub fn required_role(&self, modmail_channel: Id<ChannelMarker>) -> Option<Id<RoleMarker>> {
+        self.required_roles.get(&modmail_channel).copied()
+    }
+}
+
+impl Config {
+    /// Read and validate a config file. Files ending in `.toml` are parsed as
+    /// TOML, everything else as JSON.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)?;
+        let config: Self = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&text)?
+        } else {
+            serde_json::from_str(&text)?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Permissions the bot needs in its channels for everything this config
+    /// turns on.
+    pub fn required_permissions(&self) -> Permissions {
+        // Reading history is needed to find existing setup messages.
+        let mut permissions = Permissions::VIEW_CHANNEL
+            | Permissions::SEND_MESSAGES
+            | Permissions::EMBED_LINKS
+            | Permissions::READ_MESSAGE_HISTORY;
+        for setup in &self.setups {
+            if setup.pin {
+                permissions |= Permissions::MANAGE_MESSAGES;
+            }
+            if setup.form.long_reason_attachment {
+                permissions |= Permissions::ATTACH_FILES;
+            }
+            if setup.form.new_report_reaction.is_some() {
+                permissions |= Permissions::ADD_REACTIONS;
+            }
+            if setup.form.report_threads {
+                permissions |= Permissions::CREATE_PUBLIC_THREADS;
+            }
+            // Needed to ping roles which aren't mentionable by everyone.
+            if setup.form.ping_role.is_some() || setup.form.escalation_role.is_some() {
+                permissions |= Permissions::MENTION_EVERYONE;
+            }
+        }
+        permissions
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.messages.validate()?;
+        for messages in self.translations.values() {
+            messages.validate()?;
+        }
+        for (guild, language) in &self.guild_languages {
+            if !self.translations.contains_key(language) {
+                return Err(ConfigError::UnknownLanguage(*guild, language.clone()));
+            }
+        }
+        let mut seen = HashSet::with_capacity(self.setups.len());
+        let mut forms: HashMap<_, (usize, &FormConfig)> = HashMap::new();
+        for (index, setup) in self.setups.iter().enumerate() {
+            if !setup.form.is_enabled(ReportField::Reason) {
+                return Err(ConfigError::ReasonDisabled(index));
+            }
+            for (position, field) in setup.form.field_order.iter().enumerate() {
+                if !setup.form.is_enabled(*field) {
+                    return Err(ConfigError::OrderedFieldDisabled(index, *field));
+                }
+                if setup.form.field_order[..position].contains(field) {
+                    return Err(ConfigError::OrderedFieldRepeated(index, *field));
+                }
+            }
+            let inline = setup.form.inline_fields.as_deref().unwrap_or_default();
+            for (position, field) in inline.iter().enumerate() {
+                if !setup.form.is_enabled(*field) {
+                    return Err(ConfigError::InlineFieldDisabled(index, *field));
+                }
+                if inline[..position].contains(field) {
+                    return Err(ConfigError::InlineFieldRepeated(index, *field));
+                }
+            }
+            // Discord only fits three inline fields on a row, so more than
+            // that would wrap unpredictably depending on the field order.
+            if inline.len() > 3 {
+                return Err(ConfigError::TooManyInlineFields(index));
+            }
+            if setup.form.max_open_forms == Some(0) {
+                return Err(ConfigError::NoOpenForms(index));
+            }
+            if setup.form.forum_tags.len() > MAX_FORUM_TAGS {
+                return Err(ConfigError::TooManyForumTags(index));
+            }
+            if setup.form.resolved_forum_tag.is_some() && !setup.form.resolve_button {
+                return Err(ConfigError::ResolvedTagWithoutButton(index));
+            }
+            check_pings(index, &setup.form)?;
+            if let Some(brigade) = &setup.form.brigade {
+                if brigade.reporters < 2 || brigade.window_secs == 0 {
+                    return Err(ConfigError::InvalidBrigade(index));
+                }
+            }
+            // Forms are looked up by modmail channel, so setups sharing one
+            // must agree on how the form looks.
+            match forms.get(&setup.modmail_channel) {
+                Some((first, form)) if *form != &setup.form => {
+                    return Err(ConfigError::ConflictingForms(*first, index));
+                }
+                Some(_) => {}
+                None => {
+                    forms.insert(setup.modmail_channel, (index, &setup.form));
+                }
+            }
+            check_length(index, "message", &setup.message, 2000)?;
+            check_length(index, "select_placeholder", &setup.select_placeholder, 45)?;
+            check_length(index, "button_msg", &setup.button_msg, 32)?;
+            if let Some(emoji) = &setup.button_emoji {
+                parse_emoji(emoji).map_err(|e| ConfigError::Emoji(index, e))?;
+            }
+            for (field, url) in [
+                ("thumbnail_url", &setup.form.thumbnail_url),
+                ("image_url", &setup.form.image_url),
+            ] {
+                if let Some(url) = url {
+                    ImageSource::url(url).map_err(|e| ConfigError::ImageUrl(index, field, e))?;
+                }
+            }
+            check_custom_fields(index, &setup.form)?;
+            check_field_overrides(index, &setup.form)?;
+            if let Some(template) = &setup.form.description_template {
+                check_template(index, &setup.form, template)?;
+            }
+            if let Some(emoji) = &setup.form.new_report_reaction {
+                parse_emoji(emoji).map_err(|e| ConfigError::ReactionEmoji(index, e))?;
+            }
+            if !seen.insert((setup.button_channel, setup.modmail_channel)) {
+                return Err(ConfigError::DuplicateSetup(index));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Batched pings and escalations need a role to ping, and escalations a way
+/// to tell which reports are still open.
+fn check_pings(index: usize, form: &FormConfig) -> Result<(), ConfigError> {
+    let escalates = form.escalate_after_secs.is_some();
+    if form.escalate_after_secs == Some(0)
+        || (escalates && !form.resolve_button)
+        || (escalates && form.escalation_role.or(form.ping_role).is_none())
+        || (!escalates && form.escalation_role.is_some())
+    {
+        return Err(ConfigError::InvalidEscalation(index));
+    }
+    if form.ping_batch_secs.is_some_and(|secs| secs == 0)
+        || (form.ping_batch_secs.is_some() && form.ping_role.is_none())
+    {
+        return Err(ConfigError::InvalidPingBatch(index));
+    }
+    Ok(())
+}
+
+/// Custom fields need usable IDs, and have to fit in the modal next to the
+/// enabled usual fields.
+fn check_custom_fields(index: usize, form: &FormConfig) -> Result<(), ConfigError> {
+    let usual = ReportField::ALL
+        .into_iter()
+        .filter(|field| form.is_enabled(*field))
+        .count();
+    if usual + form.custom_fields.len() > MAX_FORM_FIELDS {
+        return Err(ConfigError::TooManyFields(index, MAX_FORM_FIELDS));
+    }
+    for (position, field) in form.custom_fields.iter().enumerate() {
+        // Custom IDs of modal inputs can be at most 100 characters long.
+        let valid_id = (1..=100).contains(&field.id.len())
+            && field
+                .id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_id || RESERVED_FIELD_IDS.contains(&field.id.as_str()) {
+            return Err(ConfigError::InvalidFieldId(index, field.id.clone()));
+        }
+        if form.custom_fields[..position]
+            .iter()
+            .any(|earlier| earlier.id == field.id)
+        {
+            return Err(ConfigError::CustomFieldRepeated(index, field.id.clone()));
+        }
+        check_length(index, "form.custom_fields.label", &field.label, 45)?;
+        if let Some(placeholder) = &field.placeholder {
+            check_length(index, "form.custom_fields.placeholder", placeholder, 100)?;
+        }
+        if field
+            .max_length
+            .is_some_and(|max| max == 0 || max > CUSTOM_FIELD_MAX_CHARS)
+        {
+            return Err(ConfigError::CustomFieldMaxLength(
+                index,
+                field.id.clone(),
+                CUSTOM_FIELD_MAX_CHARS,
+            ));
+        }
+        if field
+            .min_length
+            .is_some_and(|min| min > field.max_length.unwrap_or(CUSTOM_FIELD_MAX_CHARS))
+        {
+            return Err(ConfigError::CustomFieldMinLength(index, field.id.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Overrides have to fit in the modal, and are pointless for disabled fields.
+fn check_field_overrides(index: usize, form: &FormConfig) -> Result<(), ConfigError> {
+    for (field, change) in &form.fields {
+        if !form.is_enabled(*field) {
+            return Err(ConfigError::OverriddenFieldDisabled(index, *field));
+        }
+        if let Some(label) = &change.label {
+            check_length(index, "form.fields.label", label, 45)?;
+        }
+        if let Some(placeholder) = &change.placeholder {
+            check_length(index, "form.fields.placeholder", placeholder, 100)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every placeholder has to be known, and refer to an enabled field.
+fn check_template(index: usize, form: &FormConfig, template: &str) -> Result<(), ConfigError> {
+    check_length(
+        index,
+        "form.description_template",
+        template,
+        EMBED_DESCRIPTION_MAX_CHARS,
+    )?;
+    for name in placeholders(template) {
+        let field = match name {
+            "reporter" => continue,
+            "user" => ReportField::User,
+            "channel" => ReportField::Channel,
+            "link" => ReportField::MessageLink,
+            "reason" => ReportField::Reason,
+            _ if form.custom_fields.iter().any(|field| field.id == name) => continue,
+            _ => return Err(ConfigError::UnknownPlaceholder(index, name.to_string())),
+        };
+        if !form.is_enabled(field) {
+            return Err(ConfigError::PlaceholderDisabled(index, field));
+        }
+    }
+    Ok(())
+}
+
+/// Discord's limit on the length of an embed description.
+pub const EMBED_DESCRIPTION_MAX_CHARS: usize = 4096;
+
+/// Mirrors the `min_length`/`max_length` constraints on `SetupCommand`.
+fn check_length(
+    index: usize,
+    field: &'static str,
+    value: &str,
+    max: usize,
+) -> Result<(), ConfigError> {
+    let len = value.chars().count();
+    if (1..=max).contains(&len) {
+        Ok(())
+    } else {
+        Err(ConfigError::Length { index, field, max })
+    }
+}
+
+/// Make sure every configured setup has a message in its button channel.
+///
+/// Setup messages are matched by author and the modmail channel in their
+/// custom IDs, so an existing message is edited in place instead of a new one
+/// being posted on every start.
+pub async fn apply(
+    client: &Client,
+    bot_id: Id<UserMarker>,
+    config: &mut Config,
+) -> Result<(), ApplyError> {
+    // Checked before anything is posted, so a broken config changes nothing.
+    import_category_ping_roles(client, config).await?;
+    check_ping_roles(client, config).await?;
+    check_forum_settings(client, config).await?;
+    for setup in &config.setups {
+        let (embed, components) = setup_message(
+            setup.message.clone(),
+            setup.select_placeholder.clone(),
+            setup.button_msg.clone(),
+            // Already validated when the config was loaded.
+            setup
+                .button_emoji
+                .as_deref()
+                .and_then(|emoji| parse_emoji(emoji).ok()),
+            setup.modmail_channel,
+            setup.entry_points,
+            setup.required_role,
+        )?;
+        let existing = client
+            .channel_messages(setup.button_channel)
+            .limit(100)
+            .await?
+            .models()
+            .await?
+            .into_iter()
+            .find(|msg| msg.author.id == bot_id && is_setup_for(msg, setup.modmail_channel));
+        let (message_id, pinned) = if let Some(existing) = existing {
+            client
+                .update_message(setup.button_channel, existing.id)
+                .embeds(Some(&[embed]))
+                .components(Some(&components))
+                .await?;
+            (existing.id, existing.pinned)
+        } else {
+            let created = client
+                .create_message(setup.button_channel)
+                .embeds(&[embed])
+                .components(&components)
+                .await?
+                .model()
+                .await?;
+            (created.id, false)
+        };
+        if setup.pin && !pinned {
+            try_pin(client, setup.button_channel, message_id).await;
+        }
+    }
+    Ok(())
+}
+
+/// The roles of each server, fetched once.
+type GuildRoles = HashMap<Id<GuildMarker>, Vec<Id<RoleMarker>>>;
+
+async fn has_role(
+    client: &Client,
+    guild_roles: &mut GuildRoles,
+    guild_id: Id<GuildMarker>,
+    role: Id<RoleMarker>,
+) -> Result<bool, ApplyError> {
+    let roles = match guild_roles.entry(guild_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let roles = client.roles(guild_id).await?.models().await?;
+            entry.insert(roles.into_iter().map(|role| role.id).collect())
+        }
+    };
+    Ok(roles.contains(&role))
+}
+
+/// Give setups without a `ping_role` the one of their modmail channel's
+/// category, after making sure every role in `category_ping_roles` exists in
+/// its category's server.
+async fn import_category_ping_roles(
+    client: &Client,
+    config: &mut Config,
+) -> Result<(), ApplyError> {
+    if config.category_ping_roles.is_empty() {
+        return Ok(());
+    }
+    let mut guild_roles = GuildRoles::new();
+    for (category, role) in &config.category_ping_roles {
+        let channel = client.channel(*category).await?.model().await?;
+        if channel.kind != ChannelType::GuildCategory {
+            return Err(ApplyError::NotCategory(*category));
+        }
+        let guild_id = channel.guild_id.ok_or(ApplyError::NotCategory(*category))?;
+        if !has_role(client, &mut guild_roles, guild_id, *role).await? {
+            return Err(ApplyError::UnknownCategoryRole(*category, *role));
+        }
+    }
+    let mut applied = 0;
+    for setup in &mut config.setups {
+        if setup.form.ping_role.is_some() {
+            continue;
+        }
+        let channel = client.channel(setup.modmail_channel).await?.model().await?;
+        let role = channel
+            .parent_id
+            .and_then(|category| config.category_ping_roles.get(&category));
+        if let Some(role) = role {
+            setup.form.ping_role = Some(*role);
+            applied += 1;
+        }
+    }
+    tracing::info!(
+        "Applied {applied} of {} category ping roles",
+        config.category_ping_roles.len()
+    );
+    Ok(())
+}
+
+/// Make sure every `ping_role` exists in the guild of its modmail channel, so
+/// a typo doesn't silently leave reports unannounced.
+async fn check_ping_roles(client: &Client, config: &Config) -> Result<(), ApplyError> {
+    let mut guild_roles = GuildRoles::new();
+    let mut checked = 0;
+    for (index, setup) in config.setups.iter().enumerate() {
+        if setup.form.ping_role.is_none() && setup.form.escalation_role.is_none() {
+            continue;
+        }
+        let channel = client.channel(setup.modmail_channel).await?.model().await?;
+        let guild_id = channel.guild_id.ok_or(ApplyError::NotInGuild(index))?;
+        for role in setup
+            .form
+            .ping_role
+            .iter()
+            .chain(&setup.form.escalation_role)
+        {
+            if !has_role(client, &mut guild_roles, guild_id, *role).await? {
+                return Err(ApplyError::UnknownRole(index, *role));
+            }
+        }
+        checked += 1;
+    }
+    if checked > 0 {
+        tracing::info!("Checked ping roles for {checked} setups");
+    }
+    Ok(())
+}
+
+/// Discord doesn't allow more tags on a single forum post.
+const MAX_FORUM_TAGS: usize = 5;
+
+/// Make sure settings which depend on whether the modmail channel is a forum
+/// fit it. Every `forum_tags` entry and the `resolved_forum_tag` have to be
+/// tags of the forum, and batched pings can't be sent to one.
+async fn check_forum_settings(client: &Client, config: &Config) -> Result<(), ApplyError> {
+    for (index, setup) in config.setups.iter().enumerate() {
+        let mut tags = setup
+            .form
+            .forum_tags
+            .iter()
+            .chain(&setup.form.resolved_forum_tag)
+            .peekable();
+        let has_tags = tags.peek().is_some();
+        let batches_pings = setup.form.ping_batch_secs.is_some();
+        if !has_tags && !batches_pings {
+            continue;
+        }
+        let channel = client.channel(setup.modmail_channel).await?.model().await?;
+        let is_forum = matches!(
+            channel.kind,
+            ChannelType::GuildForum | ChannelType::GuildMedia
+        );
+        if batches_pings && is_forum {
+            return Err(ApplyError::PingBatchInForum(index));
+        }
+        if has_tags && !is_forum {
+            return Err(ApplyError::NotForum(index));
+        }
+        let available = channel.available_tags.unwrap_or_default();
+        for tag in tags {
+            if !available.iter().any(|available| available.id == *tag) {
+                return Err(ApplyError::UnknownTag(index, *tag));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_setup_for(msg: &Message, modmail_channel: Id<ChannelMarker>) -> bool {
+    // The required role may have changed since, so only the channel counts.
+    let channel = modmail_channel.to_string();
+    let opens_form = |custom_id: &str| {
+        get_custom_id_rpc(custom_id).is_ok_and(|(name, args)| {
+            matches!(name, OPEN_FORM | OPEN_FORM_USER)
+                && args.first().is_some_and(|arg| *arg == channel)
+        })
+    };
+    msg.components.iter().any(|row| {
+        let Component::ActionRow(row) = row else {
+            return false;
+        };
+        row.components.iter().any(|c| match c {
+            Component::Button(button) => button.custom_id.as_deref().is_some_and(opens_form),
+            Component::SelectMenu(select) => opens_form(&select.custom_id),
+            _ => false,
+        })
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Could not read config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JSON config: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid TOML config: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Setup #{index}: {field} must be between 1 and {max} characters")]
+    Length {
+        index: usize,
+        field: &'static str,
+        max: usize,
+    },
+    #[error("messages.{key} must be between 1 and {max} characters")]
+    MessageLength { key: &'static str, max: usize },
+    #[error("guild_languages: {1:?} for server {0} is not one of the translations")]
+    UnknownLanguage(Id<GuildMarker>, String),
+    #[error("Setup #{0} has the same button and modmail channel as an earlier setup")]
+    DuplicateSetup(usize),
+    #[error("Setup #{0}: invalid button_emoji: {1}")]
+    Emoji(usize, EmojiError),
+    #[error("Setup #{0}: invalid form.new_report_reaction: {1}")]
+    ReactionEmoji(usize, EmojiError),
+    #[error("Setup #{0}: invalid form.{1}: {2}")]
+    ImageUrl(usize, &'static str, ImageSourceUrlError),
+    #[error("Setup #{0}: form.description_template has unknown placeholder {{{1}}}")]
+    UnknownPlaceholder(usize, String),
+    #[error("Setup #{0}: form.description_template uses {1:?}, which is disabled")]
+    PlaceholderDisabled(usize, ReportField),
+    #[error("Setup #{0}: the reason field cannot be disabled")]
+    ReasonDisabled(usize),
+    #[error("Setup #{0}: the form can have at most {1} fields, including custom_fields")]
+    TooManyFields(usize, usize),
+    #[error(
+        "Setup #{0}: custom field ID {1:?} must be lowercase letters, digits and _, \
+         and not the name of a usual field"
+    )]
+    InvalidFieldId(usize, String),
+    #[error("Setup #{0}: custom field ID {1:?} is used more than once")]
+    CustomFieldRepeated(usize, String),
+    #[error("Setup #{0}: max_length of custom field {1:?} must be between 1 and {2}")]
+    CustomFieldMaxLength(usize, String, u16),
+    #[error("Setup #{0}: min_length of custom field {1:?} is more than its max_length")]
+    CustomFieldMinLength(usize, String),
+    #[error("Setup #{0}: form.fields changes {1:?}, which is disabled")]
+    OverriddenFieldDisabled(usize, ReportField),
+    #[error("Setup #{0}: field_order lists {1:?}, which is disabled")]
+    OrderedFieldDisabled(usize, ReportField),
+    #[error("Setup #{0}: field_order lists {1:?} more than once")]
+    OrderedFieldRepeated(usize, ReportField),
+    #[error("Setup #{0}: inline_fields lists {1:?}, which is disabled")]
+    InlineFieldDisabled(usize, ReportField),
+    #[error("Setup #{0}: inline_fields lists {1:?} more than once")]
+    InlineFieldRepeated(usize, ReportField),
+    #[error("Setup #{0}: at most 3 fields can be inline")]
+    TooManyInlineFields(usize),
+    #[error(
+        "Setup #{0}: form.brigade needs at least 2 reporters and a window of at least 1 second"
+    )]
+    InvalidBrigade(usize),
+    #[error("Setup #{0}: form.ping_batch_secs needs form.ping_role and must be at least 1")]
+    InvalidPingBatch(usize),
+    #[error(
+        "Setup #{0}: form.escalate_after_secs must be at least 1, and needs form.resolve_button \
+         and form.escalation_role or form.ping_role"
+    )]
+    InvalidEscalation(usize),
+    #[error("Setup #{0}: form.max_open_forms must be at least 1")]
+    NoOpenForms(usize),
+    #[error("Setup #{0}: form.forum_tags can list at most 5 tags")]
+    TooManyForumTags(usize),
+    #[error("Setup #{0}: form.resolved_forum_tag needs form.resolve_button")]
+    ResolvedTagWithoutButton(usize),
+    #[error("Setups #{0} and #{1} share a modmail channel but have different form settings")]
+    ConflictingForms(usize, usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApplyError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] twilight_http::Error),
+    #[error("Could not deserialize Discord response: {0}")]
+    Deserialize(#[from] DeserializeBodyError),
+    #[error(transparent)]
+    CustomId(#[from] CustomIdTooLong),
+    #[error("Setup #{0}: modmail_channel is not in a server")]
+    NotInGuild(usize),
+    #[error("Setup #{0}: role {1} does not exist in the modmail channel's server")]
+    UnknownRole(usize, Id<RoleMarker>),
+    #[error("category_ping_roles: {0} is not a category of a server")]
+    NotCategory(Id<ChannelMarker>),
+    #[error("category_ping_roles: role {1} does not exist in the server of category {0}")]
+    UnknownCategoryRole(Id<ChannelMarker>, Id<RoleMarker>),
+    #[error("Setup #{0}: forum tags are set, but modmail_channel is not a forum")]
+    NotForum(usize),
+    #[error("Setup #{0}: form.ping_batch_secs is set, but modmail_channel is a forum")]
+    PingBatchInForum(usize),
+    #[error("Setup #{0}: forum tag {1} is not a tag of the modmail channel")]
+    UnknownTag(usize, Id<TagMarker>),
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{self, MockDiscord};
+
+    const MODMAIL: u64 = 60;
+    const FORUM: u64 = 61;
+    const CATEGORY: u64 = 50;
+    const MOD_ROLE: u64 = 7;
+    const TEAM_ROLE: u64 = 9;
+
+    fn role(id: u64) -> Value {
+        json!({
+            "id": id.to_string(),
+            "name": "Moderators",
+            "color": 0,
+            "hoist": false,
+            "managed": false,
+            "mentionable": true,
+            "permissions": "0",
+            "position": 1,
+            "flags": 0,
+        })
+    }
+
+    fn tag(id: u64) -> Value {
+        json!({
+            "id": id.to_string(),
+            "name": "tag",
+            "moderated": false,
+            "emoji_id": null,
+            "emoji_name": null,
+        })
+    }
+
+    /// A server with [`MOD_ROLE`] and [`TEAM_ROLE`], the modmail channel in
+    /// [`CATEGORY`], and a forum with tags 1 and 2.
+    async fn discord() -> MockDiscord {
+        MockDiscord::start(|request| {
+            let guild = Some(Id::new(testing::GUILD));
+            match (&request.method, request.path.as_str()) {
+                (&Method::GET, "/channels/60") => {
+                    let mut channel = testing::channel(MODMAIL, guild, 0);
+                    channel["parent_id"] = json!(CATEGORY.to_string());
+                    (StatusCode::OK, channel)
+                }
+                (&Method::GET, "/channels/61") => {
+                    let mut channel = testing::channel(FORUM, guild, 15);
+                    channel["available_tags"] = json!([tag(1), tag(2)]);
+                    (StatusCode::OK, channel)
+                }
+                (&Method::GET, "/channels/50") => {
+                    (StatusCode::OK, testing::channel(CATEGORY, guild, 4))
+                }
+                (&Method::GET, "/guilds/10/roles") => {
+                    (StatusCode::OK, json!([role(MOD_ROLE), role(TEAM_ROLE)]))
+                }
+                (&Method::GET, "/channels/20/messages") => (StatusCode::OK, json!([])),
+                (&Method::POST, "/channels/20/messages") => {
+                    (StatusCode::OK, testing::message(testing::CHANNEL, 80))
+                }
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            }
+        })
+        .await
+    }
+
+    fn posted_setups(discord: &MockDiscord) -> usize {
+        discord
+            .requests_to(&Method::POST, "/channels/20/messages")
+            .len()
+    }
+
+    #[test]
+    fn reads_category_ping_roles() {
+        let config: Config = toml::from_str("[category_ping_roles]\n50 = \"7\"\n").unwrap();
+        assert_eq!(
+            config.category_ping_roles,
+            HashMap::from([(Id::new(CATEGORY), Id::new(MOD_ROLE))])
+        );
+    }
+
+    #[test]
+    fn unknown_ping_roles_stop_setups_from_being_posted() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let form = FormConfig {
+                ping_role: Some(Id::new(8)),
+                ..FormConfig::default()
+            };
+            let mut config = testing::config(Id::new(MODMAIL), form);
+            let result = apply(&discord.client(), Id::new(2), &mut config).await;
+            assert!(matches!(result, Err(ApplyError::UnknownRole(0, role)) if role == Id::new(8)));
+            assert_eq!(posted_setups(&discord), 0);
+        });
+    }
+
+    #[test]
+    fn imports_ping_roles_by_category() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let mut config = Config {
+                category_ping_roles: HashMap::from([(Id::new(CATEGORY), Id::new(MOD_ROLE))]),
+                ..testing::config(Id::new(MODMAIL), FormConfig::default())
+            };
+            apply(&discord.client(), Id::new(2), &mut config)
+                .await
+                .unwrap();
+            assert_eq!(config.setups[0].form.ping_role, Some(Id::new(MOD_ROLE)));
+            assert_eq!(posted_setups(&discord), 1);
+        });
+    }
+
+    #[test]
+    fn setups_keep_their_own_ping_role() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let form = FormConfig {
+                ping_role: Some(Id::new(MOD_ROLE)),
+                ..FormConfig::default()
+            };
+            let mut config = Config {
+                category_ping_roles: HashMap::from([(Id::new(CATEGORY), Id::new(MOD_ROLE))]),
+                ..testing::config(Id::new(MODMAIL), form)
+            };
+            config
+                .category_ping_roles
+                .insert(Id::new(CATEGORY), Id::new(MOD_ROLE));
+            apply(&discord.client(), Id::new(2), &mut config)
+                .await
+                .unwrap();
+            assert_eq!(config.setups[0].form.ping_role, Some(Id::new(MOD_ROLE)));
+        });
+    }
+
+    #[test]
+    fn rejects_category_roles_of_other_servers() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let mut config = Config {
+                category_ping_roles: HashMap::from([(Id::new(CATEGORY), Id::new(8))]),
+                ..testing::config(Id::new(MODMAIL), FormConfig::default())
+            };
+            let result = apply(&discord.client(), Id::new(2), &mut config).await;
+            assert!(matches!(
+                result,
+                Err(ApplyError::UnknownCategoryRole(category, role))
+                    if category == Id::new(CATEGORY) && role == Id::new(8)
+            ));
+            assert_eq!(posted_setups(&discord), 0);
+        });
+    }
+
+    #[test]
+    fn rejects_category_roles_for_channels_which_are_not_categories() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let mut config = Config {
+                category_ping_roles: HashMap::from([(Id::new(MODMAIL), Id::new(MOD_ROLE))]),
+                ..testing::config(Id::new(MODMAIL), FormConfig::default())
+            };
+            let result = apply(&discord.client(), Id::new(2), &mut config).await;
+            assert!(matches!(result, Err(ApplyError::NotCategory(_))));
+            assert_eq!(posted_setups(&discord), 0);
+        });
+    }
+
+    /// The error applying a setup with `tag` as its resolved tag, if any.
+    fn resolved_tag(modmail: u64, tag: u64) -> Option<ApplyError> {
+        let form = FormConfig {
+            resolve_button: true,
+            resolved_forum_tag: Some(Id::new(tag)),
+            ..FormConfig::default()
+        };
+        let mut config = testing::config(Id::new(modmail), form);
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            apply(&discord.client(), Id::new(2), &mut config)
+                .await
+                .err()
+        })
+    }
+
+    #[test]
+    fn checks_the_resolved_forum_tag() {
+        assert!(resolved_tag(FORUM, 2).is_none());
+        assert!(matches!(
+            resolved_tag(FORUM, 3),
+            Some(ApplyError::UnknownTag(0, tag)) if tag == Id::new(3)
+        ));
+        assert!(matches!(
+            resolved_tag(MODMAIL, 2),
+            Some(ApplyError::NotForum(0))
+        ));
+    }
+
+    /// The error applying a setup in `modmail` with batched pings, if any.
+    fn ping_batch(modmail: u64) -> Option<ApplyError> {
+        let form = FormConfig {
+            ping_role: Some(Id::new(MOD_ROLE)),
+            ping_batch_secs: Some(60),
+            ..FormConfig::default()
+        };
+        let mut config = testing::config(Id::new(modmail), form);
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            apply(&discord.client(), Id::new(2), &mut config)
+                .await
+                .err()
+        })
+    }
+
+    #[test]
+    fn batched_pings_need_a_text_channel() {
+        assert!(ping_batch(MODMAIL).is_none());
+        assert!(matches!(
+            ping_batch(FORUM),
+            Some(ApplyError::PingBatchInForum(0))
+        ));
+    }
+
+    #[test]
+    fn escalation_needs_a_resolve_button_and_a_role() {
+        let escalates = FormConfig {
+            escalate_after_secs: Some(3600),
+            resolve_button: true,
+            ping_role: Some(Id::new(MOD_ROLE)),
+            ..FormConfig::default()
+        };
+        let validate = |form| testing::config(Id::new(MODMAIL), form).validate();
+        assert!(validate(escalates.clone()).is_ok());
+        let to_team = FormConfig {
+            ping_role: None,
+            escalation_role: Some(Id::new(TEAM_ROLE)),
+            ..escalates.clone()
+        };
+        assert!(validate(to_team).is_ok());
+        for form in [
+            FormConfig {
+                escalate_after_secs: Some(0),
+                ..escalates.clone()
+            },
+            FormConfig {
+                resolve_button: false,
+                ..escalates.clone()
+            },
+            FormConfig {
+                ping_role: None,
+                ..escalates
+            },
+            FormConfig {
+                escalation_role: Some(Id::new(TEAM_ROLE)),
+                ..FormConfig::default()
+            },
+        ] {
+            assert!(matches!(
+                validate(form),
+                Err(ConfigError::InvalidEscalation(0))
+            ));
+        }
+    }
+
+    #[test]
+    fn unknown_escalation_roles_stop_setups_from_being_posted() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let form = FormConfig {
+                ping_role: Some(Id::new(MOD_ROLE)),
+                escalation_role: Some(Id::new(8)),
+                ..FormConfig::default()
+            };
+            let mut config = testing::config(Id::new(MODMAIL), form);
+            let result = apply(&discord.client(), Id::new(2), &mut config).await;
+            assert!(matches!(result, Err(ApplyError::UnknownRole(0, role)) if role == Id::new(8)));
+            assert_eq!(posted_setups(&discord), 0);
+        });
+    }
+
+    #[test]
+    fn server_languages_need_a_translation() {
+        let config: Config =
+            toml::from_str("[translations.de]\n[guild_languages]\n10 = \"de\"\n11 = \"fr\"\n")
+                .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::UnknownLanguage(guild, language)) if guild == Id::new(11) && language == "fr"
+        ));
+    }
+
+    #[test]
+    fn overrides_usual_fields() {
+        let form: FormConfig = toml::from_str(
+            "disabled_fields = [\"channel\"]\n\
+             [fields.reason]\nlabel = \"Your suggestion\"\nrequired = false\n\
+             [fields.message_link]\nplaceholder = \"A link to it\"\n",
+        )
+        .unwrap();
+        let reason = &form.fields[&ReportField::Reason];
+        assert_eq!(reason.label.as_deref(), Some("Your suggestion"));
+        assert_eq!(reason.required, Some(false));
+        assert!(testing::config(Id::new(MODMAIL), form.clone())
+            .validate()
+            .is_ok());
+
+        let mut disabled = form.clone();
+        disabled
+            .fields
+            .insert(ReportField::Channel, FieldOverride::default());
+        assert!(matches!(
+            testing::config(Id::new(MODMAIL), disabled).validate(),
+            Err(ConfigError::OverriddenFieldDisabled(
+                0,
+                ReportField::Channel
+            ))
+        ));
+
+        let mut too_long = form;
+        too_long.fields.insert(
+            ReportField::User,
+            FieldOverride {
+                label: Some("a".repeat(46)),
+                ..FieldOverride::default()
+            },
+        );
+        assert!(testing::config(Id::new(MODMAIL), too_long)
+            .validate()
+            .is_err());
+    }
+}
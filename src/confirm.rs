@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use niloecl::State;
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        Component, MessageFlags,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, InteractionMarker, RoleMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{custom_id, CidArgs, CustomIdTooLong, ExtractGuildId, ExtractMember, Localized},
+    interact::{build_report_embed, report_received_components, InteractError, ModmailFormModal},
+    messages::Messages,
+    queue::submit_report,
+    AppState,
+};
+
+/// Custom ID name of the "Confirm and submit" button.
+pub const CONFIRM_REPORT: &str = "confirm_report";
+
+/// Interaction tokens are only valid this long, so nobody could confirm a
+/// report after it anyway.
+const PENDING_TTL: Duration = Duration::from_mins(15);
+
+/// Reports waiting for their reporter to confirm them, keyed by the ID of the
+/// modal submit interaction which created them.
+#[derive(Debug, Default)]
+pub struct PendingReports(Mutex<HashMap<Id<InteractionMarker>, PendingReport>>);
+
+#[derive(Debug)]
+pub struct PendingReport {
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    /// Carried over to "Report another".
+    required_role: Option<Id<RoleMarker>>,
+    report: ModmailFormModal,
+    created: Instant,
+}
+
+impl PendingReports {
+    fn insert(&self, key: Id<InteractionMarker>, report: PendingReport) {
+        let mut pending = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        pending.retain(|_, report| report.created.elapsed() < PENDING_TTL);
+        pending.insert(key, report);
+    }
+
+    /// Take a pending report, but only for the user who submitted it.
+    fn take(&self, key: Id<InteractionMarker>, reporter: Id<UserMarker>) -> Option<PendingReport> {
+        let mut pending = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if pending
+            .get(&key)
+            .is_none_or(|report| report.reporter != reporter)
+        {
+            return None;
+        }
+        pending
+            .remove(&key)
+            .filter(|report| report.created.elapsed() < PENDING_TTL)
+    }
+}
+
+/// Hold a submitted report and show the reporter a preview with a button to
+/// actually send it.
+pub fn ask_confirmation(
+    state: &AppState,
+    messages: &Messages,
+    key: Id<InteractionMarker>,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    required_role: Option<Id<RoleMarker>>,
+    report: ModmailFormModal,
+) -> Result<InteractionResponse, CustomIdTooLong> {
+    let preview = build_report_embed(
+        state.forms.get(target_channel),
+        state.messages.reports(),
+        Some(reporter),
+        report.clone(),
+    );
+    state.pending_reports.insert(
+        key,
+        PendingReport {
+            reporter,
+            target_channel,
+            required_role,
+            report,
+            created: Instant::now(),
+        },
+    );
+
+    let button = Component::Button(Button {
+        custom_id: Some(custom_id(CONFIRM_REPORT, &[&key])?),
+        disabled: false,
+        emoji: None,
+        label: Some("Confirm and submit".to_string()),
+        style: ButtonStyle::Danger,
+        url: None,
+        sku_id: None,
+    });
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(&messages.confirm_report)
+        .embeds([preview])
+        .components([Component::ActionRow(ActionRow {
+            components: vec![button],
+        })])
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+pub async fn confirm_report(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    Localized(messages): Localized,
+    CidArgs((key,)): CidArgs<(Id<InteractionMarker>,)>,
+) -> Result<InteractionResponse, InteractError> {
+    let user = member.user.ok_or(InteractError::NoUser)?;
+    let pending = state
+        .pending_reports
+        .take(key, user.id)
+        .ok_or(InteractError::ReportExpired)?;
+
+    let target_channel = pending.target_channel;
+    submit_report(
+        &state,
+        guild_id,
+        pending.reporter,
+        target_channel,
+        pending.report,
+    )
+    .await?;
+
+    let data = InteractionResponseDataBuilder::new()
+        .content(&messages.report_received)
+        .embeds([])
+        .components(report_received_components(
+            &state,
+            target_channel,
+            pending.required_role,
+        )?)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(data),
+    })
+}
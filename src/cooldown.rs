@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{ExtractGuildId, SlashCommand},
+    interact::InteractError,
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "cooldown",
+    desc = "Show or change how long members wait between reports in this server",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct CooldownCommand {
+    /// The new cooldown in seconds, or leave it out to see the current one
+    // Shorter windows barely slow down anyone spamming reports, and longer
+    // ones would keep members from reporting anything new for days.
+    #[command(min_value = 10, max_value = 86400)]
+    seconds: Option<i64>,
+}
+
+impl CooldownCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+/// The cooldown is the window of the submit rate limit, so members may still
+/// send `AGHAST_SUBMIT_LIMIT` reports within it. Like the blocklist, it's
+/// changed in memory even if the store is down.
+pub async fn cooldown(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    SlashCommand(cmd): SlashCommand<CooldownCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let Some(secs) = cmd.seconds else {
+        return Ok(ephemeral(show(&state, guild_id)));
+    };
+    let secs = secs.unsigned_abs();
+
+    let saved = match &state.store {
+        Some(store) => store.set_cooldown(guild_id, secs).await,
+        None => Ok(()),
+    };
+    state
+        .submit_limits
+        .set_cooldown(guild_id, Duration::from_secs(secs));
+
+    let mut content = format!("Changed the cooldown to {secs} seconds.\n");
+    content.push_str(&show(&state, guild_id));
+    if state.store.is_none() {
+        content.push_str(
+            "\nThe cooldown isn't saved because `AGHAST_DB_PATH` isn't set, \
+             so this change is lost when the bot restarts.",
+        );
+    } else if let Err(e) = saved {
+        tracing::error!(
+            "Failed to save the cooldown of {guild_id}, the change only lasts until a \
+             restart: {e:?}"
+        );
+        content.push_str(
+            "\nSaving the cooldown is temporarily unavailable, \
+             so this change is lost if the bot restarts before you make it again.",
+        );
+    }
+    Ok(ephemeral(content))
+}
+
+/// How many reports members may send in the server's window.
+fn show(state: &AppState, guild_id: Id<GuildMarker>) -> String {
+    let limits = &state.submit_limits;
+    let secs = limits.window(guild_id).as_secs();
+    match limits.max() {
+        0 => format!(
+            "The cooldown is {secs} seconds, but reports aren't rate limited \
+             because `AGHAST_SUBMIT_LIMIT` is 0."
+        ),
+        1 => format!("Members can send 1 report every {secs} seconds in this server."),
+        max => format!("Members can send {max} reports every {secs} seconds in this server."),
+    }
+}
+
+fn ephemeral(content: String) -> InteractionResponse {
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(content)
+        .build();
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::{messages::Messages, ratelimit::SubmitLimits, testing};
+
+    const GUILD: Id<GuildMarker> = Id::new(testing::GUILD);
+
+    fn cooldown_command(seconds: Option<i64>) -> Interaction {
+        let options = seconds.map_or_else(
+            || json!([]),
+            |seconds| json!([{ "name": "seconds", "type": 4, "value": seconds }]),
+        );
+        testing::command("cooldown", &options)
+    }
+
+    fn limited_state() -> AppState {
+        AppState {
+            submit_limits: Arc::new(SubmitLimits::new(3, Duration::from_mins(1))),
+            ..testing::offline_state()
+        }
+    }
+
+    #[test]
+    fn shows_the_current_cooldown() {
+        testing::runtime().block_on(async {
+            let response =
+                niloecl::make_handler(cooldown)(cooldown_command(None), limited_state()).await;
+            assert_eq!(
+                testing::content(&response),
+                Some("Members can send 3 reports every 60 seconds in this server.")
+            );
+        });
+    }
+
+    #[test]
+    fn changes_the_cooldown_and_saves_it() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let state = AppState {
+                store: Some(store.clone()),
+                ..limited_state()
+            };
+            let response =
+                niloecl::make_handler(cooldown)(cooldown_command(Some(300)), state.clone()).await;
+            assert_eq!(
+                testing::content(&response),
+                Some(
+                    "Changed the cooldown to 300 seconds.\n\
+                     Members can send 3 reports every 300 seconds in this server."
+                )
+            );
+            assert_eq!(state.submit_limits.window(GUILD), Duration::from_mins(5));
+            assert_eq!(
+                state.submit_limits.window(Id::new(11)),
+                Duration::from_mins(1)
+            );
+
+            let reloaded = SubmitLimits::new(3, Duration::from_mins(1));
+            reloaded.load_cooldowns(&store).await.unwrap();
+            assert_eq!(reloaded.window(GUILD), Duration::from_mins(5));
+        });
+    }
+
+    #[test]
+    fn rejects_cooldowns_out_of_range() {
+        testing::runtime().block_on(async {
+            let state = limited_state();
+            for seconds in [0, 9, 86401] {
+                let response =
+                    niloecl::make_handler(cooldown)(cooldown_command(Some(seconds)), state.clone())
+                        .await;
+                assert_eq!(
+                    testing::error_message(&response),
+                    Some(Messages::default().command_parse_failed.as_str())
+                );
+            }
+            assert_eq!(state.submit_limits.window(GUILD), Duration::from_mins(1));
+        });
+    }
+
+    #[test]
+    fn changes_are_kept_in_memory_while_the_store_is_down() {
+        testing::runtime().block_on(async {
+            let state = AppState {
+                store: Some(testing::broken_store().await),
+                ..limited_state()
+            };
+            let response =
+                niloecl::make_handler(cooldown)(cooldown_command(Some(30)), state.clone()).await;
+            let content = testing::content(&response).unwrap();
+            assert!(content.contains("temporarily unavailable"), "{content}");
+            assert_eq!(state.submit_limits.window(GUILD), Duration::from_secs(30));
+        });
+    }
+}
@@ -0,0 +1,106 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, InteractionMarker, RoleMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    confirm::CONFIRM_REPORT,
+    extract::{get_custom_id_rpc, ExtractMember, FromCidArgs, SlashCommand, CUSTOM_ID_MAX_CHARS},
+    interact::{FormSubmitArgs, InteractError, OpenFormArgs, OPEN_FORM, OPEN_FORM_USER},
+    AppState,
+};
+
+/// Only registered when `AGHAST_DEV` is set.
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "debug-cid",
+    desc = "Show how a custom ID is parsed (dev only)",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct DebugCidCommand {
+    /// The custom ID to parse
+    custom_id: String,
+}
+
+impl DebugCidCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+pub async fn debug_cid(
+    State(state): State<AppState>,
+    ExtractMember(member): ExtractMember,
+    SlashCommand(cmd): SlashCommand<DebugCidCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let is_admin = member
+        .permissions
+        .is_some_and(|p| p.contains(Permissions::ADMINISTRATOR));
+    if !state.dev || !is_admin {
+        return Err(InteractError::DevOnly);
+    }
+
+    let mut lines = Vec::new();
+    let length = cmd.custom_id.chars().count();
+    if length > CUSTOM_ID_MAX_CHARS {
+        lines.push(format!(
+            "❌ {length} characters long, Discord allows at most {CUSTOM_ID_MAX_CHARS}"
+        ));
+    }
+    match get_custom_id_rpc(&cmd.custom_id) {
+        Ok((name, args)) => {
+            lines.push(format!("Name: `{name}`"));
+            lines.push(format!("Args: `{args:?}`"));
+            let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+            lines.push(parse_args(name, &args));
+        }
+        Err(_) => lines.push("❌ No name".to_string()),
+    }
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(lines.join("\n"))
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+fn describe_form_args(channel: Id<ChannelMarker>, required_role: Option<Id<RoleMarker>>) -> String {
+    required_role.map_or_else(
+        || format!("channel {channel}"),
+        |role| format!("channel {channel}, required role {role}"),
+    )
+}
+
+/// Run the arguments through the parser the handler for `name` uses.
+fn parse_args(name: &str, args: &[&str]) -> String {
+    let result = match name {
+        OPEN_FORM | OPEN_FORM_USER => OpenFormArgs::from_args(args)
+            .map(|args| describe_form_args(args.channel, args.required_role)),
+        "form_submit" => FormSubmitArgs::from_args(args).map(|args| {
+            let described = describe_form_args(args.channel, args.required_role);
+            match args.user {
+                Some(user) => format!("{described}, selected user {user}"),
+                None => described,
+            }
+        }),
+        CONFIRM_REPORT => <(Id<InteractionMarker>,)>::from_args(args)
+            .map(|(interaction,)| format!("interaction {interaction}")),
+        _ => return format!("❌ No handler for `{name}`"),
+    };
+    match result {
+        Ok(parsed) => format!("✅ Parsed as {parsed}"),
+        Err(e) => format!("❌ {e} ({e:?})"),
+    }
+}
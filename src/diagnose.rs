@@ -0,0 +1,133 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::{message::MessageFlags, Channel},
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, GuildMarker, RoleMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::{
+    builder::{embed::EmbedBuilder, InteractionResponseDataBuilder},
+    permission_calculator::PermissionCalculator,
+};
+
+use crate::{
+    extract::{ExtractGuildId, SlashCommand},
+    interact::InteractError,
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "diagnose",
+    desc = "List the permissions I'm missing in a channel",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct DiagnoseCommand {
+    /// The channel to check, like the button or modmail channel
+    channel: Id<ChannelMarker>,
+}
+
+impl DiagnoseCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+pub async fn diagnose(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    SlashCommand(cmd): SlashCommand<DiagnoseCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let permissions = channel_permissions(&state, guild_id, cmd.channel).await?;
+    let missing = state.required_permissions.difference(permissions);
+
+    let description = if missing.is_empty() {
+        "✅ I have every permission I need here.".to_string()
+    } else {
+        missing
+            .iter_names()
+            .map(|(name, _)| format!("❌ {}", permission_name(name)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let embed = EmbedBuilder::new()
+        .title("Permission check")
+        .description(format!("<#{}>\n{description}", cmd.channel))
+        .build();
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .embeds([embed])
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+/// Work out the bot's effective permissions in a channel from its roles and
+/// the channel's overwrites. There is no gateway cache, so this fetches
+/// everything it needs.
+async fn channel_permissions(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    channel_id: Id<ChannelMarker>,
+) -> Result<Permissions, InteractError> {
+    let channel = state.client.channel(channel_id).await?.model().await?;
+    if channel.guild_id != Some(guild_id) {
+        return Err(InteractError::ChannelNotInGuild);
+    }
+    let member = state
+        .client
+        .guild_member(guild_id, state.bot_id)
+        .await?
+        .model()
+        .await?;
+    member_permissions(state, guild_id, &channel, state.bot_id, &member.roles).await
+}
+
+/// Effective permissions of any member in `channel`, which has to be in
+/// `guild_id`.
+pub async fn member_permissions(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    channel: &Channel,
+    user_id: Id<UserMarker>,
+    user_roles: &[Id<RoleMarker>],
+) -> Result<Permissions, InteractError> {
+    let roles = state.client.roles(guild_id).await?.models().await?;
+
+    // The @everyone role has the same ID as the guild.
+    let everyone = roles
+        .iter()
+        .find(|role| role.id.cast() == guild_id)
+        .map_or_else(Permissions::empty, |role| role.permissions);
+    let member_roles: Vec<_> = roles
+        .iter()
+        .filter(|role| user_roles.contains(&role.id))
+        .map(|role| (role.id, role.permissions))
+        .collect();
+    let overwrites = channel.permission_overwrites.as_deref().unwrap_or_default();
+
+    Ok(
+        PermissionCalculator::new(guild_id, user_id, everyone, &member_roles)
+            .in_channel(channel.kind, overwrites),
+    )
+}
+
+/// Turn `SEND_MESSAGES` into `Send Messages`, like Discord shows it.
+fn permission_name(flag: &str) -> String {
+    flag.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_string() + &chars.as_str().to_lowercase()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,142 @@
+use twilight_http::request::channel::reaction::RequestReactionType;
+use twilight_model::{channel::message::EmojiReactionType, id::Id};
+
+/// Parse an emoji the way users type it into Discord: either a unicode emoji
+/// like `🚨`, or a custom emoji like `<:name:id>` or `<a:name:id>`.
+///
+/// Every place emoji are configured goes through this, so they all accept and
+/// reject the same things.
+pub fn parse_emoji(input: &str) -> Result<EmojiReactionType, EmojiError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(EmojiError::Empty);
+    }
+
+    if let Some(inner) = input.strip_prefix('<') {
+        let inner = inner.strip_suffix('>').ok_or(EmojiError::Malformed)?;
+        let (animated, rest) = match inner.strip_prefix("a:") {
+            Some(rest) => (true, rest),
+            None => (false, inner.strip_prefix(':').ok_or(EmojiError::Malformed)?),
+        };
+        let (name, id) = rest.split_once(':').ok_or(EmojiError::Malformed)?;
+        if !(2..=32).contains(&name.len())
+            || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        {
+            return Err(EmojiError::BadName);
+        }
+        let id = id
+            .parse::<u64>()
+            .ok()
+            .and_then(Id::new_checked)
+            .ok_or(EmojiError::BadId)?;
+        return Ok(EmojiReactionType::Custom {
+            animated,
+            id,
+            name: Some(name.to_string()),
+        });
+    }
+
+    // There's no cheap way to know the full list of unicode emoji, but they
+    // are all short, contain no whitespace, and have at least one character
+    // outside of ASCII (keycaps like 1️⃣ start with an ASCII digit).
+    if input.chars().count() > 16 || input.chars().any(char::is_whitespace) || input.is_ascii() {
+        return Err(EmojiError::NotEmoji);
+    }
+    Ok(EmojiReactionType::Unicode {
+        name: input.to_string(),
+    })
+}
+
+/// Borrow a parsed emoji in the form needed to react with it.
+pub fn request_reaction(emoji: &EmojiReactionType) -> RequestReactionType<'_> {
+    match emoji {
+        EmojiReactionType::Custom { id, name, .. } => RequestReactionType::Custom {
+            id: *id,
+            name: name.as_deref(),
+        },
+        EmojiReactionType::Unicode { name } => RequestReactionType::Unicode { name },
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum EmojiError {
+    #[error("No emoji was given")]
+    Empty,
+    #[error("Custom emoji must look like <:name:id> or <a:name:id>")]
+    Malformed,
+    #[error("Custom emoji names must be 2-32 letters, numbers or underscores")]
+    BadName,
+    #[error("Custom emoji ID is not a valid ID")]
+    BadId,
+    #[error("That doesn't look like an emoji")]
+    NotEmoji,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_unicode_emoji() {
+        for emoji in ["🚨", "1️⃣", "👩‍👩‍👧", " ⚠️ "] {
+            assert_eq!(
+                parse_emoji(emoji),
+                Ok(EmojiReactionType::Unicode {
+                    name: emoji.trim().to_string()
+                }),
+                "{emoji}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_custom_emoji() {
+        assert_eq!(
+            parse_emoji("<:ban_hammer:123>"),
+            Ok(EmojiReactionType::Custom {
+                animated: false,
+                id: Id::new(123),
+                name: Some("ban_hammer".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_emoji("<a:party:456>"),
+            Ok(EmojiReactionType::Custom {
+                animated: true,
+                id: Id::new(456),
+                name: Some("party".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_emoji() {
+        let cases = [
+            ("", EmojiError::Empty),
+            ("   ", EmojiError::Empty),
+            ("<:name:123", EmojiError::Malformed),
+            ("<name:123>", EmojiError::Malformed),
+            ("<b:name:123>", EmojiError::Malformed),
+            ("<:name>", EmojiError::Malformed),
+            ("<:a:123>", EmojiError::BadName),
+            (&format!("<:{}:123>", "a".repeat(33)), EmojiError::BadName),
+            ("<:bad-name:123>", EmojiError::BadName),
+            ("<:name:>", EmojiError::BadId),
+            ("<:name:0>", EmojiError::BadId),
+            ("<:name:abc>", EmojiError::BadId),
+            ("<:name:99999999999999999999>", EmojiError::BadId),
+            (":siren:", EmojiError::NotEmoji),
+            ("🚨 🚨", EmojiError::NotEmoji),
+            (&"🚨".repeat(17), EmojiError::NotEmoji),
+        ];
+        for (input, error) in cases {
+            assert_eq!(parse_emoji(input), Err(error), "{input}");
+        }
+    }
+
+    #[test]
+    fn custom_names_may_be_32_characters() {
+        let name = "a".repeat(32);
+        assert!(parse_emoji(&format!("<:{name}:123>")).is_ok());
+    }
+}
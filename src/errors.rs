@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{LazyLock, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::{Component, Embed, MessageFlags},
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::{
+    embed::{EmbedBuilder, EmbedFooterBuilder},
+    InteractionResponseDataBuilder,
+};
+
+use crate::{
+    extract::{CidArgs, CustomIdTooLong, SlashCommand},
+    interact::InteractError,
+    paginate::Page,
+};
+
+/// Errors shown to users since startup, by kind. [`ErrorReport`] records
+/// them, and it has no access to the app state, so this is a global.
+///
+/// [`ErrorReport`]: crate::interact::ErrorReport
+static SEEN: LazyLock<Mutex<HashMap<String, Seen>>> = LazyLock::new(Mutex::default);
+
+#[derive(Clone, Copy)]
+struct Seen {
+    count: u64,
+    /// Unix time in seconds.
+    last: u64,
+}
+
+/// Count an error under its type and variant, like `InteractError::Http`.
+/// Only the kind is kept, so nothing from the error itself is shown later.
+/// Returns the kind.
+pub fn record<T: Debug>(error: &T) -> String {
+    let type_name = std::any::type_name::<T>();
+    let type_name = type_name.rsplit("::").next().unwrap_or(type_name);
+    let debug = format!("{error:?}");
+    let variant: String = debug
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    let kind = if variant.is_empty() || variant == type_name {
+        type_name.to_string()
+    } else {
+        format!("{type_name}::{variant}")
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let mut seen = SEEN
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let entry = seen
+        .entry(kind.clone())
+        .or_insert(Seen { count: 0, last: 0 });
+    entry.count += 1;
+    entry.last = now;
+    drop(seen);
+    kind
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "errors",
+    desc = "Show which errors users ran into since the bot started",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct ErrorsCommand;
+
+impl ErrorsCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+/// How many kinds of errors a page lists, most recent first.
+const KINDS_PER_PAGE: usize = 20;
+
+/// Custom ID name of the Previous and Next buttons of `/errors`.
+pub const ERRORS_PAGE: &str = "errors_page";
+
+pub async fn errors(
+    SlashCommand(ErrorsCommand): SlashCommand<ErrorsCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let (embed, components) = page(&seen(), 0)?;
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .embeds([embed])
+        .components(components)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+/// Show another page of `/errors`. Errors are counted again, so it's up to
+/// date.
+pub async fn errors_page(
+    CidArgs((index,)): CidArgs<(usize,)>,
+) -> Result<InteractionResponse, InteractError> {
+    let (embed, components) = page(&seen(), index)?;
+    let data = InteractionResponseDataBuilder::new()
+        .embeds([embed])
+        .components(components)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(data),
+    })
+}
+
+/// Every kind of error seen, most recent first.
+fn seen() -> Vec<(String, Seen)> {
+    let mut seen: Vec<_> = SEEN
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .map(|(kind, seen)| (kind.clone(), *seen))
+        .collect();
+    seen.sort_unstable_by_key(|(_, seen)| std::cmp::Reverse(seen.last));
+    seen
+}
+
+/// Page `index` of `seen`, with buttons for the other pages if there are any.
+fn page(seen: &[(String, Seen)], index: usize) -> Result<(Embed, Vec<Component>), CustomIdTooLong> {
+    let embed = EmbedBuilder::new().title("Recent errors");
+    if seen.is_empty() {
+        let embed = embed.description("✅ No errors since the bot started.");
+        return Ok((embed.build(), Vec::new()));
+    }
+    let page = Page::new(seen, KINDS_PER_PAGE, index);
+    let lines: Vec<_> = page
+        .items
+        .iter()
+        .map(|(kind, seen)| format!("`{kind}` ×{}, last <t:{}:R>", seen.count, seen.last))
+        .collect();
+    let embed = embed.description(lines.join("\n"));
+    if page.count == 1 {
+        return Ok((embed.build(), Vec::new()));
+    }
+    let footer = EmbedFooterBuilder::new(page.footer());
+    Ok((
+        embed.footer(footer).build(),
+        vec![page.buttons(ERRORS_PAGE)?],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seen(kinds: usize) -> Vec<(String, Seen)> {
+        (0..kinds)
+            .map(|kind| (format!("Kind{kind}"), Seen { count: 1, last: 0 }))
+            .collect()
+    }
+
+    #[test]
+    fn lists_many_kinds_in_pages() {
+        let seen = seen(25);
+        let (embed, components) = page(&seen, 0).unwrap();
+        assert_eq!(embed.description.unwrap().lines().count(), KINDS_PER_PAGE);
+        assert_eq!(embed.footer.unwrap().text, "Page 1 of 2");
+        assert_eq!(components.len(), 1);
+
+        let (embed, _) = page(&seen, 1).unwrap();
+        let description = embed.description.unwrap();
+        assert_eq!(description.lines().count(), 5);
+        assert!(description.starts_with("`Kind20`"), "{description}");
+    }
+
+    #[test]
+    fn short_lists_have_no_buttons() {
+        let (embed, components) = page(&seen(3), 0).unwrap();
+        assert!(embed.footer.is_none());
+        assert!(components.is_empty());
+        let (embed, components) = page(&[], 0).unwrap();
+        assert_eq!(
+            embed.description.as_deref(),
+            Some("✅ No errors since the bot started.")
+        );
+        assert!(components.is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+use std::time::Duration;
+
+use twilight_model::{
+    channel::{message::AllowedMentions, Message},
+    id::{marker::ChannelMarker, Id},
+};
+
+use crate::{
+    config::FormConfig,
+    interact::{truncate, InteractError},
+    queue::is_transient,
+    resolve::{open_report, CONTENT_MAX_CHARS},
+    store::{self, OpenReport, Store},
+    AppState,
+};
+
+/// How often the worker looks for overdue reports.
+const INTERVAL: Duration = Duration::from_mins(1);
+
+/// Put in front of the content of reports which were escalated.
+pub const OVERDUE: &str = "⏰ Overdue\n";
+
+/// Keep track of a posted report, if its form escalates reports which are
+/// left open. Without a store, nothing is escalated.
+pub async fn track(
+    state: &AppState,
+    form: &FormConfig,
+    target_channel: Id<ChannelMarker>,
+    posted: &Message,
+) {
+    let (Some(store), Some(secs)) = (&state.store, form.escalate_after_secs) else {
+        return;
+    };
+    let due = store::now() + secs;
+    if let Err(e) = store
+        .track_report(posted.channel_id, posted.id, target_channel, due)
+        .await
+    {
+        tracing::error!("Failed to track report {} for escalation: {e:?}", posted.id);
+    }
+}
+
+/// Stop tracking a report once it's resolved. If this fails, the worker
+/// still sees that it's resolved when it's due.
+pub async fn untrack(state: &AppState, message: &Message) {
+    let Some(store) = &state.store else {
+        return;
+    };
+    if let Err(e) = store.untrack_report(message.channel_id, message.id).await {
+        tracing::warn!("Failed to stop tracking report {}: {e:?}", message.id);
+    }
+}
+
+/// Escalate overdue reports every [`INTERVAL`], forever.
+pub async fn run(state: AppState, store: Store) {
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        interval.tick().await;
+        escalate_overdue(&state, &store, store::now()).await;
+    }
+}
+
+/// Escalate every report which is due at `now`.
+async fn escalate_overdue(state: &AppState, store: &Store, now: u64) {
+    let overdue = match store.overdue_reports(now).await {
+        Ok(overdue) => overdue,
+        Err(e) => {
+            tracing::error!("Failed to look for overdue reports: {e:?}");
+            return;
+        }
+    };
+    for report in overdue {
+        match escalate(state, &report).await {
+            Err(e) if is_transient(&e) => {
+                tracing::warn!(
+                    "Failed to escalate report {}, retrying later: {e:?}",
+                    report.message()
+                );
+                continue;
+            }
+            Err(e) => tracing::error!("Failed to escalate report {}: {e:?}", report.message()),
+            Ok(()) => {}
+        }
+        // Every report is escalated once, and resolved or deleted ones not at
+        // all.
+        if let Err(e) = store
+            .untrack_report(report.channel(), report.message())
+            .await
+        {
+            tracing::error!("Failed to stop tracking report {}: {e:?}", report.message());
+        }
+    }
+}
+
+/// Mark a report which is still open as overdue, and ping the form's
+/// escalation role about it.
+async fn escalate(state: &AppState, report: &OpenReport) -> Result<(), InteractError> {
+    let message = state
+        .client
+        .message(report.channel(), report.message())
+        .await?
+        .model()
+        .await?;
+    if open_report(&message).is_none() {
+        return Ok(());
+    }
+    let form = state.forms.get(report.target_channel());
+    // The config may have changed since the report was posted.
+    let Some(role) = form.escalation_role.or(form.ping_role) else {
+        return Ok(());
+    };
+
+    // Retries after failing to ping shouldn't mark it twice.
+    if !message.content.starts_with(OVERDUE) {
+        let content = truncate(
+            format!("{OVERDUE}{}", message.content),
+            CONTENT_MAX_CHARS,
+            "…",
+        );
+        state
+            .client
+            .update_message(message.channel_id, message.id)
+            .content(Some(&content))
+            .allowed_mentions(Some(&AllowedMentions::default()))
+            .await?;
+    }
+    let allowed_mentions = AllowedMentions {
+        roles: vec![role],
+        ..AllowedMentions::default()
+    };
+    state
+        .client
+        .create_message(message.channel_id)
+        .content(&format!(
+            "<@&{role}> ⏰ This report from <t:{}:R> is still open.",
+            message.timestamp.as_secs()
+        ))
+        .reply(message.id)
+        .allowed_mentions(Some(&allowed_mentions))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+    use twilight_model::channel::Channel;
+
+    use super::*;
+    use crate::{
+        interact::{post_report, ModmailFormModal},
+        resolve::mark_resolved,
+        testing::{self, MockDiscord},
+    };
+
+    const MODMAIL: u64 = 60;
+    const REPORT: u64 = 70;
+    const MOD_ROLE: u64 = 7;
+    const AFTER_SECS: u64 = 600;
+
+    #[derive(Clone, Copy)]
+    enum Report {
+        Open,
+        Resolved,
+        Unavailable,
+    }
+
+    /// The report as Discord returns it, with its "Resolve" button.
+    fn report_message(content: &str, resolved: bool) -> Value {
+        let mut message = testing::message(MODMAIL, REPORT);
+        message["content"] = json!(content);
+        message["components"] = json!([{
+            "type": 1,
+            "components": [{
+                "type": 2,
+                "style": 3,
+                "custom_id": "resolve_report:30",
+                "label": "Resolve",
+                "disabled": resolved,
+            }],
+        }]);
+        message
+    }
+
+    async fn discord(report: Report) -> MockDiscord {
+        MockDiscord::start(
+            move |request| match (&request.method, request.path.as_str(), report) {
+                (&Method::GET, "/channels/60/messages/70", Report::Unavailable) => {
+                    (StatusCode::INTERNAL_SERVER_ERROR, json!({}))
+                }
+                (&Method::GET, "/channels/60/messages/70", _) => (
+                    StatusCode::OK,
+                    report_message("Report from <@30>", matches!(report, Report::Resolved)),
+                ),
+                (_, "/channels/60/messages" | "/channels/60/messages/70", _) => {
+                    (StatusCode::OK, testing::message(MODMAIL, REPORT))
+                }
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            },
+        )
+        .await
+    }
+
+    /// A state which escalates reports to [`MODMAIL`], with one posted.
+    async fn posted(discord: &MockDiscord) -> (AppState, Store) {
+        let store = testing::store().await;
+        let form = FormConfig {
+            resolve_button: true,
+            ping_role: Some(Id::new(MOD_ROLE)),
+            escalate_after_secs: Some(AFTER_SECS),
+            ..FormConfig::default()
+        };
+        let state = AppState {
+            store: Some(store.clone()),
+            forms: testing::forms(Id::new(MODMAIL), form),
+            ..discord.state()
+        };
+        let report = ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: None,
+            channel: None,
+            reason: "Being rude".to_string(),
+            custom: BTreeMap::new(),
+        };
+        post_report(
+            &state,
+            Some(Id::new(testing::GUILD)),
+            Id::new(30),
+            Id::new(MODMAIL),
+            report,
+        )
+        .await
+        .unwrap();
+        (state, store)
+    }
+
+    fn later() -> u64 {
+        store::now() + AFTER_SECS + 1
+    }
+
+    #[test]
+    fn escalates_reports_left_open_once() {
+        testing::runtime().block_on(async {
+            let discord = discord(Report::Open).await;
+            let (state, store) = posted(&discord).await;
+
+            escalate_overdue(&state, &store, store::now() + AFTER_SECS - 10).await;
+            assert!(discord
+                .requests_to(&Method::GET, "/channels/60/messages/70")
+                .is_empty());
+
+            escalate_overdue(&state, &store, later()).await;
+            let edits = discord.requests_to(&Method::PATCH, "/channels/60/messages/70");
+            assert_eq!(edits.len(), 1);
+            assert_eq!(edits[0].body["content"], "⏰ Overdue\nReport from <@30>");
+            let posts = discord.requests_to(&Method::POST, "/channels/60/messages");
+            assert_eq!(posts.len(), 2);
+            let ping = &posts[1].body;
+            let content = ping["content"].as_str().unwrap();
+            assert!(
+                content.starts_with("<@&7> ⏰ This report from <t:"),
+                "{content}"
+            );
+            assert_eq!(ping["allowed_mentions"]["roles"], json!(["7"]));
+            assert_eq!(ping["message_reference"]["message_id"], "70");
+
+            escalate_overdue(&state, &store, later() + AFTER_SECS).await;
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages")
+                    .len(),
+                2
+            );
+        });
+    }
+
+    #[test]
+    fn leaves_resolved_reports_alone() {
+        testing::runtime().block_on(async {
+            let discord = discord(Report::Resolved).await;
+            let (state, store) = posted(&discord).await;
+            escalate_overdue(&state, &store, later()).await;
+            assert!(discord
+                .requests_to(&Method::PATCH, "/channels/60/messages/70")
+                .is_empty());
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages")
+                    .len(),
+                1
+            );
+            assert!(store.overdue_reports(later()).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn tries_again_while_discord_is_unavailable() {
+        testing::runtime().block_on(async {
+            let discord = discord(Report::Unavailable).await;
+            let (state, store) = posted(&discord).await;
+            escalate_overdue(&state, &store, later()).await;
+            assert_eq!(store.overdue_reports(later()).await.unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn resolving_stops_escalation_and_clears_the_mark() {
+        testing::runtime().block_on(async {
+            let discord = discord(Report::Open).await;
+            let (state, store) = posted(&discord).await;
+            let channel: Channel =
+                serde_json::from_value(testing::channel(MODMAIL, Some(Id::new(testing::GUILD)), 0))
+                    .unwrap();
+            let message: Message =
+                serde_json::from_value(report_message("⏰ Overdue\nReport from <@30>", false))
+                    .unwrap();
+            mark_resolved(&state, Id::new(31), &channel, &message, Id::new(30), None)
+                .await
+                .unwrap();
+            let edits = discord.requests_to(&Method::PATCH, "/channels/60/messages/70");
+            assert_eq!(
+                edits[0].body["content"],
+                "✅ Resolved by <@31>\nReport from <@30>"
+            );
+            assert!(store.overdue_reports(later()).await.unwrap().is_empty());
+        });
+    }
+}
@@ -1,31 +1,113 @@
-use std::{cmp::Ordering, str::FromStr};
+use std::{
+    borrow::Cow, cmp::Ordering, collections::HashMap, convert::Infallible, fmt::Display,
+    str::FromStr, sync::Arc,
+};
 
 use niloecl::{FromRequest, IntoResponse};
 use twilight_interactions::command::CommandModel;
 use twilight_model::{
-    application::interaction::{Interaction, InteractionData, InteractionType},
+    application::interaction::{
+        message_component::MessageComponentInteractionData, Interaction, InteractionData,
+        InteractionType,
+    },
+    channel::{Channel, Message},
     guild::PartialMember,
-    id::{marker::UserMarker, Id},
+    id::{
+        marker::{GuildMarker, InteractionMarker, UserMarker},
+        Id,
+    },
     user::User,
 };
 
-use crate::interact::ErrorReport;
+use crate::{interact::ErrorReport, messages::Messages, AppState};
 
+#[derive(Debug)]
 pub struct NoNameInRpc;
 
-fn get_custom_id_rpc(custom_id: &str) -> Result<(&str, Vec<&str>), NoNameInRpc> {
+pub fn get_custom_id_rpc(custom_id: &str) -> Result<(&str, Vec<Cow<'_, str>>), NoNameInRpc> {
     let mut items_iter = custom_id.split(':');
     let name = items_iter.next().ok_or(NoNameInRpc)?;
-    let args = items_iter.collect();
+    let args = items_iter.map(decode_cid_arg).collect();
     Ok((name, args))
 }
 
+/// Discord's limit on the length of a custom ID.
+pub const CUSTOM_ID_MAX_CHARS: usize = 100;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Custom ID {0:?} is longer than Discord's limit of {CUSTOM_ID_MAX_CHARS} characters")]
+pub struct CustomIdTooLong(String);
+
+/// Build a custom ID for [`get_custom_id_rpc`] and [`CidArgs`] to take apart
+/// again. Colons in arguments are escaped, so any argument comes back as it
+/// was.
+pub fn custom_id(name: &str, args: &[&dyn Display]) -> Result<String, CustomIdTooLong> {
+    let mut custom_id = name.to_string();
+    for arg in args {
+        custom_id.push(':');
+        custom_id.push_str(&encode_cid_arg(&arg.to_string()));
+    }
+    if custom_id.chars().count() > CUSTOM_ID_MAX_CHARS {
+        return Err(CustomIdTooLong(custom_id));
+    }
+    Ok(custom_id)
+}
+
+/// Percent-encode the separator, and `%` itself so it can't be mistaken for
+/// an escape.
+fn encode_cid_arg(arg: &str) -> Cow<'_, str> {
+    if !arg.contains([':', '%']) {
+        return Cow::Borrowed(arg);
+    }
+    Cow::Owned(arg.replace('%', "%25").replace(':', "%3A"))
+}
+
+/// Undo [`encode_cid_arg`]. Anything else after a `%` is left alone, so older
+/// custom IDs keep working.
+fn decode_cid_arg(arg: &str) -> Cow<'_, str> {
+    if !arg.contains('%') {
+        return Cow::Borrowed(arg);
+    }
+    let mut decoded = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.find('%') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let escape = rest.get(..3);
+        if escape.is_some_and(|escape| escape.eq_ignore_ascii_case("%3A")) {
+            decoded.push(':');
+            rest = &rest[3..];
+        } else if escape == Some("%25") {
+            decoded.push('%');
+            rest = &rest[3..];
+        } else {
+            decoded.push('%');
+            rest = &rest[1..];
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// The name part of a component or modal custom ID, for routing interactions.
+pub fn custom_id_name(interaction: &Interaction) -> Option<&str> {
+    let custom_id = match interaction.data.as_ref()? {
+        InteractionData::MessageComponent(mc) => &mc.custom_id,
+        InteractionData::ModalSubmit(ms) => &ms.custom_id,
+        _ => return None,
+    };
+    get_custom_id_rpc(custom_id).ok().map(|(name, _)| name)
+}
+
 pub struct SlashCommand<T: CommandModel>(pub T);
 
-impl<T: CommandModel, S: Send + Sync> FromRequest<S> for SlashCommand<T> {
+impl<T: CommandModel> FromRequest<AppState> for SlashCommand<T> {
     type Rejection = SlashCommandRejection;
 
-    async fn from_request(req: &mut Interaction, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request(
+        req: &mut Interaction,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
         let Some(data) = &req.data else {
             return Err(SlashCommandRejection::NoInteractionData);
         };
@@ -33,8 +115,20 @@ impl<T: CommandModel, S: Send + Sync> FromRequest<S> for SlashCommand<T> {
             return Err(SlashCommandRejection::WrongInteractionData(req.kind));
         };
         CommandModel::from_interaction((**data).clone().into())
-            .map_err(Into::into)
             .map(SlashCommand)
+            .map_err(|source| {
+                // Parse errors are written for developers, so users only get
+                // the details in dev mode. They are always logged.
+                let mut message = state
+                    .messages
+                    .get(req.guild_id, req.locale.as_deref())
+                    .command_parse_failed
+                    .clone();
+                if state.dev {
+                    message = format!("{message}\n```{source}```");
+                }
+                SlashCommandRejection::CommandParse { source, message }
+            })
     }
 }
 
@@ -44,8 +138,11 @@ pub enum SlashCommandRejection {
     WrongInteractionData(InteractionType),
     #[error("No interaction data")]
     NoInteractionData,
-    #[error("Arguments could not be parsed")]
-    CommandParse(#[from] twilight_interactions::error::ParseError),
+    #[error("{message}")]
+    CommandParse {
+        source: twilight_interactions::error::ParseError,
+        message: String,
+    },
 }
 
 impl IntoResponse for SlashCommandRejection {
@@ -54,6 +151,43 @@ impl IntoResponse for SlashCommandRejection {
     }
 }
 
+pub struct InteractionToken(pub String);
+
+impl<S: Sync> FromRequest<S> for InteractionToken {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut Interaction, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(req.token.clone()))
+    }
+}
+
+pub struct InteractionId(pub Id<InteractionMarker>);
+
+impl<S: Sync> FromRequest<S> for InteractionId {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut Interaction, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(req.id))
+    }
+}
+
+/// The messages in the language to answer this interaction in, see
+/// [`crate::messages::Languages::resolve_language`].
+pub struct Localized(pub Arc<Messages>);
+
+impl FromRequest<AppState> for Localized {
+    type Rejection = Infallible;
+
+    async fn from_request(
+        req: &mut Interaction,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(
+            state.messages.get(req.guild_id, req.locale.as_deref()),
+        ))
+    }
+}
+
 pub struct ExtractMember(pub PartialMember);
 
 impl<S: Sync> FromRequest<S> for ExtractMember {
@@ -74,6 +208,89 @@ impl IntoResponse for ExtractMemberError {
     }
 }
 
+/// The message a component is on.
+pub struct ExtractMessage(pub Message);
+
+impl<S: Sync> FromRequest<S> for ExtractMessage {
+    type Rejection = ExtractMessageError;
+
+    async fn from_request(req: &mut Interaction, _: &S) -> Result<Self, Self::Rejection> {
+        req.message.take().map(Self).ok_or(ExtractMessageError)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Discord did not send a message on this interaction")]
+pub struct ExtractMessageError;
+
+impl IntoResponse for ExtractMessageError {
+    fn into_response(self) -> twilight_model::http::interaction::InteractionResponse {
+        ErrorReport(self).into_response()
+    }
+}
+
+/// The channel the interaction was used in.
+pub struct ExtractChannel(pub Channel);
+
+impl<S: Sync> FromRequest<S> for ExtractChannel {
+    type Rejection = ExtractChannelError;
+
+    async fn from_request(req: &mut Interaction, _: &S) -> Result<Self, Self::Rejection> {
+        req.channel.take().map(Self).ok_or(ExtractChannelError)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Discord did not send a channel on this interaction")]
+pub struct ExtractChannelError;
+
+impl IntoResponse for ExtractChannelError {
+    fn into_response(self) -> twilight_model::http::interaction::InteractionResponse {
+        ErrorReport(self).into_response()
+    }
+}
+
+pub struct ExtractGuildId(pub Id<GuildMarker>);
+
+impl<S: Sync> FromRequest<S> for ExtractGuildId {
+    type Rejection = ExtractGuildIdError;
+
+    async fn from_request(req: &mut Interaction, _: &S) -> Result<Self, Self::Rejection> {
+        req.guild_id.map(Self).ok_or(ExtractGuildIdError)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Discord did not send a guild ID on this interaction")]
+pub struct ExtractGuildIdError;
+
+impl IntoResponse for ExtractGuildIdError {
+    fn into_response(self) -> twilight_model::http::interaction::InteractionResponse {
+        ErrorReport(self).into_response()
+    }
+}
+
+/// The user who triggered the interaction, both in guilds and in DMs.
+pub struct ExtractUserId(pub Id<UserMarker>);
+
+impl<S: Sync> FromRequest<S> for ExtractUserId {
+    type Rejection = ExtractUserIdError;
+
+    async fn from_request(req: &mut Interaction, _: &S) -> Result<Self, Self::Rejection> {
+        req.author_id().map(Self).ok_or(ExtractUserIdError)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Discord did not send a user on this interaction")]
+pub struct ExtractUserIdError;
+
+impl IntoResponse for ExtractUserIdError {
+    fn into_response(self) -> twilight_model::http::interaction::InteractionResponse {
+        ErrorReport(self).into_response()
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct UserSelectMenu(pub Vec<User>);
 
@@ -81,31 +298,40 @@ impl<S: Sync> FromRequest<S> for UserSelectMenu {
     type Rejection = SelectMenuRejection;
 
     async fn from_request(req: &mut Interaction, _state: &S) -> Result<Self, Self::Rejection> {
-        let Some(data) = &req.data else {
-            return Err(SelectMenuRejection::NoInteractionData);
-        };
-        let InteractionData::MessageComponent(data) = data else {
-            return Err(SelectMenuRejection::WrongInteractionData(req.kind));
-        };
-
-        let mut users = Vec::with_capacity(data.values.len());
-
-        let Some(resolved) = &data.resolved else {
-            return Err(SelectMenuRejection::NoResolvedData);
-        };
-
-        for item in &data.values {
-            let id: Id<UserMarker> = item.parse()?;
-            let Some(user) = resolved.users.get(&id).cloned() else {
-                return Err(SelectMenuRejection::UnknownId(id));
-            };
-            users.push(user);
-        }
+        let data = select_data(req)?;
+        let resolved = data
+            .resolved
+            .as_ref()
+            .ok_or(SelectMenuRejection::NoResolvedData)?;
+        resolve_values(&data.values, &resolved.users).map(Self)
+    }
+}
 
-        Ok(Self(users))
+fn select_data(req: &Interaction) -> Result<&MessageComponentInteractionData, SelectMenuRejection> {
+    match &req.data {
+        Some(InteractionData::MessageComponent(data)) => Ok(data),
+        Some(_) => Err(SelectMenuRejection::WrongInteractionData(req.kind)),
+        None => Err(SelectMenuRejection::NoInteractionData),
     }
 }
 
+/// Look up each selected ID in the matching map of resolved data.
+fn resolve_values<M, T: Clone>(
+    values: &[String],
+    resolved: &HashMap<Id<M>, T>,
+) -> Result<Vec<T>, SelectMenuRejection> {
+    values
+        .iter()
+        .map(|value| {
+            let id: Id<M> = value.parse()?;
+            resolved
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| SelectMenuRejection::UnknownId(id.get()))
+        })
+        .collect()
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SelectMenuRejection {
     #[error("Wrong type of interaction data")]
@@ -114,8 +340,8 @@ pub enum SelectMenuRejection {
     NoInteractionData,
     #[error("No interaction resolved data")]
     NoResolvedData,
-    #[error("User <@{0}> not found")]
-    UnknownId(Id<UserMarker>),
+    #[error("Selected item {0} not found")]
+    UnknownId(u64),
     #[error("Arguments could not be parsed")]
     IdParse(#[from] std::num::ParseIntError),
 }
@@ -132,20 +358,40 @@ impl<T: FromCidArgs, S: Sync> FromRequest<S> for CidArgs<T> {
     type Rejection = FromCidArgsRejection;
 
     async fn from_request(req: &mut Interaction, _state: &S) -> Result<Self, Self::Rejection> {
-        let Some(data) = &req.data else {
-            return Err(FromCidArgsRejection::NoInteractionData);
-        };
-        let id_str = match data {
-            InteractionData::MessageComponent(mc) => &mc.custom_id,
-            InteractionData::ModalSubmit(ms) => &ms.custom_id,
-            _ => return Err(FromCidArgsRejection::WrongInteractionData(req.kind)),
-        };
-        let (_name, args) =
-            get_custom_id_rpc(id_str).map_err(|_| FromCidArgsRejection::NoDataName)?;
+        let args = custom_id_args(req)?;
+        let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
         T::from_args(&args).map(CidArgs).map_err(Into::into)
     }
 }
 
+/// Like [`CidArgs`], but the arguments are `key=value` pairs in any order, so
+/// arguments can be added or left out without shifting the others.
+pub struct CidNamed<T: FromCidNamed>(pub T);
+
+impl<T: FromCidNamed, S: Sync> FromRequest<S> for CidNamed<T> {
+    type Rejection = FromCidArgsRejection;
+
+    async fn from_request(req: &mut Interaction, _state: &S) -> Result<Self, Self::Rejection> {
+        let args = custom_id_args(req)?;
+        let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+        let args = NamedArgs::parse(&args)?;
+        T::from_named(&args).map(CidNamed).map_err(Into::into)
+    }
+}
+
+fn custom_id_args(req: &Interaction) -> Result<Vec<Cow<'_, str>>, FromCidArgsRejection> {
+    let Some(data) = &req.data else {
+        return Err(FromCidArgsRejection::NoInteractionData);
+    };
+    let id_str = match data {
+        InteractionData::MessageComponent(mc) => &mc.custom_id,
+        InteractionData::ModalSubmit(ms) => &ms.custom_id,
+        _ => return Err(FromCidArgsRejection::WrongInteractionData(req.kind)),
+    };
+    let (_name, args) = get_custom_id_rpc(id_str).map_err(|_| FromCidArgsRejection::NoDataName)?;
+    Ok(args)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum FromCidArgsRejection {
     #[error("Wrong type of interaction data")]
@@ -156,6 +402,8 @@ pub enum FromCidArgsRejection {
     NoDataName,
     #[error("Arguments could not be parsed")]
     ArgParse(#[from] FromCidArgsError),
+    #[error("Named arguments could not be parsed")]
+    NamedArgParse(#[from] FromCidNamedError),
 }
 
 impl IntoResponse for FromCidArgsRejection {
@@ -168,26 +416,76 @@ pub trait FromCidArgs: Sized {
     fn from_args(args: &[&str]) -> Result<Self, FromCidArgsError>;
 }
 
+/// One element of a [`FromCidArgs`] tuple: a single argument parsed with
+/// [`FromStr`], or a [`CidRest`] taking all that are left.
+pub trait CidArg: Sized {
+    /// Whether it takes all the remaining arguments instead of one.
+    const REST: bool = false;
+
+    fn from_cid_args(args: &[&str]) -> Result<Self, FromCidArgsError>;
+}
+
+impl<T> CidArg for T
+where
+    T: FromStr,
+    T::Err: std::error::Error + 'static,
+{
+    fn from_cid_args(args: &[&str]) -> Result<Self, FromCidArgsError> {
+        match args {
+            [arg] => parse_cid_arg(arg),
+            _ => Err(FromCidArgsError::UnconvertibleArgs(
+                "impossible arg state".into(),
+            )),
+        }
+    }
+}
+
+/// Collects all remaining arguments, as the last element of a [`CidArgs`]
+/// tuple. The arguments before it are still required.
+pub struct CidRest<T>(pub Vec<T>);
+
+impl<T> CidArg for CidRest<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + 'static,
+{
+    const REST: bool = true;
+
+    fn from_cid_args(args: &[&str]) -> Result<Self, FromCidArgsError> {
+        args.iter()
+            .map(|arg| parse_cid_arg(arg))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Parse the next element of a tuple off the front of `args`.
+fn take_cid_arg<T: CidArg>(args: &mut &[&str]) -> Result<T, FromCidArgsError> {
+    let (taken, rest) = args.split_at(if T::REST { args.len() } else { 1 });
+    *args = rest;
+    T::from_cid_args(taken)
+}
+
 macro_rules! impl_from_cid_args {
     ($($ty:ident),*) => {
         impl<$($ty,)*> FromCidArgs for ($($ty,)*)
         where
-            $($ty: FromStr,)*
-            $($ty::Err: std::error::Error + 'static,)*
+            $($ty: CidArg,)*
         {
-            fn from_args(args: &[&str]) -> Result<Self, FromCidArgsError> {
-                // stringify just serves to "use" the type. The string is unused.
-                let arg_count = 0 $(+ { stringify!($ty); 1 })*;
+            fn from_args(mut args: &[&str]) -> Result<Self, FromCidArgsError> {
+                let rest = [$($ty::REST,)*];
+                let (&ends_in_rest, leading) = rest.split_last().unwrap_or((&false, &[]));
+                if leading.contains(&true) {
+                    return Err(FromCidArgsError::RestNotLast);
+                }
+                // A trailing CidRest takes any number, the others one each.
+                let arg_count = leading.len() + usize::from(!ends_in_rest);
                 match args.len().cmp(&arg_count) {
                     Ordering::Less => return Err(FromCidArgsError::RequiredCustomIdArgMissing(arg_count - args.len())),
-                    Ordering::Equal => {},
-                    Ordering::Greater => return Err(FromCidArgsError::ExtraCustomIdArgs(arg_count, args.len())),
-                }
-                match args {
-                    #[allow(non_snake_case)]
-                    [$($ty,)*] => Ok(($($ty::from_str($ty).map_err(|e| FromCidArgsError::UnconvertibleArgs(Box::new(e)))?,)*)),
-                    _ => return Err(FromCidArgsError::UnconvertibleArgs("impossible arg state".into()))
+                    Ordering::Greater if !ends_in_rest => return Err(FromCidArgsError::ExtraCustomIdArgs(arg_count, args.len())),
+                    _ => {},
                 }
+                Ok(($(take_cid_arg::<$ty>(&mut args)?,)*))
             }
         }
     };
@@ -201,6 +499,18 @@ pub enum FromCidArgsError {
     RequiredCustomIdArgMissing(usize),
     #[error("Got wrong number of arguments: {0}, expected {1}")]
     ExtraCustomIdArgs(usize, usize),
+    #[error("Only the last custom ID argument can collect the remaining ones. this is a bug")]
+    RestNotLast,
+}
+
+/// Parse a single custom ID argument, for [`FromCidArgs`] impls which don't
+/// take a fixed number of arguments.
+pub fn parse_cid_arg<T>(arg: &str) -> Result<T, FromCidArgsError>
+where
+    T: FromStr,
+    T::Err: std::error::Error + 'static,
+{
+    T::from_str(arg).map_err(|e| FromCidArgsError::UnconvertibleArgs(Box::new(e)))
 }
 
 impl_from_cid_args!(T1);
@@ -213,3 +523,276 @@ impl_from_cid_args!(T1, T2, T3, T4, T5, T6, T7);
 impl_from_cid_args!(T1, T2, T3, T4, T5, T6, T7, T8);
 impl_from_cid_args!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
 impl_from_cid_args!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+
+/// Read a struct out of [`NamedArgs`], for [`CidNamed`].
+pub trait FromCidNamed: Sized {
+    fn from_named(args: &NamedArgs<'_>) -> Result<Self, FromCidNamedError>;
+}
+
+/// The `key=value` arguments of a custom ID. Keys nobody asks for are
+/// ignored, so older code can still read custom IDs with new arguments.
+pub struct NamedArgs<'a>(HashMap<&'a str, &'a str>);
+
+impl<'a> NamedArgs<'a> {
+    pub fn parse(args: &[&'a str]) -> Result<Self, FromCidNamedError> {
+        let mut named = HashMap::with_capacity(args.len());
+        for arg in args {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| FromCidNamedError::NotKeyValue((*arg).to_string()))?;
+            if named.insert(key, value).is_some() {
+                return Err(FromCidNamedError::DuplicateKey(key.to_string()));
+            }
+        }
+        Ok(Self(named))
+    }
+
+    pub fn required<T>(&self, key: &'static str) -> Result<T, FromCidNamedError>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        self.optional(key)?
+            .ok_or(FromCidNamedError::MissingKey(key))
+    }
+
+    pub fn optional<T>(&self, key: &'static str) -> Result<Option<T>, FromCidNamedError>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        self.0
+            .get(key)
+            .map(|value| {
+                T::from_str(value)
+                    .map_err(|e| FromCidNamedError::UnconvertibleValue(key, Box::new(e)))
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FromCidNamedError {
+    #[error("Custom ID argument {0:?} is not a key=value pair")]
+    NotKeyValue(String),
+    #[error("Custom ID argument {0:?} is given more than once")]
+    DuplicateKey(String),
+    #[error("Missing custom ID argument {0:?}")]
+    MissingKey(&'static str),
+    #[error("Custom ID argument {0:?} was not convertible: {1}")]
+    UnconvertibleValue(&'static str, Box<dyn std::error::Error + 'static>),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing;
+
+    /// A select menu of `component_type` with these values picked, like
+    /// Discord sends them.
+    fn select(component_type: u8, values: &[&str], resolved: &Value) -> Interaction {
+        testing::interaction(json!({
+            "type": 3,
+            "data": {
+                "custom_id": "picker",
+                "component_type": component_type,
+                "values": values,
+                "resolved": resolved,
+            },
+        }))
+    }
+
+    fn extract<T: FromRequest<()>>(mut interaction: Interaction) -> Result<T, T::Rejection> {
+        testing::runtime().block_on(T::from_request(&mut interaction, &()))
+    }
+
+    #[test]
+    fn extracts_user_selects() {
+        let resolved = json!({ "users": { "40": testing::user_json(40) } });
+        let UserSelectMenu(users) = extract(select(5, &["40"], &resolved)).unwrap();
+        assert_eq!(users[0].id, Id::new(40));
+    }
+
+    #[test]
+    fn rejects_picks_without_resolved_data() {
+        let resolved = json!({ "users": {} });
+        assert!(matches!(
+            extract::<UserSelectMenu>(select(5, &["40"], &resolved)),
+            Err(SelectMenuRejection::UnknownId(40))
+        ));
+        assert!(matches!(
+            extract::<UserSelectMenu>(select(5, &["40"], &Value::Null)),
+            Err(SelectMenuRejection::NoResolvedData)
+        ));
+        assert!(matches!(
+            extract::<UserSelectMenu>(testing::command("setup", &json!([]))),
+            Err(SelectMenuRejection::WrongInteractionData(
+                InteractionType::ApplicationCommand
+            ))
+        ));
+    }
+
+    #[test]
+    fn round_trips_args_with_colons_and_percent_signs() {
+        let args = ["a:b", "100%", "%3A", "::", "50%:off", ""];
+        let display: Vec<&dyn Display> = args.iter().map(|arg| arg as &dyn Display).collect();
+        let id = custom_id("name", &display).unwrap();
+        assert_eq!(id, "name:a%3Ab:100%25:%253A:%3A%3A:50%25%3Aoff:");
+        let (name, parsed) = get_custom_id_rpc(&id).unwrap();
+        assert_eq!(name, "name");
+        assert_eq!(parsed, args);
+
+        let CidArgs((first, second)) = extract::<CidArgs<(String, String)>>(testing::button(
+            &custom_id("name", &[&"a:b", &"100%"]).unwrap(),
+        ))
+        .unwrap();
+        assert_eq!((first.as_str(), second.as_str()), ("a:b", "100%"));
+    }
+
+    fn args<T: FromCidArgs>(id: &str) -> Result<T, FromCidArgsRejection> {
+        extract::<CidArgs<T>>(testing::button(id)).map(|CidArgs(args)| args)
+    }
+
+    #[test]
+    fn collects_any_number_of_trailing_args() {
+        let (channel, CidRest(roles)) = args::<(u64, CidRest<u64>)>("name:1").unwrap();
+        assert_eq!((channel, roles), (1, vec![]));
+        let (channel, CidRest(roles)) = args::<(u64, CidRest<u64>)>("name:1:2").unwrap();
+        assert_eq!((channel, roles), (1, vec![2]));
+        let (channel, CidRest(roles)) = args::<(u64, CidRest<u64>)>("name:1:2:3:4").unwrap();
+        assert_eq!((channel, roles), (1, vec![2, 3, 4]));
+        let (CidRest(all),) = args::<(CidRest<u64>,)>("name").unwrap();
+        assert!(all.is_empty());
+    }
+
+    #[test]
+    fn trailing_args_may_contain_colons() {
+        let id = custom_id("name", &[&1, &"a:b", &"c"]).unwrap();
+        let (first, CidRest(rest)) = args::<(u64, CidRest<String>)>(&id).unwrap();
+        assert_eq!(first, 1);
+        assert_eq!(rest, ["a:b", "c"]);
+    }
+
+    #[test]
+    fn still_checks_the_args_before_the_trailing_ones() {
+        assert!(matches!(
+            args::<(u64, u64, CidRest<u64>)>("name:1"),
+            Err(FromCidArgsRejection::ArgParse(
+                FromCidArgsError::RequiredCustomIdArgMissing(1)
+            ))
+        ));
+        assert!(matches!(
+            args::<(u64, CidRest<u64>)>("name:1:x"),
+            Err(FromCidArgsRejection::ArgParse(
+                FromCidArgsError::UnconvertibleArgs(_)
+            ))
+        ));
+        // Without a trailing collector, extra arguments are still an error.
+        assert!(matches!(
+            args::<(u64,)>("name:1:2"),
+            Err(FromCidArgsRejection::ArgParse(
+                FromCidArgsError::ExtraCustomIdArgs(1, 2)
+            ))
+        ));
+    }
+
+    #[test]
+    fn trailing_args_have_to_come_last() {
+        assert!(matches!(
+            args::<(CidRest<u64>, u64)>("name:1:2"),
+            Err(FromCidArgsRejection::ArgParse(
+                FromCidArgsError::RestNotLast
+            ))
+        ));
+        assert!(matches!(
+            args::<(u64, CidRest<u64>, CidRest<u64>)>("name:1:2"),
+            Err(FromCidArgsRejection::ArgParse(
+                FromCidArgsError::RestNotLast
+            ))
+        ));
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Named {
+        channel: u64,
+        role: Option<u64>,
+    }
+
+    impl FromCidNamed for Named {
+        fn from_named(args: &NamedArgs<'_>) -> Result<Self, FromCidNamedError> {
+            Ok(Self {
+                channel: args.required("channel")?,
+                role: args.optional("role")?,
+            })
+        }
+    }
+
+    fn named(id: &str) -> Result<Named, FromCidArgsRejection> {
+        extract::<CidNamed<Named>>(testing::button(id)).map(|CidNamed(named)| named)
+    }
+
+    #[test]
+    fn named_args_may_come_in_any_order() {
+        let expected = Named {
+            channel: 123,
+            role: Some(456),
+        };
+        assert_eq!(named("open_form:channel=123:role=456").unwrap(), expected);
+        assert_eq!(named("open_form:role=456:channel=123").unwrap(), expected);
+        // Unknown keys are for newer code, not an error.
+        assert_eq!(
+            named("open_form:role=456:page=2:channel=123").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn optional_named_args_may_be_left_out() {
+        assert_eq!(
+            named("open_form:channel=123").unwrap(),
+            Named {
+                channel: 123,
+                role: None,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_named_args() {
+        assert!(matches!(
+            named("open_form:role=456"),
+            Err(FromCidArgsRejection::NamedArgParse(
+                FromCidNamedError::MissingKey("channel")
+            ))
+        ));
+        assert!(matches!(
+            named("open_form:channel=123:channel=124"),
+            Err(FromCidArgsRejection::NamedArgParse(
+                FromCidNamedError::DuplicateKey(key)
+            )) if key == "channel"
+        ));
+        assert!(matches!(
+            named("open_form:channel=123:456"),
+            Err(FromCidArgsRejection::NamedArgParse(
+                FromCidNamedError::NotKeyValue(arg)
+            )) if arg == "456"
+        ));
+        assert!(matches!(
+            named("open_form:channel=general"),
+            Err(FromCidArgsRejection::NamedArgParse(
+                FromCidNamedError::UnconvertibleValue("channel", _)
+            ))
+        ));
+    }
+
+    #[test]
+    fn rejects_custom_ids_past_discords_limit() {
+        let fits = "a".repeat(CUSTOM_ID_MAX_CHARS - "name:".len());
+        assert!(custom_id("name", &[&fits]).is_ok());
+        // Escapes count too.
+        let escaped = format!("{}:", "a".repeat(CUSTOM_ID_MAX_CHARS - "name:".len() - 1));
+        assert!(custom_id("name", &[&escaped]).is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use std::{collections::HashMap, fmt::Write, sync::Mutex};
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CommandOption, CreateCommand, CreateOption};
+use twilight_model::{
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{ExtractGuildId, SlashCommand},
+    interact::InteractError,
+    store::Store,
+    AppState,
+};
+
+/// Switches of [`crate::config::FormConfig`] which servers may flip for
+/// every setup they have with `/features`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, CommandOption, CreateOption)]
+pub enum Feature {
+    #[option(name = "Report threads", value = "report_threads")]
+    ReportThreads,
+    #[option(name = "DM copy", value = "dm_copy")]
+    DmCopy,
+    #[option(name = "Confirm before submit", value = "confirm_before_submit")]
+    ConfirmBeforeSubmit,
+}
+
+impl Feature {
+    const ALL: [Self; 3] = [Self::ReportThreads, Self::DmCopy, Self::ConfirmBeforeSubmit];
+
+    /// The key in `[form]`, which is also how the store keeps it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ReportThreads => "report_threads",
+            Self::DmCopy => "dm_copy",
+            Self::ConfirmBeforeSubmit => "confirm_before_submit",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.name() == name)
+    }
+}
+
+/// Features servers turned on or off, overriding their setups. Changes are
+/// saved in the store if there is one, otherwise they only last until a
+/// restart.
+#[derive(Debug, Default)]
+pub struct Features(Mutex<HashMap<Id<GuildMarker>, HashMap<Feature, bool>>>);
+
+impl Features {
+    /// Features which aren't known anymore are skipped.
+    pub async fn load(store: Option<&Store>) -> Result<Self, sqlx::Error> {
+        let features = Self::default();
+        if let Some(store) = store {
+            for (guild, name, enabled) in store.feature_flags().await? {
+                if let Some(feature) = Feature::from_name(&name) {
+                    features.set(guild, feature, Some(enabled));
+                } else {
+                    tracing::warn!("Skipping unknown feature {name:?} of {guild}");
+                }
+            }
+        }
+        Ok(features)
+    }
+
+    /// Whether `feature` is on in `guild`: what the server chose with
+    /// `/features`, or `default` from the setup if it didn't.
+    pub fn enabled(&self, guild: Option<Id<GuildMarker>>, feature: Feature, default: bool) -> bool {
+        guild
+            .and_then(|guild| self.get(guild, feature))
+            .unwrap_or(default)
+    }
+
+    fn get(&self, guild: Id<GuildMarker>, feature: Feature) -> Option<bool> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&guild)?
+            .get(&feature)
+            .copied()
+    }
+
+    /// Turn `feature` on or off in `guild`, or leave it to the setups again
+    /// if `enabled` is `None`.
+    fn set(&self, guild: Id<GuildMarker>, feature: Feature, enabled: Option<bool>) {
+        let mut features = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let changed = features.entry(guild).or_default();
+        match enabled {
+            Some(enabled) => changed.insert(feature, enabled),
+            None => changed.remove(&feature),
+        };
+        drop(features);
+    }
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "features",
+    desc = "Turn features on or off for every setup in this server",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub enum FeaturesCommand {
+    #[command(name = "show")]
+    Show(FeaturesShow),
+    #[command(name = "set")]
+    Set(FeaturesSet),
+    #[command(name = "reset")]
+    Reset(FeaturesReset),
+}
+
+impl FeaturesCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "show",
+    desc = "Show which features this server turned on or off"
+)]
+pub struct FeaturesShow;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "set", desc = "Turn a feature on or off for every setup")]
+pub struct FeaturesSet {
+    /// The feature to change
+    feature: Feature,
+    /// Whether it's on
+    enabled: bool,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "reset", desc = "Let each setup decide about a feature again")]
+pub struct FeaturesReset {
+    /// The feature to reset
+    feature: Feature,
+}
+
+/// Features are changed in memory even if the store is down, like the
+/// blocklist, so the change applies right away either way.
+pub async fn features(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    SlashCommand(cmd): SlashCommand<FeaturesCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let (feature, enabled) = match cmd {
+        FeaturesCommand::Show(FeaturesShow) => return Ok(ephemeral(show(&state, guild_id))),
+        FeaturesCommand::Set(FeaturesSet { feature, enabled }) => (feature, Some(enabled)),
+        FeaturesCommand::Reset(FeaturesReset { feature }) => (feature, None),
+    };
+    let saved = match (&state.store, enabled) {
+        (None, _) => Ok(()),
+        (Some(store), Some(enabled)) => store.set_feature(guild_id, feature.name(), enabled).await,
+        (Some(store), None) => store.reset_feature(guild_id, feature.name()).await,
+    };
+    state.features.set(guild_id, feature, enabled);
+
+    let mut content = match enabled {
+        Some(true) => format!("Turned `{}` on for every setup.", feature.name()),
+        Some(false) => format!("Turned `{}` off for every setup.", feature.name()),
+        None => format!("Each setup decides about `{}` again.", feature.name()),
+    };
+    if state.store.is_none() {
+        content.push_str(
+            "\nFeatures aren't saved because `AGHAST_DB_PATH` isn't set, \
+             so this change is lost when the bot restarts.",
+        );
+    } else if let Err(e) = saved {
+        tracing::error!(
+            "Failed to save the {} feature of {guild_id}, the change only lasts until a \
+             restart: {e:?}",
+            feature.name()
+        );
+        content.push_str(
+            "\nSaving features is temporarily unavailable, \
+             so this change is lost if the bot restarts before you make it again.",
+        );
+    }
+    Ok(ephemeral(content))
+}
+
+/// Every feature, and whether the server turned it on or off.
+fn show(state: &AppState, guild_id: Id<GuildMarker>) -> String {
+    let mut content = "Features in this server:".to_string();
+    for feature in Feature::ALL {
+        let chosen = match state.features.get(guild_id, feature) {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "up to each setup",
+        };
+        let _ = write!(content, "\n`{}`: {chosen}", feature.name());
+    }
+    content
+}
+
+fn ephemeral(content: String) -> InteractionResponse {
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(content)
+        .build();
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::{
+        interact::handle_interaction,
+        testing::{self, MockDiscord},
+    };
+
+    fn features_command(subcommand: &str, options: &serde_json::Value) -> Interaction {
+        testing::command(
+            "features",
+            &json!([{ "name": subcommand, "type": 1, "options": options }]),
+        )
+    }
+
+    fn set(feature: &str, enabled: bool) -> Interaction {
+        features_command(
+            "set",
+            &json!([
+                { "name": "feature", "type": 3, "value": feature },
+                { "name": "enabled", "type": 5, "value": enabled },
+            ]),
+        )
+    }
+
+    fn reset(feature: &str) -> Interaction {
+        features_command(
+            "reset",
+            &json!([{ "name": "feature", "type": 3, "value": feature }]),
+        )
+    }
+
+    fn guild() -> Id<GuildMarker> {
+        Id::new(testing::GUILD)
+    }
+
+    #[test]
+    fn overrides_setups_in_that_server_only() {
+        let features = Features::default();
+        features.set(guild(), Feature::DmCopy, Some(true));
+        features.set(guild(), Feature::ReportThreads, Some(false));
+        assert!(features.enabled(Some(guild()), Feature::DmCopy, false));
+        assert!(!features.enabled(Some(guild()), Feature::ReportThreads, true));
+        assert!(features.enabled(Some(guild()), Feature::ConfirmBeforeSubmit, true));
+        assert!(!features.enabled(Some(Id::new(11)), Feature::DmCopy, false));
+        assert!(!features.enabled(None, Feature::DmCopy, false));
+    }
+
+    #[test]
+    fn starts_threads_once_turned_on() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(60, 70)),
+                "/channels/60/messages/70/threads" => (
+                    StatusCode::OK,
+                    testing::channel(70, Some(Id::new(testing::GUILD)), 11),
+                ),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = discord.state();
+            let submit = || {
+                testing::modal(
+                    "form_submit:60",
+                    &[
+                        ("user", "wumpus"),
+                        ("channel", "#general"),
+                        ("message_link", ""),
+                        ("reason", "Being rude"),
+                    ],
+                )
+            };
+            let threads = || {
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages/70/threads")
+                    .len()
+            };
+
+            let response = Box::pin(handle_interaction(
+                state.clone(),
+                set("report_threads", true),
+            ))
+            .await;
+            let content = testing::content(&response).unwrap();
+            assert!(
+                content.starts_with("Turned `report_threads` on for every setup."),
+                "{content}"
+            );
+            Box::pin(handle_interaction(state.clone(), submit())).await;
+            assert_eq!(threads(), 1);
+
+            Box::pin(handle_interaction(state.clone(), reset("report_threads"))).await;
+            Box::pin(handle_interaction(state, submit())).await;
+            assert_eq!(threads(), 1);
+        });
+    }
+
+    #[test]
+    fn shows_what_the_server_chose() {
+        testing::runtime().block_on(async {
+            let state = testing::offline_state();
+            let handler = niloecl::make_handler(features);
+            handler(set("dm_copy", false), state.clone()).await;
+            let response = handler(features_command("show", &json!([])), state).await;
+            assert_eq!(
+                testing::content(&response),
+                Some(
+                    "Features in this server:\n`report_threads`: up to each setup\n\
+                     `dm_copy`: off\n`confirm_before_submit`: up to each setup"
+                )
+            );
+        });
+    }
+
+    #[test]
+    fn changes_are_saved_in_the_store() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let state = AppState {
+                store: Some(store.clone()),
+                ..testing::offline_state()
+            };
+            let handler = niloecl::make_handler(features);
+
+            handler(set("confirm_before_submit", true), state.clone()).await;
+            let reloaded = Features::load(Some(&store)).await.unwrap();
+            assert_eq!(
+                reloaded.get(guild(), Feature::ConfirmBeforeSubmit),
+                Some(true)
+            );
+
+            handler(reset("confirm_before_submit"), state).await;
+            let reloaded = Features::load(Some(&store)).await.unwrap();
+            assert_eq!(reloaded.get(guild(), Feature::ConfirmBeforeSubmit), None);
+        });
+    }
+
+    #[test]
+    fn changes_are_kept_in_memory_while_the_store_is_down() {
+        testing::runtime().block_on(async {
+            let state = AppState {
+                store: Some(testing::broken_store().await),
+                ..testing::offline_state()
+            };
+            let response =
+                niloecl::make_handler(features)(set("dm_copy", true), state.clone()).await;
+            let content = testing::content(&response).unwrap();
+            assert!(content.contains("temporarily unavailable"), "{content}");
+            assert!(state
+                .features
+                .enabled(Some(guild()), Feature::DmCopy, false));
+        });
+    }
+}
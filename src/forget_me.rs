@@ -0,0 +1,302 @@
+use std::fmt::Write;
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        AllowedMentions, Component, MessageFlags,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{ExtractUserId, SlashCommand},
+    interact::{escape_markdown, truncate, InteractError},
+    store::StoredReport,
+    AppState,
+};
+
+/// Custom ID name of the button confirming `/forget-me`.
+pub const FORGET_ME: &str = "forget_me";
+
+const NOTHING_STORED: &str = "Nothing about reports you sent is stored.";
+
+/// How many of the newest stored reports are listed, so the list fits in a
+/// message.
+const LISTED: usize = 8;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "forget-me",
+    desc = "Unlink the reports you sent from your account"
+)]
+pub struct ForgetMeCommand;
+
+/// Ask before forgetting, listing the stored reports.
+pub async fn forget_me(
+    State(state): State<AppState>,
+    ExtractUserId(user_id): ExtractUserId,
+    SlashCommand(ForgetMeCommand): SlashCommand<ForgetMeCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let stored = match &state.store {
+        Some(store) => store.reports_by_user(user_id).await?,
+        None => Vec::new(),
+    };
+    let mut data = InteractionResponseDataBuilder::new().flags(MessageFlags::EPHEMERAL);
+    if stored.is_empty() {
+        data = data.content(NOTHING_STORED);
+    } else {
+        let button = Component::Button(Button {
+            custom_id: Some(FORGET_ME.to_string()),
+            disabled: false,
+            emoji: None,
+            label: Some("Forget me".to_string()),
+            style: ButtonStyle::Danger,
+            url: None,
+            sku_id: None,
+        });
+        let mut content = format!(
+            "{} linked to your account. Forgetting you unlinks them, so they can't be traced \
+             back to you anymore. The reports moderators got stay where they were posted.\n",
+            stored_reports(stored.len())
+        );
+        let listed = stored.iter().rev().take(LISTED);
+        for report in listed {
+            let _ = write!(content, "\n{}", describe(report));
+        }
+        if let Some(more) = stored.len().checked_sub(LISTED).filter(|&more| more > 0) {
+            let _ = write!(content, "\n…and {more} older ones");
+        }
+        data = data
+            .allowed_mentions(AllowedMentions::default())
+            .content(content)
+            .components([Component::ActionRow(ActionRow {
+                components: vec![button],
+            })]);
+    }
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data.build()),
+    })
+}
+
+/// Forget the user who confirmed [`forget_me`] with the button.
+pub async fn confirm_forget_me(
+    State(state): State<AppState>,
+    ExtractUserId(user_id): ExtractUserId,
+) -> Result<InteractionResponse, InteractError> {
+    let forgotten = match &state.store {
+        Some(store) => store.forget_reporter(user_id).await?,
+        None => 0,
+    };
+    let content = if forgotten == 0 {
+        NOTHING_STORED.to_string()
+    } else {
+        // Logging who it was would keep the link this removes.
+        tracing::info!("Unlinked {forgotten} stored reports from a reporter at their request");
+        let count = usize::try_from(forgotten).unwrap_or(usize::MAX);
+        format!("Done, {} no longer linked to you.", stored_reports(count))
+    };
+    let data = InteractionResponseDataBuilder::new()
+        .content(content)
+        .components([])
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(data),
+    })
+}
+
+/// A line like "- 3 days ago in #reports: Being rude (about wumpus)". What
+/// reporters wrote is shortened and escaped, so it can't break the list.
+fn describe(report: &StoredReport) -> String {
+    let reason = truncate(report.reason.replace('\n', " "), 80, "…");
+    let mut line = format!(
+        "- <t:{}:R> in <#{}>: {}",
+        report.created_at,
+        report.target_channel(),
+        escape_markdown(&reason)
+    );
+    let about = [&report.user, &report.message_link, &report.channel]
+        .into_iter()
+        .find_map(Option::as_deref);
+    if let Some(about) = about {
+        let about = truncate(about.replace('\n', " "), 40, "…");
+        let _ = write!(line, " (about {})", escape_markdown(&about));
+    }
+    line
+}
+
+/// Like "1 stored report is" or "3 stored reports are".
+fn stored_reports(count: usize) -> String {
+    if count == 1 {
+        "1 stored report is".to_string()
+    } else {
+        format!("{count} stored reports are")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use twilight_model::{application::interaction::Interaction, id::Id};
+
+    use super::*;
+    use crate::{
+        interact::{handle_interaction, ModmailFormModal},
+        store::Store,
+        testing,
+    };
+
+    const REPORTER: u64 = 30;
+
+    /// The same interaction, sent from a DM.
+    fn in_dm(mut interaction: Interaction) -> Interaction {
+        interaction.guild_id = None;
+        interaction.user = interaction.member.take().and_then(|member| member.user);
+        interaction
+    }
+
+    async fn record(store: &Store, reporter: u64) {
+        let report = ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: None,
+            channel: None,
+            reason: "Being rude".to_string(),
+            custom: BTreeMap::new(),
+        };
+        store
+            .record_report(Id::new(reporter), Id::new(60), &report)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn forgets_reporters_once_confirmed() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            for reporter in [REPORTER, REPORTER, 31] {
+                record(&store, reporter).await;
+            }
+            let state = AppState {
+                store: Some(store.clone()),
+                ..testing::offline_state()
+            };
+
+            let response = Box::pin(handle_interaction(
+                state.clone(),
+                in_dm(testing::command("forget-me", &serde_json::json!([]))),
+            ))
+            .await;
+            let content = testing::content(&response).unwrap();
+            assert!(
+                content.starts_with("2 stored reports are linked to your account."),
+                "{content}"
+            );
+            let listed: Vec<_> = content.lines().skip(2).collect();
+            assert_eq!(listed.len(), 2, "{content}");
+            for line in listed {
+                assert!(line.starts_with("- <t:"), "{line}");
+                assert!(
+                    line.ends_with(":R> in <#60>: Being rude (about wumpus)"),
+                    "{line}"
+                );
+            }
+            let Some(Component::ActionRow(row)) = response.data.unwrap().components.unwrap().pop()
+            else {
+                panic!("no confirm button");
+            };
+            let Component::Button(button) = &row.components[0] else {
+                panic!("no confirm button");
+            };
+            assert_eq!(button.custom_id.as_deref(), Some(FORGET_ME));
+            // Nothing is forgotten before it's confirmed.
+            assert_eq!(
+                store
+                    .reports_by_user(Id::new(REPORTER))
+                    .await
+                    .unwrap()
+                    .len(),
+                2
+            );
+
+            let response =
+                Box::pin(handle_interaction(state, in_dm(testing::button(FORGET_ME)))).await;
+            assert_eq!(response.kind, InteractionResponseType::UpdateMessage);
+            assert_eq!(
+                testing::content(&response),
+                Some("Done, 2 stored reports are no longer linked to you.")
+            );
+            assert!(store
+                .reports_by_user(Id::new(REPORTER))
+                .await
+                .unwrap()
+                .is_empty());
+            assert_eq!(store.reports_by_user(Id::new(31)).await.unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn has_nothing_to_forget_without_stored_reports() {
+        testing::runtime().block_on(async {
+            let command = || testing::command("forget-me", &serde_json::json!([]));
+            let handler = niloecl::make_handler(forget_me);
+            let response = handler(command(), testing::offline_state()).await;
+            assert_eq!(testing::content(&response), Some(NOTHING_STORED));
+            assert!(response.data.unwrap().components.is_none());
+
+            let state = AppState {
+                store: Some(testing::store().await),
+                ..testing::offline_state()
+            };
+            let response = handler(command(), state.clone()).await;
+            assert_eq!(testing::content(&response), Some(NOTHING_STORED));
+
+            let response =
+                niloecl::make_handler(confirm_forget_me)(testing::button(FORGET_ME), state).await;
+            assert_eq!(testing::content(&response), Some(NOTHING_STORED));
+        });
+    }
+
+    #[test]
+    fn lists_the_newest_reports_escaped() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            for i in 0..10 {
+                let report = ModmailFormModal {
+                    user: None,
+                    message_link: None,
+                    channel: Some("<#61>".to_string()),
+                    reason: format!("*report* {i}\nsee above"),
+                    custom: BTreeMap::new(),
+                };
+                store
+                    .record_report(Id::new(REPORTER), Id::new(60), &report)
+                    .await
+                    .unwrap();
+            }
+            let state = AppState {
+                store: Some(store),
+                ..testing::offline_state()
+            };
+            let response = niloecl::make_handler(forget_me)(
+                testing::command("forget-me", &serde_json::json!([])),
+                state,
+            )
+            .await;
+            let content = testing::content(&response).unwrap();
+            let listed: Vec<_> = content.lines().skip(2).collect();
+            assert_eq!(listed.len(), LISTED + 1, "{content}");
+            assert!(
+                listed[0].ends_with(r"in <#60>: \*report\* 9 see above (about <#61>)"),
+                "{}",
+                listed[0]
+            );
+            assert!(listed[LISTED - 1].contains(r"\*report\* 2 "));
+            assert_eq!(listed[LISTED], "…and 2 older ones");
+        });
+    }
+}
@@ -1,26 +1,80 @@
-use std::fmt::{Debug, Display};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use niloecl::{IntoResponse, ModalSubmit, State};
+use twilight_http::{
+    api_error::ApiError, error::ErrorType, response::DeserializeBodyError, Client,
+};
 use twilight_interactions::command::{CommandModel, CreateCommand};
 use twilight_model::{
-    application::interaction::{Interaction, InteractionType},
-    channel::message::{
-        component::{
-            ActionRow, Button, ButtonStyle, SelectMenu, SelectMenuType, TextInput, TextInputStyle,
+    application::interaction::{Interaction, InteractionData, InteractionType},
+    channel::{
+        message::{
+            component::{
+                ActionRow, Button, ButtonStyle, SelectMenu, SelectMenuType, TextInput,
+                TextInputStyle,
+            },
+            AllowedMentions, Component, Embed, EmojiReactionType, MessageFlags,
         },
-        AllowedMentions, Component, MessageFlags,
+        Channel, ChannelType, Message,
     },
     guild::Permissions,
-    http::interaction::{InteractionResponse, InteractionResponseType},
-    id::{marker::ChannelMarker, Id},
+    http::{
+        attachment::Attachment,
+        interaction::{InteractionResponse, InteractionResponseType},
+    },
+    id::{
+        marker::{ChannelMarker, GuildMarker, MessageMarker, RoleMarker, TagMarker, UserMarker},
+        Id,
+    },
 };
 use twilight_util::builder::{
-    embed::{EmbedBuilder, EmbedFieldBuilder},
+    embed::{EmbedBuilder, EmbedFieldBuilder, ImageSource},
     InteractionResponseDataBuilder,
 };
 
 use crate::{
-    extract::{CidArgs, ExtractMember, SlashCommand, UserSelectMenu},
+    appinfo::{self, KeyError, RefreshAppInfoCommand},
+    assign,
+    availability::{self, AvailableCommand, UnavailableCommand},
+    blocklist::{self, BlocklistCommand},
+    brigade::Verdict,
+    bulk_resolve::{self, BulkResolveCommand},
+    config::{
+        ChannelPolicy, DmFailure, EntryPoints, FieldStyle, FormConfig, ReportField,
+        CUSTOM_FIELD_MAX_CHARS, EMBED_DESCRIPTION_MAX_CHARS,
+    },
+    confirm,
+    cooldown::{self, CooldownCommand},
+    debug_cid::{self, DebugCidCommand},
+    diagnose::{self, DiagnoseCommand},
+    emoji::{parse_emoji, request_reaction, EmojiError},
+    errors::{self, ErrorsCommand},
+    escalate,
+    extract::{
+        custom_id, custom_id_name, parse_cid_arg, CidArgs, CidRest, CustomIdTooLong,
+        ExtractGuildId, ExtractMember, FromCidArgs, FromCidArgsError, InteractionId,
+        InteractionToken, Localized, SlashCommand, UserSelectMenu,
+    },
+    features::{self, Feature, FeaturesCommand},
+    forget_me::{self, ForgetMeCommand},
+    invite::{self, InviteCommand},
+    loadtest::{self, LoadtestCommand},
+    messages::Messages,
+    preview::{self, PreviewReportCommand},
+    progress::{deferred_ephemeral, deferred_update, respond_early, Progress},
+    queue::submit_report,
+    reassign::{self, ReassignCommand},
+    report::{self, ReportCommand},
+    resolve,
+    selftest::{self, SelftestCommand},
+    set_message::{self, SetMessageCommand},
+    store::Store,
+    template,
+    testdm::{self, TestDmCommand},
     AppState,
 };
 
@@ -45,6 +99,15 @@ pub struct SetupCommand {
     button_channel: Id<ChannelMarker>,
     /// The channel to create modmails in
     modmail_channel: Id<ChannelMarker>,
+    /// Pin the message in the button channel (default false)
+    pin: Option<bool>,
+    /// Emoji to show on the button, e.g. 🚨 or <:name:id>
+    #[command(max_length = 100)]
+    button_emoji: Option<String>,
+    /// Ways to open the form (default both)
+    entry_points: Option<EntryPoints>,
+    /// Role members need to send reports (default anyone)
+    required_role: Option<Id<RoleMarker>>,
 }
 
 impl SetupCommand {
@@ -57,7 +120,9 @@ pub struct ErrorReport<T: Display + Debug>(pub T);
 
 impl<T: Display + Debug> IntoResponse for ErrorReport<T> {
     fn into_response(self) -> InteractionResponse {
-        eprint!("ERROR: {:?}", self.0);
+        tracing::error!(error = ?self.0, "{}", self.0);
+        let kind = errors::record(&self.0);
+        tracing::Span::current().record("outcome", kind);
         let embed = EmbedBuilder::new().description(self.0.to_string()).build();
         let data = InteractionResponseDataBuilder::new()
             .flags(MessageFlags::EPHEMERAL)
@@ -71,46 +136,431 @@ impl<T: Display + Debug> IntoResponse for ErrorReport<T> {
 }
 
 pub async fn handle_interaction(state: AppState, interaction: Interaction) -> InteractionResponse {
+    if let Some(plugin) = &state.plugin {
+        if let Some(response) = plugin.handle(&interaction).await {
+            return response;
+        }
+    }
+    if interaction.guild_id.is_none() && needs_guild(&interaction) {
+        let data = InteractionResponseDataBuilder::new()
+            .flags(MessageFlags::EPHEMERAL)
+            .content(
+                &state
+                    .messages
+                    .get(None, interaction.locale.as_deref())
+                    .guild_only,
+            )
+            .build();
+        return InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(data),
+        };
+    }
+    // Handlers which call the Discord API before responding can be deferred
+    // with AGHAST_EARLY_DEFER. The rest answer straight away anyway.
+    let token = interaction.token.clone();
+    let author = interaction.author_id();
     match interaction.kind {
         InteractionType::ApplicationCommand => {
-            niloecl::make_handler(app_command)(interaction, state).await
+            Box::pin(handle_command(state, interaction, token, author)).await
         }
         InteractionType::MessageComponent => {
-            niloecl::make_handler(msg_component)(interaction, state).await
+            Box::pin(handle_component(state, interaction, token, author)).await
         }
         InteractionType::ModalSubmit => {
-            niloecl::make_handler(modal_submit)(interaction, state).await
+            if custom_id_name(&interaction) == Some(resolve::RESOLVE_REASON) {
+                let response = Box::pin(niloecl::make_handler(resolve::resolve_with_reason)(
+                    interaction,
+                    state.clone(),
+                ));
+                return respond_early(&state, token, author, deferred_ephemeral(), response).await;
+            }
+            let response = Box::pin(niloecl::make_handler(modal_submit)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        InteractionType::Ping => PingPong.into_response(),
+        InteractionType::ApplicationCommandAutocomplete => {
+            tracing::warn!("Got autocomplete for a command without autocomplete options");
+            no_choices()
+        }
+        kind => {
+            tracing::warn!("Unhandled interaction type {kind:?}");
+            let data = InteractionResponseDataBuilder::new()
+                .flags(MessageFlags::EPHEMERAL)
+                .content("Sorry, I don't know how to handle that.")
+                .build();
+            InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(data),
+            }
+        }
+    }
+}
+
+async fn handle_component(
+    state: AppState,
+    interaction: Interaction,
+    token: String,
+    author: Option<Id<UserMarker>>,
+) -> InteractionResponse {
+    match custom_id_name(&interaction) {
+        Some(confirm::CONFIRM_REPORT) => {
+            let response = Box::pin(niloecl::make_handler(confirm::confirm_report)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_update(), response).await
+        }
+        // The close reason modal has to be the first response, so it
+        // can't be deferred.
+        Some(resolve::RESOLVE_REPORT) if resolve::asks_reason(&state, &interaction) => {
+            Box::pin(niloecl::make_handler(resolve::resolve_report)(
+                interaction,
+                state,
+            ))
+            .await
+        }
+        Some(resolve::RESOLVE_REPORT) => {
+            let response = Box::pin(niloecl::make_handler(resolve::resolve_report)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(blocklist::BLOCKLIST_PAGE) => {
+            niloecl::make_handler(blocklist::blocklist_page)(interaction, state).await
+        }
+        Some(errors::ERRORS_PAGE) => {
+            niloecl::make_handler(errors::errors_page)(interaction, state).await
+        }
+        Some(forget_me::FORGET_ME) => {
+            let response = Box::pin(niloecl::make_handler(forget_me::confirm_forget_me)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_update(), response).await
+        }
+        Some(bulk_resolve::BULK_RESOLVE) => {
+            niloecl::make_handler(bulk_resolve::confirm_bulk_resolve)(interaction, state).await
+        }
+        _ => niloecl::make_handler(msg_component)(interaction, state).await,
+    }
+}
+
+async fn handle_command(
+    state: AppState,
+    interaction: Interaction,
+    token: String,
+    author: Option<Id<UserMarker>>,
+) -> InteractionResponse {
+    let name = command_name(&interaction).map(ToOwned::to_owned);
+    match name.as_deref() {
+        Some(LoadtestCommand::NAME) => {
+            niloecl::make_handler(loadtest::loadtest)(interaction, state).await
         }
-        _ => PingPong.into_response(),
+        Some(DebugCidCommand::NAME) => {
+            niloecl::make_handler(debug_cid::debug_cid)(interaction, state).await
+        }
+        Some(SelftestCommand::NAME) => {
+            niloecl::make_handler(selftest::selftest)(interaction, state).await
+        }
+        Some(ErrorsCommand::NAME) => {
+            niloecl::make_handler(errors::errors)(interaction, state).await
+        }
+        Some(ForgetMeCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(forget_me::forget_me)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(InviteCommand::NAME) => {
+            niloecl::make_handler(invite::invite)(interaction, state).await
+        }
+        Some(TestDmCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(testdm::testdm)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(DiagnoseCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(diagnose::diagnose)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(RefreshAppInfoCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(appinfo::refresh_appinfo)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(PreviewReportCommand::NAME) => {
+            niloecl::make_handler(preview::preview_report)(interaction, state).await
+        }
+        Some(BulkResolveCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(bulk_resolve::bulk_resolve)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(ReassignCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(reassign::reassign)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(ReportCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(report::report)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        _ => Box::pin(handle_settings_command(state, interaction, token, author)).await,
+    }
+}
+
+/// Commands which change how the bot works in a server, split from
+/// [`handle_command`] to keep each one's stack frame small.
+async fn handle_settings_command(
+    state: AppState,
+    interaction: Interaction,
+    token: String,
+    author: Option<Id<UserMarker>>,
+) -> InteractionResponse {
+    let name = command_name(&interaction).map(ToOwned::to_owned);
+    match name.as_deref() {
+        Some(BlocklistCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(blocklist::blocklist)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(SetMessageCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(set_message::set_message)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(FeaturesCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(features::features)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(CooldownCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(cooldown::cooldown)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(AvailableCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(availability::available)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        Some(UnavailableCommand::NAME) => {
+            let response = Box::pin(niloecl::make_handler(availability::unavailable)(
+                interaction,
+                state.clone(),
+            ));
+            respond_early(&state, token, author, deferred_ephemeral(), response).await
+        }
+        _ => niloecl::make_handler(app_command)(interaction, state).await,
+    }
+}
+
+/// Whether an interaction has to come from a server. Checking this up front
+/// gives DM users one clear message, instead of whichever extractor happens
+/// to fail first.
+fn needs_guild(interaction: &Interaction) -> bool {
+    match interaction.kind {
+        InteractionType::Ping => false,
+        InteractionType::ApplicationCommand => !matches!(
+            command_name(interaction),
+            Some(InviteCommand::NAME | TestDmCommand::NAME | ForgetMeCommand::NAME)
+        ),
+        InteractionType::MessageComponent => {
+            custom_id_name(interaction) != Some(forget_me::FORGET_ME)
+        }
+        _ => true,
+    }
+}
+
+fn command_name(interaction: &Interaction) -> Option<&str> {
+    match &interaction.data {
+        Some(InteractionData::ApplicationCommand(data)) => Some(&data.name),
+        _ => None,
     }
 }
 
 async fn app_command(
     State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    InteractionToken(token): InteractionToken,
     SlashCommand(cmd): SlashCommand<SetupCommand>,
-) -> Result<InteractionResponse, InteractError> {
-    let embed = EmbedBuilder::new().description(cmd.message).build();
+) -> InteractionResponse {
+    // Setup takes several API calls, so respond right away and report
+    // progress as we go.
+    tokio::spawn(async move {
+        let progress = Progress::new(&state, token);
+        let result = run_setup(&state, &progress, guild_id, cmd).await;
+        progress.finish(result).await;
+    });
+    deferred_ephemeral()
+}
+
+async fn run_setup(
+    state: &AppState,
+    progress: &Progress,
+    guild_id: Id<GuildMarker>,
+    cmd: SetupCommand,
+) -> Result<Embed, InteractError> {
+    let button_emoji = cmd.button_emoji.as_deref().map(parse_emoji).transpose()?;
+    let (embed, components) = setup_message(
+        cmd.message,
+        cmd.select_placeholder,
+        cmd.button_msg,
+        button_emoji,
+        cmd.modmail_channel,
+        cmd.entry_points.unwrap_or_default(),
+        cmd.required_role,
+    )?;
+
+    progress.update("Posting setup message...").await;
+    let response = state
+        .client
+        .create_message(cmd.button_channel)
+        .embeds(&[embed])
+        .components(&components)
+        .await?;
+
+    // The message is already posted at this point, so failing to read it back
+    // only costs us the details in the confirmation.
+    let message = match response.model().await {
+        Ok(message) => Some(message),
+        Err(e) => {
+            tracing::warn!("Could not read back setup message: {e:?}");
+            None
+        }
+    };
+
+    let mut confirmation = EmbedBuilder::new().title("✅ Setup complete");
+    if let Some(message) = &message {
+        let link = format!(
+            "https://discord.com/channels/{guild_id}/{}/{}",
+            message.channel_id, message.id
+        );
+        let label = button_label(message).unwrap_or("(no button)");
+        confirmation = confirmation
+            .description(format!("Posted the report form: {link}"))
+            .field(EmbedFieldBuilder::new("Button", label).inline());
+    } else {
+        confirmation = confirmation.description(format!(
+            "Posted the report form in <#{}>",
+            cmd.button_channel
+        ));
+    }
+    confirmation = confirmation.field(
+        EmbedFieldBuilder::new("Reports go to", format!("<#{}>", cmd.modmail_channel)).inline(),
+    );
+
+    if cmd.pin.unwrap_or(false) {
+        progress.update("Pinning setup message...").await;
+        let pinned = match &message {
+            Some(message) => try_pin(&state.client, cmd.button_channel, message.id).await,
+            None => false,
+        };
+        let pin_status = if pinned {
+            "Yes"
+        } else {
+            "No. The channel may already have 50 pins, or I may be missing Manage Messages."
+        };
+        confirmation = confirmation.field(EmbedFieldBuilder::new("Pinned", pin_status));
+    }
+
+    Ok(confirmation.build())
+}
+
+fn button_label(message: &Message) -> Option<&str> {
+    message
+        .components
+        .iter()
+        .filter_map(|row| match row {
+            Component::ActionRow(row) => Some(&row.components),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|component| match component {
+            Component::Button(button) => button.label.as_deref(),
+            _ => None,
+        })
+}
+
+/// Pin a setup message, returning whether it worked. Failure is only a
+/// warning, because the setup message itself is still usable.
+pub async fn try_pin(
+    client: &Client,
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+) -> bool {
+    match client.create_pin(channel_id, message_id).await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::warn!("Failed to pin setup message {message_id} in {channel_id}: {e:?}");
+            false
+        }
+    }
+}
+
+/// Build the embed and components of a setup message, which lets users open
+/// the report form for `modmail_channel`.
+pub fn setup_message(
+    message: String,
+    select_placeholder: String,
+    button_msg: String,
+    button_emoji: Option<EmojiReactionType>,
+    modmail_channel: Id<ChannelMarker>,
+    entry_points: EntryPoints,
+    required_role: Option<Id<RoleMarker>>,
+) -> Result<(Embed, Vec<Component>), CustomIdTooLong> {
+    let embed = EmbedBuilder::new().description(message).build();
 
     let user_select = Component::SelectMenu(SelectMenu {
         channel_types: None,
-        custom_id: format!("open_form_user:{}", cmd.modmail_channel.get()),
+        custom_id: open_form_id(OPEN_FORM_USER, modmail_channel, required_role)?,
         default_values: None,
         disabled: false,
         kind: SelectMenuType::User,
         max_values: None,
         min_values: None,
         options: None,
-        placeholder: Some(cmd.select_placeholder),
+        placeholder: Some(select_placeholder),
     });
     let user_select_row = Component::ActionRow(ActionRow {
         components: vec![user_select],
     });
 
     let submit_button = Component::Button(Button {
-        custom_id: Some(format!("open_form:{}", cmd.modmail_channel.get())),
+        custom_id: Some(open_form_id(OPEN_FORM, modmail_channel, required_role)?),
         disabled: false,
-        emoji: None,
-        label: Some(cmd.button_msg),
+        emoji: button_emoji,
+        label: Some(button_msg),
         style: ButtonStyle::Success,
         url: None,
         sku_id: None,
@@ -119,94 +569,153 @@ async fn app_command(
         components: vec![submit_button],
     });
 
-    state
-        .client
-        .create_message(cmd.button_channel)
-        .embeds(&[embed])
-        .components(&[user_select_row, submit_button_row])
-        .await?;
-
-    let data = InteractionResponseDataBuilder::new()
-        .flags(MessageFlags::EPHEMERAL)
-        .content("Creating button message")
-        .build();
-
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(data),
-    })
+    let components = [
+        (entry_points.has_select(), user_select_row),
+        (entry_points.has_button(), submit_button_row),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, row)| enabled.then_some(row))
+    .collect();
+    Ok((embed, components))
 }
 
 /// This is a const to allow the `msg_component` function to format
 const EXAMPLE_MESSAGE_LINK: &str =
     "e.g. https://discord.com/channels/302094807046684672/768594508287311882/768594834231132222";
 
+/// Custom ID name of the setup button, and of "Report another".
+pub const OPEN_FORM: &str = "open_form";
+/// Custom ID name of the user select menu of setups.
+pub const OPEN_FORM_USER: &str = "open_form_user";
+
+/// Custom ID of a component which opens the form for `channel`. The role is
+/// only added when there is one, so setups without stay as they were.
+pub fn open_form_id(
+    name: &str,
+    channel: Id<ChannelMarker>,
+    required_role: Option<Id<RoleMarker>>,
+) -> Result<String, CustomIdTooLong> {
+    required_role.map_or_else(
+        || custom_id(name, &[&channel]),
+        |role| custom_id(name, &[&channel, &role]),
+    )
+}
+
+/// Arguments of [`OPEN_FORM`] and [`OPEN_FORM_USER`] custom IDs: the modmail
+/// channel, then the role needed to report if the setup has one.
+pub struct OpenFormArgs {
+    pub channel: Id<ChannelMarker>,
+    pub required_role: Option<Id<RoleMarker>>,
+}
+
+impl FromCidArgs for OpenFormArgs {
+    fn from_args(args: &[&str]) -> Result<Self, FromCidArgsError> {
+        let (channel, CidRest(roles)) = FromCidArgs::from_args(args)?;
+        let required_role = match roles[..] {
+            [] => None,
+            [role] => Some(role),
+            _ => return Err(FromCidArgsError::ExtraCustomIdArgs(2, args.len())),
+        };
+        Ok(Self {
+            channel,
+            required_role,
+        })
+    }
+}
+
+/// Custom ID name of the report form.
+pub const FORM_SUBMIT: &str = "form_submit";
+
+/// Custom ID of the report form. The user picked in the select menu and the
+/// required role are left out when there are none, and the user is left empty
+/// when only the role is set.
+fn form_submit_id(
+    channel: Id<ChannelMarker>,
+    user: Option<Id<UserMarker>>,
+    required_role: Option<Id<RoleMarker>>,
+) -> Result<String, CustomIdTooLong> {
+    match (user, required_role) {
+        (None, None) => custom_id(FORM_SUBMIT, &[&channel]),
+        (Some(user), None) => custom_id(FORM_SUBMIT, &[&channel, &user]),
+        (None, Some(role)) => custom_id(FORM_SUBMIT, &[&channel, &"", &role]),
+        (Some(user), Some(role)) => custom_id(FORM_SUBMIT, &[&channel, &user, &role]),
+    }
+}
+
+/// Arguments of `form_submit` custom IDs, see [`form_submit_id`].
+pub struct FormSubmitArgs {
+    pub channel: Id<ChannelMarker>,
+    /// The user picked in the select menu, if the form was opened from it.
+    pub user: Option<Id<UserMarker>>,
+    pub required_role: Option<Id<RoleMarker>>,
+}
+
+impl FromCidArgs for FormSubmitArgs {
+    fn from_args(args: &[&str]) -> Result<Self, FromCidArgsError> {
+        let (channel, user, required_role) = match args {
+            [] => return Err(FromCidArgsError::RequiredCustomIdArgMissing(1)),
+            [channel] => (channel, None, None),
+            [channel, user] => (channel, Some(parse_cid_arg(user)?), None),
+            [channel, user, role] => {
+                let user = (!user.is_empty())
+                    .then(|| parse_cid_arg(user))
+                    .transpose()?;
+                (channel, user, Some(parse_cid_arg(role)?))
+            }
+            _ => return Err(FromCidArgsError::ExtraCustomIdArgs(3, args.len())),
+        };
+        Ok(Self {
+            channel: parse_cid_arg(channel)?,
+            user,
+            required_role,
+        })
+    }
+}
+
 async fn msg_component(
-    CidArgs((target_channel,)): CidArgs<(Id<ChannelMarker>,)>,
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    Localized(messages): Localized,
+    CidArgs(args): CidArgs<OpenFormArgs>,
     usm: Option<UserSelectMenu>,
 ) -> Result<ModalResponse, InteractError> {
-    let components = [
-        TextInput {
-            custom_id: "user".into(),
-            label: "Username or ID of the user you wish to report".into(), // this cannot be made longer
-            max_length: Some(1000),
-            min_length: None,
-            placeholder: Some("e.g. wumpus or 302094807046684672".into()),
-            required: Some(true),
-            style: TextInputStyle::Short,
-            value: None,
-        },
-        TextInput {
-            custom_id: "channel".into(),
-            label: "Channel name".into(),
-            max_length: Some(128),
-            min_length: None,
-            placeholder: Some("e.g. #minecraft".into()),
-            required: Some(true),
-            style: TextInputStyle::Short,
-            value: None,
-        },
-        TextInput {
-            custom_id: "message_link".into(),
-            label: "Message link".into(),
-            max_length: Some(128),
-            min_length: None,
-            placeholder: Some(EXAMPLE_MESSAGE_LINK.into()),
-            required: Some(false),
-            style: TextInputStyle::Paragraph,
-            value: None,
-        },
-        TextInput {
-            custom_id: "reason".into(),
-            label: "Reason for reporting (what happened, in detail)".into(),
-            max_length: Some(128),
-            min_length: None,
-            placeholder: Some("e.g. User is being overly rude".into()),
-            required: Some(true),
-            style: TextInputStyle::Paragraph,
-            value: None,
-        },
-    ]
-    .map(|c| {
-        Component::ActionRow(ActionRow {
-            components: vec![Component::TextInput(c)],
-        })
-    });
-    let (custom_id, components) = if let Some(UserSelectMenu(users)) = usm {
+    let user_id = member.user.ok_or(InteractError::NoUser)?.id;
+    if state.blocklist.is_blocked(guild_id, user_id) {
+        return Err(InteractError::Blocked);
+    }
+    let OpenFormArgs {
+        channel: target_channel,
+        required_role,
+    } = args;
+    if let Some(role) = required_role.filter(|role| !member.roles.contains(role)) {
+        return Err(InteractError::MissingRequiredRole(role));
+    }
+    let form = state.forms.get(target_channel);
+    if let Some(max) = form.max_open_forms {
+        if !state.open_forms.try_open(user_id, max) {
+            return Err(InteractError::FormAlreadyOpen);
+        }
+    }
+    let (custom_id, user_preselected) = if let Some(UserSelectMenu(users)) = usm {
         let Some(user) = users.first() else {
             return Err(InteractError::NoUser);
         };
         (
-            format!("form_submit:{}:{}", target_channel.get(), user.id),
-            components[1..].to_vec(),
+            form_submit_id(target_channel, Some(user.id), required_role)?,
+            true,
         )
     } else {
-        (
-            format!("form_submit:{}", target_channel.get()),
-            components.as_slice().to_vec(),
-        )
+        (form_submit_id(target_channel, None, required_role)?, false)
     };
-    let title = "ModMail Form".to_string();
+    let components = form_fields(form, user_preselected)
+        .map(|(_, input)| {
+            Component::ActionRow(ActionRow {
+                components: vec![Component::TextInput(input)],
+            })
+        })
+        .collect();
+    let title = messages.form_title.clone();
     Ok(ModalResponse {
         title,
         custom_id,
@@ -214,50 +723,237 @@ async fn msg_component(
     })
 }
 
-#[derive(serde::Deserialize)]
+/// Reasons longer than an embed field only fit if they can be attached.
+pub const fn reason_max_length(form: &FormConfig) -> u16 {
+    if form.long_reason_attachment {
+        4000
+    } else {
+        EMBED_FIELD_MAX_CHARS
+    }
+}
+
+/// The text inputs of the report form, as configured for `form`, with the
+/// names reports show them under.
+pub fn form_fields(
+    form: &FormConfig,
+    user_preselected: bool,
+) -> impl Iterator<Item = (&str, TextInput)> + '_ {
+    let fields = [
+        (
+            ReportField::User,
+            TextInput {
+                custom_id: "user".into(),
+                label: "Username or ID of the user you wish to report".into(), // this cannot be made longer
+                max_length: Some(1000),
+                min_length: None,
+                placeholder: Some("e.g. wumpus or 302094807046684672".into()),
+                required: Some(true),
+                style: TextInputStyle::Short,
+                value: None,
+            },
+        ),
+        (
+            ReportField::Channel,
+            TextInput {
+                custom_id: "channel".into(),
+                label: "Channel name".into(),
+                max_length: Some(128),
+                min_length: None,
+                placeholder: Some("e.g. #minecraft".into()),
+                required: Some(true),
+                style: TextInputStyle::Short,
+                value: None,
+            },
+        ),
+        (
+            ReportField::MessageLink,
+            TextInput {
+                custom_id: "message_link".into(),
+                label: "Message link".into(),
+                max_length: Some(128),
+                min_length: None,
+                placeholder: Some(EXAMPLE_MESSAGE_LINK.into()),
+                required: Some(false),
+                style: TextInputStyle::Paragraph,
+                value: None,
+            },
+        ),
+        (
+            ReportField::Reason,
+            TextInput {
+                custom_id: "reason".into(),
+                label: "Reason for reporting (what happened, in detail)".into(),
+                max_length: Some(reason_max_length(form)),
+                min_length: None,
+                placeholder: Some("e.g. User is being overly rude".into()),
+                required: Some(true),
+                style: TextInputStyle::Paragraph,
+                value: None,
+            },
+        ),
+    ];
+    let custom = form.custom_fields.iter().map(|field| {
+        let input = TextInput {
+            custom_id: field.id.clone(),
+            label: field.label.clone(),
+            max_length: Some(field.max_length.unwrap_or(CUSTOM_FIELD_MAX_CHARS)),
+            min_length: field.min_length,
+            placeholder: field.placeholder.clone(),
+            required: Some(field.required),
+            style: match field.style {
+                FieldStyle::Short => TextInputStyle::Short,
+                FieldStyle::Paragraph => TextInputStyle::Paragraph,
+            },
+            value: None,
+        };
+        (field.label.as_str(), input)
+    });
+    fields
+        .into_iter()
+        .filter(move |(field, _)| {
+            form.is_enabled(*field) && !(user_preselected && *field == ReportField::User)
+        })
+        .map(|(field, mut input)| {
+            if let Some(change) = form.fields.get(&field) {
+                if let Some(label) = &change.label {
+                    input.label.clone_from(label);
+                }
+                if let Some(placeholder) = &change.placeholder {
+                    input.placeholder = Some(placeholder.clone());
+                }
+                if let Some(required) = change.required {
+                    input.required = Some(required);
+                }
+            }
+            (field.name(), input)
+        })
+        .chain(custom)
+}
+
+/// Enforce the limits the form declared again. Clients are supposed to do this
+/// before submitting, but not all of them do, and nothing stops a modified one.
+pub fn validate_report(
+    form: &FormConfig,
+    report: &ModmailFormModal,
+    user_preselected: bool,
+) -> Result<(), FieldError> {
+    for (name, input) in form_fields(form, user_preselected) {
+        let value = report.value(&input.custom_id);
+        let length = value.map_or(0, |value| value.trim().chars().count());
+        if length == 0 {
+            if input.required.unwrap_or(true) {
+                return Err(FieldError::Missing(name.to_string()));
+            }
+            continue;
+        }
+        if let Some(max) = input.max_length {
+            if value.map_or(0, |value| value.chars().count()) > usize::from(max) {
+                return Err(FieldError::TooLong(name.to_string(), max));
+            }
+        }
+        if let Some(min) = input.min_length {
+            if length < usize::from(min) {
+                return Err(FieldError::TooShort(name.to_string(), min));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fields other than `reason` can be disabled per setup, so they may be absent.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct ModmailFormModal {
-    user: String,
-    message_link: String,
-    channel: String,
-    reason: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub message_link: Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+    pub reason: String,
+    /// Values of the form's `custom_fields`, by ID.
+    #[serde(flatten)]
+    pub custom: BTreeMap<String, String>,
+}
+
+impl ModmailFormModal {
+    /// The value of the text input with this custom ID.
+    pub fn value(&self, id: &str) -> Option<&str> {
+        match id {
+            "user" => self.user.as_deref(),
+            "channel" => self.channel.as_deref(),
+            "message_link" => self.message_link.as_deref(),
+            "reason" => Some(&self.reason),
+            _ => self.custom.get(id).map(String::as_str),
+        }
+    }
 }
 
 async fn modal_submit(
     State(state): State<AppState>,
+    InteractionId(interaction_id): InteractionId,
+    ExtractGuildId(guild_id): ExtractGuildId,
     ExtractMember(member): ExtractMember,
+    Localized(messages): Localized,
     modal: ModalSubmit<ModmailFormModal>,
-    CidArgs((target_channel,)): CidArgs<(Id<ChannelMarker>,)>,
+    CidArgs(args): CidArgs<FormSubmitArgs>,
 ) -> Result<InteractionResponse, InteractError> {
     let user = member.user.ok_or(InteractError::NoUser)?;
+    // Checked again in case they were blocked while the form was open.
+    if state.blocklist.is_blocked(guild_id, user.id) {
+        return Err(InteractError::Blocked);
+    }
+    let FormSubmitArgs {
+        channel: target_channel,
+        user: preselected,
+        required_role,
+    } = args;
+    // The form was only shown to members with the role, but it could have
+    // been taken away since.
+    if let Some(role) = required_role.filter(|role| !member.roles.contains(role)) {
+        return Err(InteractError::MissingRequiredRole(role));
+    }
+    let form = state.forms.get(target_channel);
+    if form.max_open_forms.is_some() {
+        state.open_forms.close(user.id);
+    }
+    if user.bot && !form.allow_bot_reporters {
+        tracing::warn!("Rejected report from bot {} in {target_channel}", user.id);
+        return Err(InteractError::BotReporter);
+    }
+    let mut report = modal.data;
+    let submitter = Submitter {
+        guild_id,
+        user: user.id,
+        roles: &member.roles,
+    };
+    check_report(&state, submitter, target_channel, &mut report, preselected).await?;
 
-    let user_field = EmbedFieldBuilder::new("User", modal.data.user)
-        .inline()
-        .build();
-    let channel_field = EmbedFieldBuilder::new("Channel", modal.data.channel)
-        .inline()
-        .build();
-    let message_link_field =
-        EmbedFieldBuilder::new("Message link", modal.data.message_link).build();
-    let reason_field = EmbedFieldBuilder::new("Reason", modal.data.reason).build();
-
-    let embed = EmbedBuilder::new()
-        .field(user_field)
-        .field(channel_field)
-        .field(message_link_field)
-        .field(reason_field)
-        .build();
+    let confirm = form.confirm_before_submit;
+    if state
+        .features
+        .enabled(Some(guild_id), Feature::ConfirmBeforeSubmit, confirm)
+    {
+        return Ok(confirm::ask_confirmation(
+            &state,
+            &messages,
+            interaction_id,
+            user.id,
+            target_channel,
+            required_role,
+            report,
+        )?);
+    }
 
-    state
-        .client
-        .create_message(target_channel)
-        .content(&format!("Report from <@{}>", user.id))
-        .embeds(&[embed])
-        .allowed_mentions(Some(&AllowedMentions::default()))
-        .await?;
+    submit_report(&state, guild_id, user.id, target_channel, report).await?;
 
     let data = InteractionResponseDataBuilder::new()
-        .flags(MessageFlags::EPHEMERAL)
-        .content("Thanks for making a report. A moderator will handle it as soon as possible.")
+        .flags(form.response_flags())
+        .content(&messages.report_received)
+        .components(report_received_components(
+            &state,
+            target_channel,
+            required_role,
+        )?)
         .build();
 
     Ok(InteractionResponse {
@@ -266,6 +962,871 @@ async fn modal_submit(
     })
 }
 
+/// Who submitted a report, and where.
+#[derive(Clone, Copy)]
+pub struct Submitter<'a> {
+    pub guild_id: Id<GuildMarker>,
+    pub user: Id<UserMarker>,
+    pub roles: &'a [Id<RoleMarker>],
+}
+
+/// Everything a report from the form goes through before it's submitted:
+/// validation, the channel policy and the rate limit. Brigade detection,
+/// the queue and the store follow in [`submit_report`].
+pub async fn check_report(
+    state: &AppState,
+    submitter: Submitter<'_>,
+    target_channel: Id<ChannelMarker>,
+    report: &mut ModmailFormModal,
+    preselected: Option<Id<UserMarker>>,
+) -> Result<(), InteractError> {
+    let form = state.forms.get(target_channel);
+    validate_report(form, report, preselected.is_some())?;
+    // The form had no user field then, and nothing typed in could be more
+    // accurate than the selected user anyway.
+    if let Some(preselected) = preselected {
+        report.user = Some(format!("<@{preselected}>"));
+    }
+    check_channel_ref(
+        state,
+        form,
+        submitter.guild_id,
+        submitter.user,
+        submitter.roles,
+        &mut report.channel,
+    )
+    .await?;
+    check_submit_limit(state, submitter.guild_id, submitter.user, target_channel)
+        .map_err(InteractError::RateLimited)
+}
+
+/// Count a submission against the reporter's rate limit. If they sent too
+/// many recently, returns the Unix time in seconds at which they may submit
+/// again.
+pub fn check_submit_limit(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+) -> Result<(), u64> {
+    state
+        .submit_limits
+        .try_submit(guild_id, reporter)
+        .map_err(|wait| {
+            tracing::warn!("Rate limited report from {reporter} in {target_channel}");
+            // Rounded up, so the countdown doesn't end before the limit does.
+            let until = (SystemTime::now() + wait)
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            until.as_secs() + u64::from(until.subsec_nanos() > 0)
+        })
+}
+
+/// Apply the form's `channel_policy` to the channel field of a report,
+/// rejecting it or marking it in place. With `warn_hidden_channels`, also
+/// mark channels the reporter can't see.
+pub async fn check_channel_ref(
+    state: &AppState,
+    form: &FormConfig,
+    guild_id: Id<GuildMarker>,
+    reporter: Id<UserMarker>,
+    reporter_roles: &[Id<RoleMarker>],
+    channel: &mut Option<String>,
+) -> Result<(), InteractError> {
+    let Some(input) = channel.clone() else {
+        return Ok(());
+    };
+    let channel_id = parse_channel_ref(&input);
+    let problem = match (form.channel_policy, channel_id) {
+        (ChannelPolicy::Lenient, _) => None,
+        (_, Some(channel_id)) => channel_problem(state, form, guild_id, channel_id).await,
+        (_, None) => Some("not a channel of this server"),
+    };
+    match (problem, form.channel_policy) {
+        (None, _) | (Some(_), ChannelPolicy::Lenient) => {}
+        (Some(problem), ChannelPolicy::Warn) => {
+            *channel = Some(format!("{input} (⚠️ {problem})"));
+        }
+        (Some(problem), ChannelPolicy::Strict) => {
+            return Err(InteractError::ChannelNotAllowed(problem));
+        }
+    }
+    if let (true, Some(channel_id)) = (form.warn_hidden_channels, channel_id) {
+        if !can_view(state, guild_id, channel_id, reporter, reporter_roles).await {
+            if let Some(channel) = channel {
+                channel.push_str(" (⚠️ reporter cannot view this channel)");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the reporter can see a channel. Anything we can't check counts as
+/// visible, so only a definite answer adds a warning.
+async fn can_view(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    channel_id: Id<ChannelMarker>,
+    reporter: Id<UserMarker>,
+    reporter_roles: &[Id<RoleMarker>],
+) -> bool {
+    let Some(channel) = fetch_channel(state, channel_id).await else {
+        return true;
+    };
+    if channel.guild_id != Some(guild_id) {
+        return true;
+    }
+    match diagnose::member_permissions(state, guild_id, &channel, reporter, reporter_roles).await {
+        Ok(permissions) => permissions.contains(Permissions::VIEW_CHANNEL),
+        Err(e) => {
+            tracing::warn!("Could not check reporter's permissions in {channel_id}: {e:?}");
+            true
+        }
+    }
+}
+
+/// Why a channel can't be referenced in a report, if it can't.
+async fn channel_problem(
+    state: &AppState,
+    form: &FormConfig,
+    guild_id: Id<GuildMarker>,
+    channel_id: Id<ChannelMarker>,
+) -> Option<&'static str> {
+    // Channels we can't fetch are either gone or hidden from the bot, and
+    // either way nobody could look at them from the report.
+    let Some(channel) = fetch_channel(state, channel_id).await else {
+        return Some("not a channel of this server");
+    };
+    if channel.guild_id != Some(guild_id) {
+        return Some("not a channel of this server");
+    }
+    if form.allowed_categories.is_empty() {
+        return None;
+    }
+    // A thread's category is the one its parent channel is in.
+    let parent = if channel.kind.is_thread() {
+        match channel.parent_id {
+            Some(parent) => fetch_channel(state, parent).await?.parent_id,
+            None => None,
+        }
+    } else {
+        channel.parent_id
+    };
+    if parent.is_some_and(|parent| form.allowed_categories.contains(&parent)) {
+        None
+    } else {
+        Some("not in an allowed category")
+    }
+}
+
+pub async fn fetch_channel(state: &AppState, channel_id: Id<ChannelMarker>) -> Option<Channel> {
+    match state.client.channel(channel_id).await {
+        Ok(response) => response.model().await.ok(),
+        Err(_) => None,
+    }
+}
+
+/// Components shown under the "report received" message: a button to open
+/// the form again, if the setup wants one.
+pub fn report_received_components(
+    state: &AppState,
+    target_channel: Id<ChannelMarker>,
+    required_role: Option<Id<RoleMarker>>,
+) -> Result<Vec<Component>, CustomIdTooLong> {
+    if !state.forms.get(target_channel).report_another_button {
+        return Ok(Vec::new());
+    }
+    let button = Component::Button(Button {
+        custom_id: Some(open_form_id(OPEN_FORM, target_channel, required_role)?),
+        disabled: false,
+        emoji: None,
+        label: Some("Report another".to_string()),
+        style: ButtonStyle::Secondary,
+        url: None,
+        sku_id: None,
+    });
+    Ok(vec![Component::ActionRow(ActionRow {
+        components: vec![button],
+    })])
+}
+
+/// Post a report to `target_channel`. Submissions go through
+/// [`submit_report`] instead, so they can be queued. Reports are numbered per
+/// `guild_id` if there is a store, and reports without one, like self tests,
+/// aren't numbered. Returns the posted message.
+pub async fn post_report(
+    state: &AppState,
+    guild_id: Option<Id<GuildMarker>>,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    report: ModmailFormModal,
+) -> Result<Message, InteractError> {
+    let form = state.forms.get(target_channel);
+    let (alert, held) = brigade_alert(state, form, reporter, target_channel, &report)
+        .map_or((None, false), |(alert, held)| (Some(alert), held));
+
+    let attachments: Vec<Attachment> = (form.long_reason_attachment
+        && report.reason.chars().count() > usize::from(EMBED_FIELD_MAX_CHARS))
+    .then(|| Attachment::from_bytes("reason.txt".into(), report.reason.clone().into(), 0))
+    .into_iter()
+    .collect();
+    // Forum posts need a title, and the reported user is the most useful one.
+    let title = truncate(
+        report
+            .user
+            .as_deref()
+            .map_or_else(|| "Report".to_string(), |user| format!("Report: {user}")),
+        100,
+        "…",
+    );
+    let stored = state.store.as_ref().map(|_| report.clone());
+    let number = match (&state.store, guild_id) {
+        (Some(store), Some(guild_id)) => take_report_number(store, guild_id).await,
+        _ => None,
+    };
+    let mut embed = build_report_embed(form, state.messages.reports(), Some(reporter), report);
+    if let Some(number) = number {
+        embed.title = Some(format!("Report #{number}"));
+        fit_embed(&mut embed, &state.messages.reports().truncated);
+    }
+    let embeds = [embed];
+    let copy = state
+        .features
+        .enabled(guild_id, Feature::DmCopy, form.dm_copy)
+        .then(|| embeds.clone());
+    let assignee = if held {
+        None
+    } else {
+        assign::next(state, form, guild_id, target_channel).await
+    };
+    let content = alert.unwrap_or_else(|| format!("Report from <@{reporter}>"));
+    let (content, allowed_mentions) = with_ping(form, content, held, assignee);
+
+    let message = ReportMessage {
+        title: &title,
+        content: &content,
+        embeds: &embeds,
+        attachments: &attachments,
+        allowed_mentions: &allowed_mentions,
+        components: &resolve::components(form, reporter)?,
+        forum_tags: &form.forum_tags,
+    };
+    let posted = match send_report(state, target_channel, &message).await {
+        Ok(posted) => posted,
+        Err(err) => {
+            let is_unknown_channel = matches!(
+                &err,
+                InteractError::Http(e) if api_error_code(e) == Some(UNKNOWN_CHANNEL)
+            );
+            let Some(fallback) = state
+                .fallback_channel
+                .filter(|fallback| is_unknown_channel && *fallback != target_channel)
+            else {
+                return Err(err);
+            };
+
+            tracing::warn!(
+                "Modmail channel {target_channel} is gone, posting report to {fallback}"
+            );
+            let content = format!(
+                "⚠️ Sent to the fallback channel because <#{target_channel}> no longer exists.\n{content}"
+            );
+            // The tags belong to the original forum.
+            let message = ReportMessage {
+                content: &content,
+                forum_tags: &[],
+                ..message
+            };
+            send_report(state, fallback, &message).await?
+        }
+    };
+
+    if let Some(moderator) = assignee {
+        assign::assign(state, &posted, moderator).await;
+    }
+    if !held {
+        let is_assigned = assignee.is_some();
+        announce(
+            state,
+            form,
+            guild_id,
+            target_channel,
+            &title,
+            &posted,
+            is_assigned,
+        )
+        .await;
+    }
+    if let Some(report) = stored {
+        store_report(state, reporter, target_channel, &report).await;
+    }
+    if let Some(embeds) = copy {
+        send_copy(state, form, reporter, &posted, &embeds).await;
+    }
+    Ok(posted)
+}
+
+/// Put the ping in front of a report's `content`. Only the configured role,
+/// or the moderator the report is assigned to, may be pinged, never anything
+/// from the report.
+fn with_ping(
+    form: &FormConfig,
+    content: String,
+    held: bool,
+    assignee: Option<Id<UserMarker>>,
+) -> (String, AllowedMentions) {
+    let mut allowed_mentions = AllowedMentions::default();
+    if let Some(moderator) = assignee {
+        allowed_mentions.users.push(moderator);
+        let content = format!("{}<@{moderator}>\n{content}", assign::CLAIMED);
+        return (content, allowed_mentions);
+    }
+    if let (Some(role), None, false) = (form.ping_role, form.ping_batch_secs, held) {
+        allowed_mentions.roles.push(role);
+        return (format!("<@&{role}> {content}"), allowed_mentions);
+    }
+    (content, allowed_mentions)
+}
+
+/// DM the reporter a copy of their report. The report is out already, so
+/// failing here doesn't fail the submission, and `form.dm_failure` decides
+/// who hears about it.
+async fn send_copy(
+    state: &AppState,
+    form: &FormConfig,
+    reporter: Id<UserMarker>,
+    posted: &Message,
+    embeds: &[Embed],
+) {
+    let copy = &state.messages.reports().report_copy;
+    let result = async {
+        let channel = state
+            .client
+            .create_private_channel(reporter)
+            .await?
+            .model()
+            .await?;
+        state
+            .client
+            .create_message(channel.id)
+            .content(copy)
+            .embeds(embeds)
+            .await?;
+        Ok::<_, InteractError>(())
+    }
+    .await;
+    let Err(e) = result else {
+        return;
+    };
+    if matches!(&e, InteractError::Http(e) if api_error_code(e) == Some(CANNOT_MESSAGE_USER)) {
+        tracing::warn!("Could not DM {reporter} a copy of their report, their DMs are closed");
+    } else {
+        tracing::warn!("Failed to DM {reporter} a copy of their report: {e:?}");
+    }
+
+    let (content, embeds) = match form.dm_failure {
+        DmFailure::Log => return,
+        DmFailure::Notify => (
+            format!("⚠️ Couldn't DM <@{reporter}> a copy of their report."),
+            &[][..],
+        ),
+        DmFailure::Fallback => (
+            format!(
+                "⚠️ Couldn't DM <@{reporter}> a copy of their report. This is what they would \
+                 have gotten:\n{copy}"
+            ),
+            embeds,
+        ),
+    };
+    // The reporter can't see the report anyway, so pinging them is no use.
+    let result = state
+        .client
+        .create_message(posted.channel_id)
+        .reply(posted.id)
+        .content(&content)
+        .embeds(embeds)
+        .allowed_mentions(Some(&AllowedMentions::default()))
+        .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to tell moderators about the undelivered DM to {reporter}: {e:?}");
+    }
+}
+
+/// The content to post the report with if its user might be brigaded, and
+/// whether it's held. Held reports don't ping anyone.
+fn brigade_alert(
+    state: &AppState,
+    form: &FormConfig,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    report: &ModmailFormModal,
+) -> Option<(String, bool)> {
+    let brigade = form.brigade.as_ref().zip(report.user.as_deref()).map_or(
+        Verdict::Post,
+        |(brigade, target)| {
+            state
+                .brigades
+                .check(brigade, target_channel, target, reporter)
+        },
+    );
+    match brigade {
+        Verdict::Post => None,
+        Verdict::Alert(reporters) => {
+            tracing::warn!(
+                "Possible brigade against {:?} in {target_channel}, reported by {reporters:?}",
+                report.user
+            );
+            let mentions: Vec<_> = reporters.iter().map(|user| format!("<@{user}>")).collect();
+            Some((
+                format!(
+                    "⚠️ Possible brigade: {} users reported this user within a short time ({}). \
+                     Further reports about them are held for now.",
+                    reporters.len(),
+                    mentions.join(", ")
+                ),
+                false,
+            ))
+        }
+        Verdict::Hold => {
+            tracing::warn!(
+                "Holding report from {reporter} in {target_channel} during possible brigade"
+            );
+            Some((
+                format!(
+                    "⏸️ Held report from <@{reporter}>, sent during a possible brigade against \
+                     this user. It doesn't ping anyone."
+                ),
+                true,
+            ))
+        }
+    }
+}
+
+/// Take the next report number of `guild_id`. Numbers only make reports
+/// easier to refer to, so reports are posted without one if that fails.
+async fn take_report_number(store: &Store, guild_id: Id<GuildMarker>) -> Option<u64> {
+    store
+        .next_report_number(guild_id)
+        .await
+        .inspect_err(|e| tracing::error!("Failed to number a report in {guild_id}: {e:?}"))
+        .ok()
+}
+
+/// Keep a posted report in the database, if there is one. The report is out
+/// already, so failing here only gets logged.
+async fn store_report(
+    state: &AppState,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    report: &ModmailFormModal,
+) {
+    let Some(store) = &state.store else {
+        return;
+    };
+    if let Err(e) = store.record_report(reporter, target_channel, report).await {
+        tracing::error!("Failed to store report from {reporter}: {e:?}");
+    }
+}
+
+/// Let moderators know about a posted report, as far as the form wants.
+/// Reports `assigned` to a moderator pinged them already, and are theirs to
+/// handle, so they're left out of ping batches and escalation.
+async fn announce(
+    state: &AppState,
+    form: &FormConfig,
+    guild_id: Option<Id<GuildMarker>>,
+    target_channel: Id<ChannelMarker>,
+    title: &str,
+    posted: &Message,
+    assigned: bool,
+) {
+    let batch = form
+        .ping_role
+        .zip(form.ping_batch_secs)
+        .filter(|_| !assigned);
+    if let Some((role, batch_secs)) = batch {
+        state.ping_batches.add(
+            &state.client,
+            target_channel,
+            role,
+            Duration::from_secs(batch_secs),
+            (posted.channel_id, posted.id),
+        );
+    }
+
+    if let Some(reaction) = &form.new_report_reaction {
+        // Already validated when the config was loaded.
+        if let Ok(emoji) = parse_emoji(reaction) {
+            add_reaction(&state.client, posted.channel_id, posted.id, &emoji).await;
+        }
+    }
+
+    // The first message of a forum post has the ID of the post, which is a
+    // thread already.
+    let threads = state
+        .features
+        .enabled(guild_id, Feature::ReportThreads, form.report_threads);
+    if threads && posted.id.get() != posted.channel_id.get() {
+        start_thread(&state.client, posted.channel_id, posted.id, title).await;
+    }
+    if !assigned {
+        escalate::track(state, form, target_channel, posted).await;
+    }
+}
+
+/// Everything needed to post a report, so it can be posted again elsewhere.
+#[derive(Clone, Copy)]
+struct ReportMessage<'a> {
+    /// Only used for posts in forum channels.
+    title: &'a str,
+    content: &'a str,
+    embeds: &'a [Embed],
+    attachments: &'a [Attachment],
+    allowed_mentions: &'a AllowedMentions,
+    components: &'a [Component],
+    /// Only used for posts in forum channels.
+    forum_tags: &'a [Id<TagMarker>],
+}
+
+/// Post a report in `channel`. Forum channels can't take messages, so they
+/// get a new post with the report as its first message instead.
+async fn send_report(
+    state: &AppState,
+    channel: Id<ChannelMarker>,
+    message: &ReportMessage<'_>,
+) -> Result<Message, InteractError> {
+    let started = Instant::now();
+    let result = state
+        .client
+        .create_message(channel)
+        .content(message.content)
+        .embeds(message.embeds)
+        .attachments(message.attachments)
+        .allowed_mentions(Some(message.allowed_mentions))
+        .components(message.components)
+        .await;
+    state.metrics.create_message(started.elapsed());
+    let err = match result {
+        Ok(response) => return Ok(response.model().await?),
+        Err(err) if api_error_code(&err) == Some(CANNOT_SEND_NON_TEXT) => err,
+        Err(err) => return Err(err.into()),
+    };
+    let is_forum = fetch_channel(state, channel).await.is_some_and(|channel| {
+        matches!(
+            channel.kind,
+            ChannelType::GuildForum | ChannelType::GuildMedia
+        )
+    });
+    if !is_forum {
+        return Err(err.into());
+    }
+    let started = Instant::now();
+    let result = state
+        .client
+        .create_forum_thread(channel, message.title)
+        .applied_tags(message.forum_tags)
+        .message()
+        .content(message.content)
+        .embeds(message.embeds)
+        .attachments(message.attachments)
+        .allowed_mentions(Some(message.allowed_mentions))
+        .components(message.components)
+        .await;
+    state.metrics.create_forum_thread(started.elapsed());
+    Ok(result?.model().await?.message)
+}
+
+/// Discord's JSON error code for sending messages to channels like forums.
+const CANNOT_SEND_NON_TEXT: u64 = 50008;
+
+/// React to a posted report. Failure is only a warning, because the report
+/// itself was posted, and a missing Add Reactions permission shouldn't make
+/// it look like it wasn't.
+async fn add_reaction(
+    client: &Client,
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+    emoji: &EmojiReactionType,
+) {
+    if let Err(e) = client
+        .create_reaction(channel_id, message_id, &request_reaction(emoji))
+        .await
+    {
+        tracing::warn!("Failed to react to report {message_id} in {channel_id}: {e:?}");
+    }
+}
+
+/// Start a thread on a posted report. Like reactions, failing only gets
+/// logged, and the report stays where it was posted.
+async fn start_thread(
+    client: &Client,
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+    name: &str,
+) {
+    let result = client
+        .create_thread_from_message(channel_id, message_id, name)
+        .await;
+    match result {
+        Ok(_) => {}
+        Err(e) if api_error_code(&e) == Some(MISSING_PERMISSIONS) => {
+            tracing::warn!(
+                "Missing Create Public Threads permission for report {message_id} in {channel_id}"
+            );
+        }
+        Err(e) => {
+            tracing::warn!("Failed to start thread on report {message_id} in {channel_id}: {e:?}");
+        }
+    }
+}
+
+/// Discord's JSON error code for a channel that doesn't exist (anymore).
+pub const UNKNOWN_CHANNEL: u64 = 10003;
+/// Discord's JSON error code for a channel the bot can't see.
+pub const MISSING_ACCESS: u64 = 50001;
+/// Discord's JSON error code for an action the bot lacks permissions for.
+pub const MISSING_PERMISSIONS: u64 = 50013;
+/// Discord's JSON error code for a user who doesn't accept our DMs.
+pub const CANNOT_MESSAGE_USER: u64 = 50007;
+
+/// Get Discord's JSON error code out of an HTTP error, if it has one.
+pub const fn api_error_code(error: &twilight_http::Error) -> Option<u64> {
+    match error.kind() {
+        ErrorType::Response {
+            error: ApiError::General(general),
+            ..
+        } => Some(general.code),
+        _ => None,
+    }
+}
+
+pub fn build_report_embed(
+    form: &FormConfig,
+    messages: &Messages,
+    reporter: Option<Id<UserMarker>>,
+    mut report: ModmailFormModal,
+) -> Embed {
+    // Reporters often paste a mention or ID, which is much more useful as a
+    // clickable mention than as the raw text.
+    let mut channel = report.channel.take().map(|channel| {
+        parse_channel_ref(&channel).map_or(channel, |channel_id| format!("<#{channel_id}>"))
+    });
+
+    let mut embed = EmbedBuilder::new();
+    if let Some(template) = &form.description_template {
+        let description =
+            render_description(template, messages, reporter, &report, channel.as_deref());
+        embed = embed.description(description);
+    }
+    let fields = form
+        .field_order()
+        .filter(|_| form.description_template.is_none());
+    for field in fields {
+        let value = match field {
+            ReportField::User => report.user.take(),
+            ReportField::Channel => channel.take(),
+            ReportField::MessageLink => report.message_link.take(),
+            ReportField::Reason => {
+                let note = if form.long_reason_attachment {
+                    &messages.truncated_attached
+                } else {
+                    &messages.truncated
+                };
+                let reason = truncate(
+                    std::mem::take(&mut report.reason),
+                    EMBED_FIELD_MAX_CHARS.into(),
+                    note,
+                );
+                Some(reason)
+            }
+        };
+        // Optional fields left empty come through as empty strings.
+        let Some(value) = value.filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        let mut builder = EmbedFieldBuilder::new(field.name(), value);
+        if form.is_inline(field) {
+            builder = builder.inline();
+        }
+        embed = embed.field(builder);
+    }
+    let custom = form
+        .custom_fields
+        .iter()
+        .filter(|_| form.description_template.is_none());
+    for field in custom {
+        // Optional fields left empty come through as empty strings.
+        if let Some(value) = report.custom.remove(&field.id).filter(|v| !v.is_empty()) {
+            embed = embed.field(EmbedFieldBuilder::new(&field.label, value));
+        }
+    }
+    // Already validated when the config was loaded.
+    if let Some(thumbnail) = form
+        .thumbnail_url
+        .clone()
+        .and_then(|url| ImageSource::url(url).ok())
+    {
+        embed = embed.thumbnail(thumbnail);
+    }
+    if let Some(image) = form
+        .image_url
+        .clone()
+        .and_then(|url| ImageSource::url(url).ok())
+    {
+        embed = embed.image(image);
+    }
+    let mut embed = embed.build();
+    fit_embed(&mut embed, &messages.truncated);
+    embed
+}
+
+/// Discord's limit on the length of all text in an embed together.
+const EMBED_TOTAL_MAX_CHARS: usize = 6000;
+
+/// Fields which are shortened first when a report doesn't fit in an embed,
+/// after any custom fields.
+const SHRINK_ORDER: [ReportField; 4] = [
+    ReportField::MessageLink,
+    ReportField::Channel,
+    ReportField::User,
+    ReportField::Reason,
+];
+
+/// Shorten the fields of `embed`, least important first, and then its
+/// description until everything fits in one embed.
+fn fit_embed(embed: &mut Embed, note: &str) {
+    let is_custom = |name: &str| !ReportField::ALL.iter().any(|field| field.name() == name);
+    let indices: Vec<_> = (0..embed.fields.len())
+        .filter(|&index| is_custom(&embed.fields[index].name))
+        .chain(
+            SHRINK_ORDER
+                .iter()
+                .filter_map(|field| embed.fields.iter().position(|f| f.name == field.name())),
+        )
+        .collect();
+    for index in indices {
+        let excess = embed_len(embed).saturating_sub(EMBED_TOTAL_MAX_CHARS);
+        if excess == 0 {
+            return;
+        }
+        let value = std::mem::take(&mut embed.fields[index].value);
+        let max = value.chars().count().saturating_sub(excess);
+        embed.fields[index].value = truncate(value, max, note);
+    }
+    let excess = embed_len(embed).saturating_sub(EMBED_TOTAL_MAX_CHARS);
+    if excess > 0 {
+        embed.description = embed.description.take().map(|description| {
+            let max = description.chars().count().saturating_sub(excess);
+            truncate(description, max, note)
+        });
+    }
+}
+
+/// How much of [`EMBED_TOTAL_MAX_CHARS`] an embed uses.
+fn embed_len(embed: &Embed) -> usize {
+    let len = |text: Option<&str>| text.map_or(0, |text| text.chars().count());
+    len(embed.title.as_deref())
+        + len(embed.description.as_deref())
+        + len(embed.footer.as_ref().map(|footer| footer.text.as_str()))
+        + len(embed.author.as_ref().map(|author| author.name.as_str()))
+        + embed
+            .fields
+            .iter()
+            .map(|field| field.name.chars().count() + field.value.chars().count())
+            .sum::<usize>()
+}
+
+/// Fill in a `description_template`. Values are escaped, so reporters can't
+/// change the formatting of the rest of the description.
+fn render_description(
+    template: &str,
+    messages: &Messages,
+    reporter: Option<Id<UserMarker>>,
+    report: &ModmailFormModal,
+    channel: Option<&str>,
+) -> String {
+    let escape = |value: Option<&str>| value.map(escape_markdown);
+    let user = escape(report.user.as_deref());
+    let channel = escape(channel);
+    let link = report.message_link.as_deref().map(|link| {
+        // Escaping would break links, and they can't be formatted anyway.
+        if link.starts_with("https://") && !link.contains(char::is_whitespace) {
+            link.to_string()
+        } else {
+            escape_markdown(link)
+        }
+    });
+    let reason = escape_markdown(&report.reason);
+    let reporter = reporter.map(|reporter| format!("<@{reporter}>"));
+    let custom: BTreeMap<_, _> = report
+        .custom
+        .iter()
+        .map(|(id, value)| (id.as_str(), escape_markdown(value)))
+        .collect();
+    let description = template::render(template, |name| match name {
+        "user" => user.as_deref(),
+        "channel" => channel.as_deref(),
+        "link" => link.as_deref(),
+        "reason" => Some(reason.as_str()),
+        "reporter" => reporter.as_deref(),
+        _ => custom.get(name).map(String::as_str),
+    });
+    truncate(
+        description,
+        EMBED_DESCRIPTION_MAX_CHARS,
+        &messages.truncated,
+    )
+}
+
+/// Put a backslash before everything Discord would treat as formatting.
+/// Quotes, headings and lists only start lines, so mentions like `<#1>` keep
+/// working.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut line_start = true;
+    for c in text.chars() {
+        let special = matches!(c, '\\' | '*' | '_' | '~' | '`' | '|' | '[' | ']')
+            || (line_start && matches!(c, '>' | '#' | '-'));
+        if special {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+    }
+    escaped
+}
+
+/// Discord's limit on the length of an embed field value.
+const EMBED_FIELD_MAX_CHARS: u16 = 1024;
+
+/// Shorten `text` to at most `max` characters, ending it with `note` if
+/// anything had to be cut off.
+pub fn truncate(mut text: String, max: usize, note: &str) -> String {
+    if text.chars().count() <= max {
+        return text;
+    }
+    let keep = max.saturating_sub(note.chars().count());
+    let cut = text.char_indices().nth(keep).map_or(text.len(), |(i, _)| i);
+    text.truncate(cut);
+    text.push_str(note);
+    text
+}
+
+/// Parse a channel mention (`<#id>`) or a bare channel ID. Anything else,
+/// like `#minecraft`, is free text and returns `None`.
+pub fn parse_channel_ref(input: &str) -> Option<Id<ChannelMarker>> {
+    let input = input.trim();
+    let id = input
+        .strip_prefix("<#")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .unwrap_or(input);
+    // Snowflakes have been at least 17 digits long since 2015.
+    if !(17..=20).contains(&id.len()) || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    id.parse().ok()
+}
+
 struct PingPong;
 
 impl IntoResponse for PingPong {
@@ -277,11 +1838,20 @@ impl IntoResponse for PingPong {
     }
 }
 
+/// None of our commands offer autocomplete, so there's nothing to suggest.
+fn no_choices() -> InteractionResponse {
+    let data = InteractionResponseDataBuilder::new().choices([]).build();
+    InteractionResponse {
+        kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+        data: Some(data),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ModalResponse {
-    title: String,
-    custom_id: String,
-    components: Vec<Component>,
+    pub title: String,
+    pub custom_id: String,
+    pub components: Vec<Component>,
 }
 
 impl IntoResponse for ModalResponse {
@@ -300,10 +1870,92 @@ impl IntoResponse for ModalResponse {
 
 #[derive(thiserror::Error, Debug)]
 pub enum InteractError {
-    #[error("HTTP error: {0}")]
+    // The Display impls are shown to users, so Discord's errors are translated
+    // into something actionable. The details still end up in the logs through
+    // Debug.
+    #[error("{}", http_error_message(.0))]
     Http(#[from] twilight_http::Error),
+    #[error("Discord sent a response I couldn't understand. Please try again in a moment.")]
+    Deserialize(#[from] DeserializeBodyError),
+    #[error("Invalid emoji: {0}")]
+    Emoji(#[from] EmojiError),
+    #[error("{0}")]
+    Key(#[from] KeyError),
+    #[error("This report has expired. Please fill out the form again.")]
+    ReportExpired,
     #[error("Discord did not send a user where they were required to")]
     NoUser,
+    #[error("This command is only available to administrators in dev mode")]
+    DevOnly,
+    #[error("Reporting with /report is not set up in this server")]
+    NoReportChannel,
+    #[error("The {0} option is required in this server")]
+    MissingOption(&'static str),
+    #[error("The reason can be at most {0} characters long")]
+    ReasonTooLong(u16),
+    #[error("That channel isn't in this server")]
+    ChannelNotInGuild,
+    #[error("You already have a report form open.")]
+    FormAlreadyOpen,
+    #[error("Sorry, this report can't be accepted.")]
+    BotReporter,
+    #[error("You can't send reports in this server.")]
+    Blocked,
+    #[error("This is temporarily unavailable because the database can't be reached. Please try again in a moment.")]
+    Store(#[from] sqlx::Error),
+    #[error("You don't have permission to resolve reports")]
+    CannotResolve,
+    #[error("That report isn't claimed by anyone")]
+    NotClaimed,
+    #[error("Only <@{0}>, who claimed this report, or an administrator can reassign it")]
+    CannotReassign(Id<UserMarker>),
+    #[error("Use this in the thread or forum post of a report, or pass a link to the report")]
+    NoReportGiven,
+    #[error("Pick a target or an age, so not every report is resolved at once")]
+    NoBulkFilter,
+    #[error("That message can be at most {0} characters long")]
+    MessageLength(usize),
+    #[error(transparent)]
+    CustomId(#[from] CustomIdTooLong),
+    #[error("Only members with the <@&{0}> role can send reports here.")]
+    MissingRequiredRole(Id<RoleMarker>),
+    /// Discord shows the time the reporter may submit again as a countdown.
+    #[error("You're sending reports too quickly. You can report again <t:{0}:R>.")]
+    RateLimited(u64),
+    #[error(transparent)]
+    Field(#[from] FieldError),
+    #[error("The channel you entered is {0}. Please mention a channel like #general.")]
+    ChannelNotAllowed(&'static str),
+    #[error("We're getting a lot of reports right now. Please try again in a minute.")]
+    Busy,
+    #[error("Reports can't be accepted right now because the bot is shutting down")]
+    QueueClosed,
+}
+
+const fn http_error_message(error: &twilight_http::Error) -> &'static str {
+    match api_error_code(error) {
+        Some(MISSING_PERMISSIONS) => {
+            "I don't have permission to do that there. Please check my role and the channel's permissions."
+        }
+        Some(MISSING_ACCESS) => {
+            "I can't see that channel. Please make sure I have the View Channel permission there."
+        }
+        Some(UNKNOWN_CHANNEL) => {
+            "That channel doesn't exist anymore. Please ask an administrator to set up the form again."
+        }
+        _ => "Something went wrong while talking to Discord. Please try again in a moment.",
+    }
+}
+
+/// A form field which doesn't meet the limits its text input declared.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum FieldError {
+    #[error("{0} is required")]
+    Missing(String),
+    #[error("{0} can be at most {1} characters long")]
+    TooLong(String, u16),
+    #[error("{0} must be at least {1} characters long")]
+    TooShort(String, u16),
 }
 
 impl IntoResponse for InteractError {
@@ -311,3 +1963,744 @@ impl IntoResponse for InteractError {
         ErrorReport(self).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        config::{BrigadeConfig, CustomField, FieldOverride},
+        ratelimit::SubmitLimits,
+        testing::{self, MockDiscord},
+    };
+
+    const MODMAIL: u64 = 60;
+    const REPORTER: u64 = 30;
+    const REPORTED: u64 = 31;
+
+    fn report() -> ModmailFormModal {
+        ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: None,
+            channel: None,
+            reason: "Being rude".to_string(),
+            custom: BTreeMap::new(),
+        }
+    }
+
+    /// Discord with a text channel as the modmail channel, and the reporter's
+    /// DMs closed.
+    async fn dms_closed() -> MockDiscord {
+        MockDiscord::start(|request| match request.path.as_str() {
+            "/users/@me/channels" => (StatusCode::OK, testing::channel(80, None, 1)),
+            "/channels/80/messages" => {
+                testing::api_error(StatusCode::FORBIDDEN, CANNOT_MESSAGE_USER)
+            }
+            "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await
+    }
+
+    /// Post a report with `dm_copy` to a reporter with closed DMs, returning
+    /// what was posted to the modmail channel.
+    fn post_to_closed_dms(dm_failure: DmFailure) -> Vec<testing::Request> {
+        testing::runtime().block_on(async {
+            let discord = dms_closed().await;
+            let form = FormConfig {
+                dm_copy: true,
+                dm_failure,
+                ..FormConfig::default()
+            };
+            let state = AppState {
+                forms: testing::forms(Id::new(MODMAIL), form),
+                ..discord.state()
+            };
+            let posted = post_report(
+                &state,
+                Some(Id::new(testing::GUILD)),
+                Id::new(REPORTER),
+                Id::new(MODMAIL),
+                report(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(posted.id, Id::new(70));
+            assert_eq!(discord.requests_to(&Method::POST, "/channels/80").len(), 1);
+            discord.requests_to(&Method::POST, "/channels/60/messages")
+        })
+    }
+
+    fn custom_field(id: &str, required: bool) -> CustomField {
+        CustomField {
+            id: id.to_string(),
+            label: id.to_string(),
+            placeholder: None,
+            required,
+            style: FieldStyle::Short,
+            max_length: Some(10),
+            min_length: Some(3),
+        }
+    }
+
+    /// A form with custom fields `extra` (3 to 10 characters, required) and
+    /// `note` (optional, 3 to 10 characters if given).
+    fn form_with_custom_fields() -> FormConfig {
+        FormConfig {
+            custom_fields: vec![custom_field("extra", true), custom_field("note", false)],
+            ..FormConfig::default()
+        }
+    }
+
+    /// A report which meets every limit of [`form_with_custom_fields`].
+    fn valid_report() -> ModmailFormModal {
+        ModmailFormModal {
+            channel: Some("#general".to_string()),
+            custom: BTreeMap::from([("extra".to_string(), "details".to_string())]),
+            ..report()
+        }
+    }
+
+    fn validate(edit: impl FnOnce(&mut ModmailFormModal)) -> Result<(), FieldError> {
+        let mut report = valid_report();
+        edit(&mut report);
+        validate_report(&form_with_custom_fields(), &report, false)
+    }
+
+    #[test]
+    fn accepts_reports_within_the_limits() {
+        assert!(validate(|_| {}).is_ok());
+        // Optional fields may be left out or empty.
+        assert!(validate(|report| report.message_link = None).is_ok());
+        assert!(validate(|report| {
+            report.custom.insert("note".to_string(), String::new());
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_required_fields() {
+        let missing = |result| matches!(result, Err(FieldError::Missing(name)) if name == "User");
+        assert!(missing(validate(|report| report.user = None)));
+        assert!(missing(validate(|report| {
+            report.user = Some("   ".to_string());
+        })));
+        assert!(matches!(
+            validate(|report| report.reason = " ".to_string()),
+            Err(FieldError::Missing(name)) if name == "Reason"
+        ));
+        assert!(matches!(
+            validate(|report| report.custom.clear()),
+            Err(FieldError::Missing(name)) if name == "extra"
+        ));
+    }
+
+    #[test]
+    fn rejects_fields_over_their_max_length() {
+        let too_long = |result: Result<(), FieldError>, field: &str, max: u16| matches!(result, Err(FieldError::TooLong(name, m)) if name == field && m == max);
+        assert!(too_long(
+            validate(|report| report.user = Some("a".repeat(1001))),
+            "User",
+            1000
+        ));
+        assert!(validate(|report| report.user = Some("a".repeat(1000))).is_ok());
+        assert!(too_long(
+            validate(|report| report.channel = Some("a".repeat(129))),
+            "Channel",
+            128
+        ));
+        assert!(too_long(
+            validate(|report| report.message_link = Some("a".repeat(129))),
+            "Message link",
+            128
+        ));
+        assert!(too_long(
+            validate(|report| report.reason = "a".repeat(1025)),
+            "Reason",
+            EMBED_FIELD_MAX_CHARS
+        ));
+        assert!(too_long(
+            validate(|report| {
+                report.custom.insert("extra".to_string(), "a".repeat(11));
+            }),
+            "extra",
+            10
+        ));
+    }
+
+    #[test]
+    fn rejects_fields_under_their_min_length() {
+        assert!(matches!(
+            validate(|report| {
+                report.custom.insert("extra".to_string(), " ab ".to_string());
+            }),
+            Err(FieldError::TooShort(name, 3)) if name == "extra"
+        ));
+        assert!(matches!(
+            validate(|report| {
+                report.custom.insert("note".to_string(), "ab".to_string());
+            }),
+            Err(FieldError::TooShort(name, 3)) if name == "note"
+        ));
+    }
+
+    #[test]
+    fn skips_fields_the_form_did_not_have() {
+        let form = FormConfig {
+            disabled_fields: vec![ReportField::Channel],
+            ..form_with_custom_fields()
+        };
+        let report = ModmailFormModal {
+            channel: Some("a".repeat(129)),
+            ..valid_report()
+        };
+        assert!(validate_report(&form, &report, false).is_ok());
+        // A preselected user replaces whatever was typed in.
+        let report = ModmailFormModal {
+            user: None,
+            ..valid_report()
+        };
+        assert!(validate_report(&form, &report, true).is_ok());
+    }
+
+    #[test]
+    fn submitting_past_the_limits_gets_an_error() {
+        let state = AppState {
+            forms: testing::forms(Id::new(MODMAIL), form_with_custom_fields()),
+            ..testing::offline_state()
+        };
+        let long_user = "a".repeat(1001);
+        let submit = testing::modal(
+            "form_submit:60",
+            &[
+                ("user", &long_user),
+                ("channel", "#general"),
+                ("reason", "Being rude"),
+                ("extra", "details"),
+            ],
+        );
+        let response = testing::runtime().block_on(handle_interaction(state, submit));
+        assert_eq!(
+            testing::error_message(&response),
+            Some("User can be at most 1000 characters long")
+        );
+    }
+
+    /// A pick of [`REPORTED`] in the user select menu of a setup.
+    fn user_selected() -> Interaction {
+        testing::interaction(json!({
+            "type": 3,
+            "data": {
+                "custom_id": "open_form_user:60",
+                "component_type": 5,
+                "values": [REPORTED.to_string()],
+                "resolved": { "users": { REPORTED.to_string(): testing::user_json(REPORTED) } },
+            },
+        }))
+    }
+
+    /// The custom ID and the text inputs of the form a click opened.
+    fn opened_form(response: &InteractionResponse) -> (&str, Vec<&str>) {
+        let data = response.data.as_ref().unwrap();
+        let inputs = data
+            .components
+            .iter()
+            .flatten()
+            .filter_map(|row| match row {
+                Component::ActionRow(row) => match &row.components[0] {
+                    Component::TextInput(input) => Some(input.custom_id.as_str()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        (data.custom_id.as_deref().unwrap(), inputs)
+    }
+
+    #[test]
+    fn the_select_leaves_out_the_user_field() {
+        let from_button = handle(testing::button("open_form:60"));
+        assert_eq!(
+            opened_form(&from_button),
+            (
+                "form_submit:60",
+                vec!["user", "channel", "message_link", "reason"]
+            )
+        );
+        let from_select = handle(user_selected());
+        assert_eq!(
+            opened_form(&from_select),
+            (
+                "form_submit:60:31",
+                vec!["channel", "message_link", "reason"]
+            )
+        );
+    }
+
+    /// The value of the User field of the report `submit` posted.
+    fn reported_user(submit: Interaction) -> String {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let response = Box::pin(handle_interaction(discord.state(), submit)).await;
+            assert_eq!(
+                testing::content(&response),
+                Some(Messages::default().report_received.as_str())
+            );
+            let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+            let fields = posted[0].body["embeds"][0]["fields"].as_array().unwrap();
+            let user = fields.iter().find(|field| field["name"] == "User").unwrap();
+            user["value"].as_str().unwrap().to_string()
+        })
+    }
+
+    #[test]
+    fn submits_forms_from_the_button() {
+        let submit = testing::modal(
+            "form_submit:60",
+            &[
+                ("user", "wumpus"),
+                ("channel", "#general"),
+                ("message_link", ""),
+                ("reason", "Being rude"),
+            ],
+        );
+        assert_eq!(reported_user(submit), "wumpus");
+    }
+
+    #[test]
+    fn submits_forms_from_the_select_with_the_picked_user() {
+        let submit = testing::modal(
+            "form_submit:60:31",
+            &[
+                ("channel", "#general"),
+                ("message_link", ""),
+                ("reason", "Being rude"),
+            ],
+        );
+        assert_eq!(reported_user(submit), "<@31>");
+    }
+
+    fn handle(interaction: Interaction) -> InteractionResponse {
+        testing::runtime().block_on(handle_interaction(testing::offline_state(), interaction))
+    }
+
+    /// The same interaction, sent from a DM.
+    fn in_dm(mut interaction: Interaction) -> Interaction {
+        interaction.guild_id = None;
+        interaction.user = interaction.member.take().and_then(|member| member.user);
+        interaction
+    }
+
+    #[test]
+    fn pongs_pings() {
+        let response = handle(testing::interaction(json!({ "type": 1 })));
+        assert_eq!(response.kind, InteractionResponseType::Pong);
+        assert!(response.data.is_none());
+    }
+
+    #[test]
+    fn offers_no_autocomplete_choices() {
+        let autocomplete = testing::interaction(json!({
+            "type": 4,
+            "data": { "id": "50", "name": "setup", "type": 1, "options": [] },
+        }));
+        let response = handle(autocomplete);
+        assert_eq!(
+            response.kind,
+            InteractionResponseType::ApplicationCommandAutocompleteResult
+        );
+        assert_eq!(response.data.unwrap().choices, Some(Vec::new()));
+    }
+
+    #[test]
+    fn routes_components_and_modals_by_custom_id() {
+        for mut interaction in [
+            testing::button("resolve_report:40"),
+            testing::modal("resolve_reason:40", &[("reason", "spam")]),
+        ] {
+            interaction.message = Some(serde_json::from_value(testing::message(20, 70)).unwrap());
+            if let Some(member) = &mut interaction.member {
+                member.permissions = Some(Permissions::empty());
+            }
+            let response = handle(interaction);
+            assert_eq!(
+                testing::error_message(&response),
+                Some("You don't have permission to resolve reports")
+            );
+        }
+    }
+
+    #[test]
+    fn answers_commands() {
+        let response = handle(testing::command("invite", &json!([])));
+        assert!(testing::content(&response)
+            .unwrap()
+            .starts_with("https://discord.com/oauth2/authorize?client_id=1"));
+        // /invite works in DMs too.
+        let response = handle(in_dm(testing::command("invite", &json!([]))));
+        assert!(testing::content(&response)
+            .unwrap()
+            .starts_with("https://discord.com/oauth2/authorize"));
+    }
+
+    #[test]
+    fn needs_a_server_for_components_and_modals() {
+        let guild_only = Messages::default().guild_only;
+        for interaction in [
+            testing::button("resolve_report:40"),
+            testing::modal("resolve_reason:40", &[("reason", "spam")]),
+            testing::command("setup", &json!([])),
+        ] {
+            let response = handle(in_dm(interaction));
+            assert_eq!(testing::content(&response), Some(guild_only.as_str()));
+        }
+    }
+
+    fn reason_field(form: &FormConfig, reason: String) -> String {
+        let report = ModmailFormModal { reason, ..report() };
+        let embed = build_report_embed(form, &Messages::default(), None, report);
+        embed
+            .fields
+            .into_iter()
+            .find(|field| field.name == ReportField::Reason.name())
+            .unwrap()
+            .value
+    }
+
+    #[test]
+    fn applies_field_overrides() {
+        let form = FormConfig {
+            fields: HashMap::from([(
+                ReportField::Reason,
+                FieldOverride {
+                    label: Some("Your suggestion".to_string()),
+                    placeholder: Some("e.g. More emoji".to_string()),
+                    required: Some(false),
+                },
+            )]),
+            ..FormConfig::default()
+        };
+        let (name, reason) = form_fields(&form, false)
+            .find(|(_, input)| input.custom_id == "reason")
+            .unwrap();
+        assert_eq!(name, "Reason");
+        assert_eq!(reason.label, "Your suggestion");
+        assert_eq!(reason.placeholder.as_deref(), Some("e.g. More emoji"));
+        assert_eq!(reason.required, Some(false));
+        // The rest keep their defaults.
+        let (_, user) = form_fields(&form, false).next().unwrap();
+        assert_eq!(
+            user.placeholder.as_deref(),
+            Some("e.g. wumpus or 302094807046684672")
+        );
+
+        // An optional reason may be left empty, and isn't shown then.
+        let report = ModmailFormModal {
+            channel: Some("#general".to_string()),
+            reason: String::new(),
+            ..report()
+        };
+        assert!(validate_report(&form, &report, false).is_ok());
+        let embed = build_report_embed(&form, &Messages::default(), None, report);
+        assert!(embed
+            .fields
+            .iter()
+            .all(|field| field.name != ReportField::Reason.name()));
+    }
+
+    #[test]
+    fn open_form_args_take_an_optional_role() {
+        let id = open_form_id(OPEN_FORM, Id::new(60), Some(Id::new(7))).unwrap();
+        let (_, args) = crate::extract::get_custom_id_rpc(&id).unwrap();
+        let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+        let OpenFormArgs {
+            channel,
+            required_role,
+        } = OpenFormArgs::from_args(&args).unwrap();
+        assert_eq!((channel, required_role), (Id::new(60), Some(Id::new(7))));
+
+        let OpenFormArgs { required_role, .. } = OpenFormArgs::from_args(&["60"]).unwrap();
+        assert_eq!(required_role, None);
+        assert!(matches!(
+            OpenFormArgs::from_args(&["60", "7", "8"]),
+            Err(FromCidArgsError::ExtraCustomIdArgs(2, 3))
+        ));
+        assert!(matches!(
+            OpenFormArgs::from_args(&[]),
+            Err(FromCidArgsError::RequiredCustomIdArgMissing(1))
+        ));
+    }
+
+    #[test]
+    fn truncates_past_the_limit() {
+        assert_eq!(truncate("abcde".to_string(), 5, "…"), "abcde");
+        assert_eq!(truncate("abcdef".to_string(), 5, "…"), "abcd…");
+        assert_eq!(truncate("ääääää".to_string(), 5, "…"), "ääää…");
+        // Notes longer than the limit leave nothing of the text.
+        assert_eq!(truncate("abcdef".to_string(), 1, "…!"), "…!");
+    }
+
+    #[test]
+    fn truncates_reasons_at_the_field_limit() {
+        let form = FormConfig {
+            long_reason_attachment: true,
+            ..FormConfig::default()
+        };
+        let max = usize::from(EMBED_FIELD_MAX_CHARS);
+        let fits = "a".repeat(max);
+        assert_eq!(reason_field(&form, fits.clone()), fits);
+
+        let note = Messages::default().truncated_attached;
+        let cut = reason_field(&form, "a".repeat(max + 1));
+        assert_eq!(cut.chars().count(), max);
+        assert!(cut.ends_with(&note));
+    }
+
+    #[test]
+    fn shrinks_custom_fields_first_to_fit_in_one_embed() {
+        let max = usize::from(EMBED_FIELD_MAX_CHARS);
+        let ids = ["a", "b", "c", "d", "e"];
+        let long_field = |id| CustomField {
+            max_length: Some(CUSTOM_FIELD_MAX_CHARS),
+            min_length: None,
+            ..custom_field(id, true)
+        };
+        let form = FormConfig {
+            custom_fields: ids.map(long_field).to_vec(),
+            ..FormConfig::default()
+        };
+        let report = ModmailFormModal {
+            user: Some("u".repeat(1000)),
+            reason: "r".repeat(4000),
+            custom: ids.map(|id| (id.to_string(), "c".repeat(max))).into(),
+            ..report()
+        };
+        let embed = build_report_embed(&form, &Messages::default(), None, report);
+        assert!(embed_len(&embed) <= EMBED_TOTAL_MAX_CHARS);
+        let len = |name: &str| {
+            let field = embed
+                .fields
+                .iter()
+                .find(|field| field.name == name)
+                .unwrap();
+            field.value.chars().count()
+        };
+        assert!(ids.iter().all(|id| len(id) <= max));
+        assert!(len("a") < max);
+        assert_eq!(len(ReportField::Reason.name()), max);
+        assert_eq!(len(ReportField::User.name()), 1000);
+    }
+
+    #[test]
+    fn attaches_reasons_past_the_field_limit() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let form = FormConfig {
+                long_reason_attachment: true,
+                ..FormConfig::default()
+            };
+            let state = AppState {
+                forms: testing::forms(Id::new(MODMAIL), form),
+                ..discord.state()
+            };
+            let max = usize::from(EMBED_FIELD_MAX_CHARS);
+            for reason in ["a".repeat(max), "a".repeat(max + 1)] {
+                let report = ModmailFormModal { reason, ..report() };
+                post_report(
+                    &state,
+                    Some(Id::new(testing::GUILD)),
+                    Id::new(REPORTER),
+                    Id::new(MODMAIL),
+                    report,
+                )
+                .await
+                .unwrap();
+            }
+            let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+            // Messages with attachments are sent as multipart forms, not JSON.
+            assert!(posted[0].body.is_object());
+            assert!(posted[1].body.is_null());
+        });
+    }
+
+    #[test]
+    fn reports_are_posted_while_the_store_is_down() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = AppState {
+                store: Some(testing::broken_store().await),
+                ..discord.state()
+            };
+            let posted = post_report(
+                &state,
+                Some(Id::new(testing::GUILD)),
+                Id::new(REPORTER),
+                Id::new(MODMAIL),
+                report(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(posted.id, Id::new(70));
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages")
+                    .len(),
+                1
+            );
+        });
+    }
+
+    #[test]
+    fn posts_brigaded_reports_as_held() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let form = FormConfig {
+                ping_role: Some(Id::new(7)),
+                brigade: Some(BrigadeConfig {
+                    reporters: 2,
+                    window_secs: 3600,
+                }),
+                ..FormConfig::default()
+            };
+            let state = AppState {
+                forms: testing::forms(Id::new(MODMAIL), form),
+                ..discord.state()
+            };
+            for reporter in [31, 32, 33] {
+                post_report(
+                    &state,
+                    Some(Id::new(testing::GUILD)),
+                    Id::new(reporter),
+                    Id::new(MODMAIL),
+                    report(),
+                )
+                .await
+                .unwrap();
+            }
+
+            let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+            let content: Vec<_> = posted
+                .iter()
+                .map(|post| post.body["content"].as_str().unwrap())
+                .collect();
+            assert_eq!(content[0], "<@&7> Report from <@31>");
+            assert!(
+                content[1].starts_with("<@&7> ⚠️ Possible brigade: 2 users"),
+                "{}",
+                content[1]
+            );
+            assert!(
+                content[2].starts_with("⏸️ Held report from <@33>"),
+                "{}",
+                content[2]
+            );
+            assert_eq!(posted[0].body["allowed_mentions"]["roles"], json!(["7"]));
+            assert!(posted[2].body["allowed_mentions"].get("roles").is_none());
+        });
+    }
+
+    #[test]
+    fn closed_dms_are_only_logged_by_default() {
+        let posted = post_to_closed_dms(DmFailure::Log);
+        assert_eq!(posted.len(), 1);
+    }
+
+    #[test]
+    fn closed_dms_notify_moderators() {
+        let posted = post_to_closed_dms(DmFailure::Notify);
+        assert_eq!(posted.len(), 2);
+        let note = &posted[1].body;
+        assert_eq!(note["message_reference"]["message_id"], "70");
+        assert_eq!(
+            note["content"],
+            "⚠️ Couldn't DM <@30> a copy of their report."
+        );
+        assert_eq!(note["allowed_mentions"]["parse"], json!([]));
+    }
+
+    #[test]
+    fn closed_dms_fall_back_to_the_report() {
+        let posted = post_to_closed_dms(DmFailure::Fallback);
+        assert_eq!(posted.len(), 2);
+        let fallback = &posted[1].body;
+        assert_eq!(fallback["message_reference"]["message_id"], "70");
+        let content = fallback["content"].as_str().unwrap();
+        assert!(content.ends_with(&Messages::default().report_copy));
+        assert_eq!(fallback["embeds"], posted[0].body["embeds"]);
+    }
+
+    #[test]
+    fn closed_dms_dont_fail_the_submission() {
+        let submit = testing::modal(
+            "form_submit:60",
+            &[
+                ("user", "wumpus"),
+                ("channel", "#general"),
+                ("message_link", ""),
+                ("reason", "Being rude"),
+            ],
+        );
+        testing::runtime().block_on(async {
+            let discord = dms_closed().await;
+            let form = FormConfig {
+                dm_copy: true,
+                ..FormConfig::default()
+            };
+            let state = AppState {
+                forms: testing::forms(Id::new(MODMAIL), form),
+                ..discord.state()
+            };
+            let response = Box::pin(handle_interaction(state, submit)).await;
+            assert_eq!(testing::error_message(&response), None);
+            assert_eq!(
+                testing::content(&response),
+                Some(Messages::default().report_received.as_str())
+            );
+            assert_eq!(discord.requests_to(&Method::POST, "/channels/80").len(), 1);
+        });
+    }
+
+    #[test]
+    fn rate_limited_reporters_see_when_they_can_report_again() {
+        let state = AppState {
+            submit_limits: Arc::new(SubmitLimits::new(1, Duration::from_mins(1))),
+            ..testing::offline_state()
+        };
+        let (reporter, channel) = (Id::new(REPORTER), Id::new(MODMAIL));
+        assert!(check_submit_limit(&state, Id::new(testing::GUILD), reporter, channel).is_ok());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let until =
+            check_submit_limit(&state, Id::new(testing::GUILD), reporter, channel).unwrap_err();
+        assert!(
+            (now + 59..=now + 61).contains(&until),
+            "{until} isn't a minute after {now}"
+        );
+        assert_eq!(
+            InteractError::RateLimited(until).to_string(),
+            format!("You're sending reports too quickly. You can report again <t:{until}:R>.")
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{extract::SlashCommand, AppState};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "invite", desc = "Get a link to add this bot to a server")]
+pub struct InviteCommand;
+
+pub async fn invite(
+    State(state): State<AppState>,
+    SlashCommand(InviteCommand): SlashCommand<InviteCommand>,
+) -> InteractionResponse {
+    let url = format!(
+        "https://discord.com/oauth2/authorize?client_id={}&scope=bot+applications.commands&permissions={}",
+        state.application_id,
+        state.required_permissions.bits()
+    );
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(url)
+        .build();
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use niloecl::State;
+use tokio::task::JoinSet;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    guild::Permissions,
+    http::interaction::InteractionResponse,
+    id::{marker::ChannelMarker, Id},
+};
+
+use crate::{
+    extract::{ExtractGuildId, ExtractMember, InteractionToken, SlashCommand},
+    interact::{check_report, fetch_channel, InteractError, ModmailFormModal, Submitter},
+    progress::deferred_ephemeral,
+    queue::submit_report,
+    AppState,
+};
+
+/// Only registered when `AGHAST_DEV` is set.
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "loadtest",
+    desc = "Submit synthetic reports to measure throughput (dev only)",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct LoadtestCommand {
+    /// How many reports to submit
+    #[command(min_value = 1, max_value = 100)]
+    count: i64,
+    /// The channel to submit the reports to
+    modmail_channel: Id<ChannelMarker>,
+}
+
+impl LoadtestCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+pub async fn loadtest(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    InteractionToken(token): InteractionToken,
+    SlashCommand(cmd): SlashCommand<LoadtestCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    // Discord enforces default_permissions, but this command can post a lot of
+    // messages, so check again in case the command was left registered.
+    let is_admin = member
+        .permissions
+        .is_some_and(|p| p.contains(Permissions::ADMINISTRATOR));
+    if !state.dev || !is_admin {
+        return Err(InteractError::DevOnly);
+    }
+    let reporter = member.user.ok_or(InteractError::NoUser)?.id;
+    // Reports may only go to this server's moderators.
+    let channel = fetch_channel(&state, cmd.modmail_channel)
+        .await
+        .ok_or(InteractError::ChannelNotInGuild)?;
+    if channel.guild_id != Some(guild_id) {
+        return Err(InteractError::ChannelNotInGuild);
+    }
+
+    tokio::spawn(async move {
+        let submitter = Submitter {
+            guild_id,
+            user: reporter,
+            roles: &member.roles,
+        };
+        let summary = run(&state, submitter, cmd).await;
+        if let Err(e) = state
+            .client
+            .interaction(state.application_id)
+            .update_response(&token)
+            .content(Some(&summary))
+            .await
+        {
+            tracing::error!("Failed to send loadtest results: {e:?}");
+        }
+    });
+
+    Ok(deferred_ephemeral())
+}
+
+/// Submit the reports like the form would, so rate limits, brigade
+/// detection, the queue and the store all see them.
+async fn run(state: &AppState, submitter: Submitter<'_>, cmd: LoadtestCommand) -> String {
+    let start = Instant::now();
+    let mut tasks = JoinSet::new();
+    for i in 0..cmd.count {
+        let state = state.clone();
+        let roles = submitter.roles.to_vec();
+        let (guild_id, reporter) = (submitter.guild_id, submitter.user);
+        let mut report = ModmailFormModal {
+            user: Some("loadtest".to_string()),
+            message_link: None,
+            channel: Some(format!("<#{}>", cmd.modmail_channel)),
+            reason: format!("Synthetic report {} of {}", i + 1, cmd.count),
+            custom: BTreeMap::new(),
+        };
+        tasks.spawn(async move {
+            let report_start = Instant::now();
+            let submitter = Submitter {
+                guild_id,
+                user: reporter,
+                roles: &roles,
+            };
+            let result = async {
+                check_report(&state, submitter, cmd.modmail_channel, &mut report, None).await?;
+                submit_report(&state, guild_id, reporter, cmd.modmail_channel, report).await
+            }
+            .await;
+            (report_start.elapsed(), result)
+        });
+    }
+
+    let mut timings = Vec::with_capacity(tasks.len());
+    // Rejections are expected under load, so they're grouped by reason.
+    let mut failures: BTreeMap<String, usize> = BTreeMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((elapsed, Ok(()))) => timings.push(elapsed),
+            Ok((_, Err(e))) => {
+                tracing::warn!("loadtest report failed: {e:?}");
+                *failures.entry(e.to_string()).or_default() += 1;
+            }
+            Err(e) => {
+                tracing::error!("loadtest task panicked: {e:?}");
+                *failures.entry("The task panicked".to_string()).or_default() += 1;
+            }
+        }
+    }
+    let total = start.elapsed();
+
+    let min = timings.iter().min().copied().unwrap_or_default();
+    let max = timings.iter().max().copied().unwrap_or_default();
+    let avg = u32::try_from(timings.len())
+        .ok()
+        .filter(|&n| n > 0)
+        .map_or(Duration::ZERO, |n| timings.iter().sum::<Duration>() / n);
+    let mut summary = format!(
+        "Submitted {} of {} reports in {total:.2?} ({} failed)\n\
+         min {min:.2?} / avg {avg:.2?} / max {max:.2?}",
+        timings.len(),
+        cmd.count,
+        failures.values().sum::<usize>(),
+    );
+    for (error, count) in failures {
+        let _ = write!(summary, "\n×{count}: {error}");
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::{
+        ratelimit::SubmitLimits,
+        testing::{self, MockDiscord},
+    };
+
+    const MODMAIL: u64 = 60;
+
+    fn loadtest_command(modmail_channel: u64) -> Interaction {
+        testing::command(
+            "loadtest",
+            &json!([
+                { "name": "count", "type": 4, "value": 3 },
+                { "name": "modmail_channel", "type": 7, "value": modmail_channel.to_string() },
+            ]),
+        )
+    }
+
+    #[test]
+    fn rejects_channels_of_other_servers() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60" => (
+                    StatusCode::OK,
+                    testing::channel(MODMAIL, Some(Id::new(11)), 0),
+                ),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = AppState {
+                dev: true,
+                ..discord.state()
+            };
+            let response = niloecl::make_handler(loadtest)(loadtest_command(MODMAIL), state).await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some("That channel isn't in this server")
+            );
+            assert!(discord
+                .requests_to(&Method::POST, "/channels/60/messages")
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn goes_through_the_rate_limit() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = AppState {
+                submit_limits: Arc::new(SubmitLimits::new(1, Duration::from_mins(1))),
+                ..discord.state()
+            };
+            let submitter = Submitter {
+                guild_id: Id::new(testing::GUILD),
+                user: Id::new(30),
+                roles: &[],
+            };
+            let cmd = LoadtestCommand {
+                count: 3,
+                modmail_channel: Id::new(MODMAIL),
+            };
+            let summary = run(&state, submitter, cmd).await;
+            assert!(summary.starts_with("Submitted 1 of 3 reports"), "{summary}");
+            assert!(summary.contains("×2: You're sending reports too quickly"));
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages")
+                    .len(),
+                1
+            );
+        });
+    }
+
+    #[test]
+    fn numbers_concurrent_reports_uniquely() {
+        testing::runtime().block_on(async {
+            let posts = Arc::new(AtomicUsize::new(0));
+            let discord = MockDiscord::start({
+                let posts = posts.clone();
+                move |request| match request.path.as_str() {
+                    "/channels/60/messages" if posts.fetch_add(1, Ordering::Relaxed) == 3 => (
+                        StatusCode::FORBIDDEN,
+                        json!({ "code": 50013, "message": "" }),
+                    ),
+                    "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                    _ => (StatusCode::NOT_FOUND, json!({})),
+                }
+            })
+            .await;
+            let state = AppState {
+                store: Some(testing::store().await),
+                ..discord.state()
+            };
+            let submitter = Submitter {
+                guild_id: Id::new(testing::GUILD),
+                user: Id::new(30),
+                roles: &[],
+            };
+            let cmd = LoadtestCommand {
+                count: 20,
+                modmail_channel: Id::new(MODMAIL),
+            };
+            let summary = run(&state, submitter, cmd).await;
+            assert!(
+                summary.starts_with("Submitted 19 of 20 reports"),
+                "{summary}"
+            );
+
+            // Including the one which failed to post, whose number is skipped.
+            let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+            let mut titles: Vec<_> = posted
+                .iter()
+                .map(|request| request.body["embeds"][0]["title"].as_str().unwrap())
+                .collect();
+            titles.sort_unstable();
+            titles.dedup();
+            let mut expected: Vec<_> = (1..=20).map(|n| format!("Report #{n}")).collect();
+            expected.sort_unstable();
+            assert_eq!(titles, expected);
+        });
+    }
+}
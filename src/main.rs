@@ -1,36 +1,114 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery)]
 #![allow(clippy::module_name_repetitions)]
-use std::{fmt::Debug, future::IntoFuture, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    future::IntoFuture,
+    net::{AddrParseError, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
+use arc_swap::ArcSwap;
 use axum::{
     body::Bytes,
     extract::State,
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderValue, StatusCode},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
-use ed25519_dalek::{Signature, Verifier, VerifyingKey};
-use hex::FromHex;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{ExporterBuildError, WithExportConfig};
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
 use tokio::net::TcpListener;
+use tower_http::set_header::SetResponseHeaderLayer;
+use tracing::{Instrument, Subscriber};
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
 use twilight_http::Client;
 use twilight_interactions::command::CreateCommand;
 use twilight_model::{
-    application::interaction::Interaction, http::interaction::InteractionResponse,
+    application::{command::Command, interaction::Interaction},
+    guild::Permissions,
+    http::interaction::InteractionResponse,
+    id::{
+        marker::{ApplicationMarker, ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
 };
-use valk_utils::get_var;
+use valk_utils::{get_var, parse_var_or};
 
+mod appinfo;
+mod assign;
+mod audit;
+mod availability;
+mod backup;
+mod blocklist;
+mod brigade;
+mod bulk_resolve;
+mod config;
+mod confirm;
+mod cooldown;
+mod debug_cid;
+mod diagnose;
+mod emoji;
+mod errors;
+mod escalate;
 mod extract;
+mod features;
+mod forget_me;
 mod interact;
+mod invite;
+mod loadtest;
+mod messages;
+mod metrics;
+mod open_forms;
+mod outbox;
+mod paginate;
+mod ping_batch;
+mod plugin;
+mod preview;
+mod progress;
+mod queue;
+mod ratelimit;
+mod reassign;
+mod report;
+mod resolve;
+mod retention;
+mod selftest;
+mod set_message;
+mod store;
+mod template;
+mod testdm;
+#[cfg(test)]
+mod testing;
+mod verify;
+mod watchdog;
 
 fn main() {
+    install_crypto_provider();
+    let otlp = init_logging();
+    if run_store_command() {
+        return;
+    }
     let token = get_var("AGHAST_TOKEN");
+    let dev = parse_var_or("AGHAST_DEV", false);
+    let early_defer = parse_var_or("AGHAST_EARLY_DEFER", false);
+    let queue_size: usize = parse_var_or("AGHAST_QUEUE_SIZE", 0);
+    let workers: usize = parse_var_or("AGHAST_WORKERS", 1);
+    let watchdog_secs: u64 = parse_var_or("AGHAST_WATCHDOG_SECS", 10);
+    let signature_tolerance_secs: u64 = parse_var_or("AGHAST_SIGNATURE_TOLERANCE_SECS", 300);
+    let fallback_channel = fallback_channel();
+    let bind = bind_addr();
+    let plugin = load_plugin();
+    let audit = start_audit_log();
+    let mut config = load_config();
 
-    let rt = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .thread_name("aghast-main")
-        .build()
-        .unwrap();
+    let rt = build_runtime(workers);
+    let store = open_store(&rt);
 
     let client = Client::new(token);
 
@@ -43,34 +121,77 @@ fn main() {
             .await
             .expect("Failed to deserialize current user")
     });
-    let key = VerifyingKey::from_bytes(
-        &FromHex::from_hex(bot_info.verify_key).expect("Invalid signature hex"),
-    )
-    .expect("Invalid signature bytes");
+    let key = appinfo::parse_verify_key(&bot_info.verify_key).expect("Invalid verify key");
 
-    rt.block_on(async {
-        client
-            .interaction(bot_info.id)
-            .set_global_commands(&[interact::SetupCommand::create_command().into()])
-            .into_future()
-            .await
-    })
-    .expect("Failed to set global commands");
+    let commands = commands(dev, config.as_ref().map(|(_, config)| config));
+
+    let interaction = client.interaction(bot_info.id);
+    rt.block_on(interaction.set_global_commands(&commands).into_future())
+        .expect("Failed to set global commands");
+
+    let bot_id = bot_info
+        .bot
+        .as_ref()
+        .map_or_else(|| bot_info.id.cast(), |bot| bot.id);
+    if let Some((config_path, config)) = &mut config {
+        if let Err(e) = rt.block_on(config::apply(&client, bot_id, config)) {
+            tracing::error!("Failed to apply setups from {config_path}: {e}");
+            std::process::exit(1);
+        }
+    }
+
+    let config = config.map(|(_, config)| config).unwrap_or_default();
+
+    // 0 posts reports directly, without a queue.
+    let (report_queue, queued_reports) = (queue_size > 0)
+        .then(|| queue::ReportQueue::new(queue_size))
+        .unzip();
 
     let state = AppState {
         client: Arc::new(client),
-        key,
+        application_id: bot_info.id,
+        bot_id,
+        keys: Arc::new(ArcSwap::from_pointee(vec![key])),
+        forms: Arc::new(config::Forms::new(&config)),
+        required_permissions: config.required_permissions(),
+        messages: Arc::new(load_languages(&rt, &config, store.as_ref())),
+        report_channels: Arc::new(config.report_channels),
+        fallback_channel,
+        pending_reports: Arc::default(),
+        brigades: Arc::default(),
+        ping_batches: Arc::default(),
+        open_forms: Arc::default(),
+        submit_limits: Arc::new(submit_limits(&rt, store.as_ref())),
+        resolve_permissions: resolve_permissions(),
+        blocklist: Arc::new(load_blocklist(&rt, store.as_ref())),
+        features: Arc::new(load_features(&rt, store.as_ref())),
+        availability: Arc::new(load_availability(&rt, store.as_ref())),
+        metrics: Arc::default(),
+        plugin,
+        audit,
+        store,
+        report_queue,
+        early_defer,
+        dev,
     };
 
-    let router = Router::new()
-        .route("/api/interactions", post(interaction_handler))
-        .with_state(state);
+    if let Some(rx) = queued_reports {
+        rt.spawn(queue::run(state.clone(), rx));
+    }
+    spawn_store_workers(&rt, &state);
+
+    let router = router(&rt, state, signature_tolerance_secs);
 
     let tcp = rt
-        .block_on(TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], 8080))))
-        .expect("Failed to bind to 8080");
+        .block_on(TcpListener::bind(bind))
+        .unwrap_or_else(|e| panic!("Failed to bind to {bind}: {e}"));
+
+    // 0 turns the watchdog off.
+    if watchdog_secs > 0 {
+        watchdog::start(&rt, Duration::from_secs(watchdog_secs));
+    }
 
-    eprintln!("Event loop started");
+    tracing::info!("Event loop started");
 
     rt.block_on(
         axum::serve(tcp, router)
@@ -78,46 +199,495 @@ fn main() {
             .into_future(),
     )
     .expect("Could not run server");
+
+    if let Some(otlp) = otlp {
+        if let Err(e) = otlp.shutdown() {
+            tracing::warn!("Failed to send the last spans to AGHAST_OTLP_ENDPOINT: {e}");
+        }
+    }
 }
 
-async fn interaction_handler(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    body: Bytes,
-) -> Result<Json<InteractionResponse>, RequestError> {
-    // Extract the timestamp header for use later to check the signature.
-    let timestamp = headers
-        .get("x-signature-timestamp")
-        .ok_or(RequestError::BadSignature)?;
+/// reqwest is built without a TLS provider of its own, so the plugin and OTLP
+/// clients need the one twilight uses set as the default before they're built.
+fn install_crypto_provider() {
+    // Only fails if one is installed already.
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+}
+
+/// Log to stderr, filtered by `RUST_LOG`. Logs everything from `info` up by
+/// default, as text or, if `AGHAST_LOG_FORMAT` is `json`, one JSON object per
+/// line. Spans are also sent to `AGHAST_OTLP_ENDPOINT` if it's set, until the
+/// returned provider is shut down.
+fn init_logging() -> Option<SdkTracerProvider> {
+    let format = std::env::var("AGHAST_LOG_FORMAT").ok();
+    let format = parse_log_format(format.as_deref()).map_err(|()| format);
+    let otlp = std::env::var("AGHAST_OTLP_ENDPOINT")
+        .ok()
+        .map(|endpoint| otlp_provider(&endpoint).map_err(|e| (endpoint, e)));
+    let otlp_layer = otlp
+        .as_ref()
+        .and_then(|otlp| otlp.as_ref().ok())
+        .map(|provider| {
+            tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        });
+    let rust_log = std::env::var("RUST_LOG").ok();
+    tracing_subscriber::registry()
+        .with(log_filter(rust_log.as_deref()))
+        .with(fmt_layer(
+            format.as_ref().ok().copied().unwrap_or(LogFormat::Text),
+            std::io::stderr,
+        ))
+        .with(otlp_layer)
+        .init();
+    if let Err(format) = format {
+        tracing::error!("Invalid AGHAST_LOG_FORMAT {format:?}, expected text or json");
+        std::process::exit(1);
+    }
+    otlp.transpose().unwrap_or_else(|(endpoint, e)| {
+        tracing::error!("Invalid AGHAST_OTLP_ENDPOINT {endpoint:?}: {e}");
+        std::process::exit(1);
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    /// For people reading the terminal.
+    Text,
+    /// For log collectors.
+    Json,
+}
+
+fn parse_log_format(format: Option<&str>) -> Result<LogFormat, ()> {
+    match format {
+        None | Some("text") => Ok(LogFormat::Text),
+        Some("json") => Ok(LogFormat::Json),
+        Some(_) => Err(()),
+    }
+}
+
+/// `RUST_LOG`'s directives, or `info` if it's unset or invalid.
+fn log_filter(rust_log: Option<&str>) -> EnvFilter {
+    rust_log
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| EnvFilter::new("info"))
+}
+
+fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+/// Export spans over HTTP to `endpoint`, like
+/// `http://localhost:4318/v1/traces`. Spans are batched on a thread of their
+/// own, so handling interactions doesn't wait for the collector.
+fn otlp_provider(endpoint: &str) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+    let resource = Resource::builder()
+        .with_service_name(env!("CARGO_PKG_NAME"))
+        .build();
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+/// Load `AGHAST_CONFIG_FILE`, if set, exiting if it's invalid.
+fn load_config() -> Option<(String, config::Config)> {
+    let path = std::env::var("AGHAST_CONFIG_FILE").ok()?;
+    let config = config::Config::load(path.as_ref()).unwrap_or_else(|e| {
+        tracing::error!("Invalid config file {path}: {e}");
+        std::process::exit(1);
+    });
+    Some((path, config))
+}
+
+/// Read `AGHAST_FALLBACK_CHANNEL`, if set, exiting if it's not a channel ID.
+fn fallback_channel() -> Option<Id<ChannelMarker>> {
+    let id = std::env::var("AGHAST_FALLBACK_CHANNEL").ok()?;
+    let channel = id.parse().unwrap_or_else(|e| {
+        tracing::error!("Invalid AGHAST_FALLBACK_CHANNEL {id:?}, expected a channel ID: {e}");
+        std::process::exit(1);
+    });
+    Some(channel)
+}
+
+/// Read `AGHAST_BIND`, exiting if it's invalid.
+fn bind_addr() -> SocketAddr {
+    let bind = std::env::var("AGHAST_BIND").ok();
+    parse_bind(bind.as_deref()).unwrap_or_else(|e| {
+        tracing::error!("Invalid AGHAST_BIND {bind:?}, expected an address and port: {e}");
+        std::process::exit(1);
+    })
+}
+
+/// Parse a listen address like `127.0.0.1:8080` or `[::1]:9000`. Listens on
+/// port 8080 of every IPv4 interface by default.
+fn parse_bind(bind: Option<&str>) -> Result<SocketAddr, AddrParseError> {
+    bind.map_or_else(|| Ok(SocketAddr::from(([0, 0, 0, 0], 8080))), str::parse)
+}
+
+fn router(rt: &tokio::runtime::Runtime, state: AppState, signature_tolerance_secs: u64) -> Router {
+    let (router, metrics) = routes(state, signature_tolerance_secs);
+    serve_metrics(rt, router, metrics)
+}
+
+/// The public routes, and `/metrics`, which may be served apart from them.
+fn routes(state: AppState, signature_tolerance_secs: u64) -> (Router, Router) {
+    let verification = verify::SignatureVerificationLayer::new(
+        state.keys.clone(),
+        signature_tolerance_secs,
+        state.metrics.clone(),
+    );
+    let metrics = Router::new()
+        .route("/metrics", get(metrics::metrics).layer(no_store()))
+        .with_state(state.clone());
+    let router = Router::new()
+        .route(
+            "/api/interactions",
+            post(interaction_handler).layer(verification),
+        )
+        .route("/healthz", get(healthz).layer(no_store()))
+        .with_state(state);
+    (router, metrics)
+}
+
+/// Keeps caching proxies from answering health checks and scrapes with stale
+/// responses.
+fn no_store() -> SetResponseHeaderLayer<HeaderValue> {
+    SetResponseHeaderLayer::overriding(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))
+}
+
+/// Serve `/metrics` on `AGHAST_METRICS_BIND` if set, so it can be kept off
+/// the public port. Otherwise it's added to `router`.
+fn serve_metrics(rt: &tokio::runtime::Runtime, router: Router, metrics: Router) -> Router {
+    let Ok(bind) = std::env::var("AGHAST_METRICS_BIND") else {
+        return router.merge(metrics);
+    };
+    let bind: SocketAddr = bind.parse().unwrap_or_else(|e| {
+        tracing::error!("Invalid AGHAST_METRICS_BIND {bind:?}, expected an address and port: {e}");
+        std::process::exit(1);
+    });
+    let tcp = rt
+        .block_on(TcpListener::bind(bind))
+        .unwrap_or_else(|e| panic!("Failed to bind to {bind}: {e}"));
+    rt.spawn(async move {
+        if let Err(e) = axum::serve(tcp, metrics).await {
+            tracing::error!("Metrics server stopped: {e:?}");
+        }
+    });
+    router
+}
+
+/// Set up the `AGHAST_PLUGIN_URL` webhook, if any.
+fn load_plugin() -> Option<Arc<plugin::Plugin>> {
+    let url = std::env::var("AGHAST_PLUGIN_URL").ok()?;
+    let timeout_ms: u64 = parse_var_or("AGHAST_PLUGIN_TIMEOUT_MS", 1000);
+    Some(Arc::new(plugin::Plugin::new(
+        url,
+        Duration::from_millis(timeout_ms),
+        std::env::var("AGHAST_PLUGIN_SECRET").ok(),
+    )))
+}
+
+/// Open `AGHAST_AUDIT_LOG`, if set, rotating it at `AGHAST_AUDIT_LOG_MAX_MB`
+/// and keeping `AGHAST_AUDIT_LOG_FILES` rotated files.
+fn start_audit_log() -> Option<audit::AuditLog> {
+    let path = std::env::var("AGHAST_AUDIT_LOG").ok()?;
+    let max_mb: u64 = parse_var_or("AGHAST_AUDIT_LOG_MAX_MB", 100);
+    let max_files: usize = parse_var_or("AGHAST_AUDIT_LOG_FILES", 5);
+    let log = audit::AuditLog::start(path.into(), max_mb * 1024 * 1024, max_files)
+        .expect("Failed to open AGHAST_AUDIT_LOG");
+    Some(log)
+}
+
+/// `aghast backup <file>` copies everything in the store at `AGHAST_DB_PATH`
+/// to a file, and `aghast restore <file>` replaces everything in it with such
+/// a copy. The bot loads what's in the store when it starts, so it should be
+/// stopped while restoring. Returns whether it was one of those instead of
+/// starting the bot.
+fn run_store_command() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (restore, path) = match &args[..] {
+        [] => return false,
+        [command, path] if command == "backup" => (false, path),
+        [command, path] if command == "restore" => (true, path),
+        _ => {
+            tracing::error!("Usage: aghast [backup <file> | restore <file>]");
+            std::process::exit(2);
+        }
+    };
+    let rt = build_runtime(1);
+    let Some(store) = open_store(&rt) else {
+        tracing::error!("AGHAST_DB_PATH isn't set, so there is no store to back up or restore");
+        std::process::exit(1);
+    };
+    let result = if restore {
+        std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| backup::Backup::from_json(&json).map_err(|e| e.to_string()))
+            .and_then(|backup| {
+                rt.block_on(backup.restore(&store))
+                    .map_err(|e| e.to_string())
+            })
+            .map(|rows| format!("Restored {rows} rows from {path}"))
+    } else {
+        rt.block_on(backup::Backup::create(&store))
+            .map_err(|e| e.to_string())
+            .and_then(|backup| backup.to_json().map_err(|e| e.to_string()))
+            .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()))
+            .map(|()| format!("Backed up the store to {path}"))
+    };
+    match result {
+        Ok(done) => tracing::info!("{done}"),
+        Err(e) => {
+            tracing::error!("Failed to {}: {e}", args[0]);
+            std::process::exit(1);
+        }
+    }
+    true
+}
+
+/// Open the report database at `AGHAST_DB_PATH`, if set.
+fn open_store(rt: &tokio::runtime::Runtime) -> Option<store::Store> {
+    let path = std::env::var("AGHAST_DB_PATH").ok()?;
+    let store = rt
+        .block_on(store::Store::open(&path))
+        .expect("Failed to open AGHAST_DB_PATH");
+    Some(store)
+}
+
+/// Work which needs a store: posting reports left in the outbox, escalating
+/// overdue reports, and purging stored reports older than
+/// `AGHAST_RETENTION_DAYS`. 0, the default, keeps them forever.
+fn spawn_store_workers(rt: &tokio::runtime::Runtime, state: &AppState) {
+    let Some(store) = state.store.clone() else {
+        return;
+    };
+    rt.spawn(outbox::run(state.clone(), store.clone()));
+    rt.spawn(escalate::run(state.clone(), store.clone()));
+    let retention_days: u64 = parse_var_or("AGHAST_RETENTION_DAYS", 0);
+    if retention_days > 0 {
+        rt.spawn(retention::run(store, retention_days));
+    }
+}
+
+/// Servers which can't get their changed messages still get the configured
+/// ones, so failing to load them doesn't stop the bot from starting.
+fn load_languages(
+    rt: &tokio::runtime::Runtime,
+    config: &config::Config,
+    store: Option<&store::Store>,
+) -> messages::Languages {
+    let languages = messages::Languages::new(config);
+    if let Some(store) = store {
+        if let Err(e) = rt.block_on(languages.load_overrides(store)) {
+            tracing::error!("Failed to load changed messages from AGHAST_DB_PATH: {e}");
+        }
+    }
+    languages
+}
+
+/// Servers which can't get their features still get their setups' ones, so
+/// failing to load them doesn't stop the bot from starting.
+fn load_features(rt: &tokio::runtime::Runtime, store: Option<&store::Store>) -> features::Features {
+    rt.block_on(features::Features::load(store))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to load features from AGHAST_DB_PATH: {e}");
+            features::Features::default()
+        })
+}
+
+/// Moderators who can't get their availability are assigned reports, which
+/// is better than not starting at all.
+fn load_availability(
+    rt: &tokio::runtime::Runtime,
+    store: Option<&store::Store>,
+) -> availability::Availability {
+    rt.block_on(availability::Availability::load(store))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to load moderator availability from AGHAST_DB_PATH: {e}");
+            availability::Availability::default()
+        })
+}
 
-    // Extract the signature to check against.
-    let signature: Signature = headers
-        .get("x-signature-ed25519")
-        .and_then(|v| v.to_str().ok())
-        .ok_or(RequestError::BadSignature)?
-        .parse()
-        .map_err(|_| RequestError::BadSignature)?;
+/// Starting with an empty blocklist would let blocked users report again, so
+/// this is the one store failure the bot doesn't start without.
+fn load_blocklist(
+    rt: &tokio::runtime::Runtime,
+    store: Option<&store::Store>,
+) -> blocklist::Blocklist {
+    rt.block_on(blocklist::Blocklist::load(store))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to load the blocklist from AGHAST_DB_PATH: {e}");
+            std::process::exit(1);
+        })
+}
+
+/// At most `AGHAST_SUBMIT_LIMIT` reports per user every
+/// `AGHAST_SUBMIT_WINDOW_SECS`, or the server's `/cooldown`. Servers whose
+/// cooldown can't be loaded get the default window, so failing to load them
+/// doesn't stop the bot from starting.
+fn submit_limits(
+    rt: &tokio::runtime::Runtime,
+    store: Option<&store::Store>,
+) -> ratelimit::SubmitLimits {
+    let max: usize = parse_var_or("AGHAST_SUBMIT_LIMIT", 3);
+    let window_secs: u64 = parse_var_or("AGHAST_SUBMIT_WINDOW_SECS", 60);
+    let limits = ratelimit::SubmitLimits::new(max, Duration::from_secs(window_secs));
+    if let Some(store) = store {
+        if let Err(e) = rt.block_on(limits.load_cooldowns(store)) {
+            tracing::error!("Failed to load cooldowns from AGHAST_DB_PATH: {e}");
+        }
+    }
+    limits
+}
 
-    let whole_body = [timestamp.as_bytes(), &body].concat();
+/// `AGHAST_RESOLVE_PERMISSIONS` as a permission integer, Manage Messages by
+/// default.
+fn resolve_permissions() -> Permissions {
+    let bits = parse_var_or(
+        "AGHAST_RESOLVE_PERMISSIONS",
+        Permissions::MANAGE_MESSAGES.bits(),
+    );
+    Permissions::from_bits_truncate(bits)
+}
 
-    if state.key.verify(&whole_body, &signature).is_err() {
-        return Err(RequestError::BadSignature);
+/// One thread is plenty for most bots, so only spread out when asked to.
+fn build_runtime(workers: usize) -> tokio::runtime::Runtime {
+    if workers > 1 {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(workers)
+            .enable_all()
+            .thread_name("aghast-worker")
+            .build()
+            .unwrap()
+    } else {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .thread_name("aghast-main")
+            .build()
+            .unwrap()
     }
+}
 
+fn commands(dev: bool, config: Option<&config::Config>) -> Vec<Command> {
+    let mut commands = vec![
+        interact::SetupCommand::create_command().into(),
+        invite::InviteCommand::create_command().into(),
+        diagnose::DiagnoseCommand::create_command().into(),
+        appinfo::RefreshAppInfoCommand::create_command().into(),
+        preview::PreviewReportCommand::create_command().into(),
+        selftest::SelftestCommand::create_command().into(),
+        errors::ErrorsCommand::create_command().into(),
+        testdm::TestDmCommand::create_command().into(),
+        forget_me::ForgetMeCommand::create_command().into(),
+        blocklist::BlocklistCommand::create_command().into(),
+        set_message::SetMessageCommand::create_command().into(),
+        features::FeaturesCommand::create_command().into(),
+        cooldown::CooldownCommand::create_command().into(),
+        availability::AvailableCommand::create_command().into(),
+        availability::UnavailableCommand::create_command().into(),
+        bulk_resolve::BulkResolveCommand::create_command().into(),
+        reassign::ReassignCommand::create_command().into(),
+    ];
+    if dev {
+        commands.push(loadtest::LoadtestCommand::create_command().into());
+        commands.push(debug_cid::DebugCidCommand::create_command().into());
+    }
+    if config.is_some_and(|config| !config.report_channels.is_empty()) {
+        commands.push(report::ReportCommand::create_command().into());
+    }
+    commands
+}
+
+/// Liveness probe. Everything it could check was set up before the server
+/// started, so it doesn't call Discord.
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Only sees requests [`verify::SignatureVerificationLayer`] let through.
+async fn interaction_handler(
+    State(state): State<AppState>,
+    body: Bytes,
+) -> Result<Json<InteractionResponse>, RequestError> {
     let interaction: Interaction =
         serde_json::from_slice(&body).map_err(|_| RequestError::BadJson)?;
-    let response = Box::pin(interact::handle_interaction(state.clone(), interaction)).await;
+    state.metrics.interaction(interaction.kind);
+    if let Some(audit) = &state.audit {
+        audit.record(body);
+    }
+    let span = tracing::info_span!(
+        "interaction",
+        id = %interaction.id,
+        kind = ?interaction.kind,
+        guild_id = ?interaction.guild_id,
+        user_id = ?interaction.author_id(),
+        // Set to the kind of error by `ErrorReport` if handling fails.
+        outcome = "ok",
+    );
+    let response = Box::pin(interact::handle_interaction(state.clone(), interaction))
+        .instrument(span)
+        .await;
     Ok(Json(response))
 }
 
 #[derive(Clone, Debug)]
 pub struct AppState {
     client: Arc<Client>,
-    key: VerifyingKey,
+    application_id: Id<ApplicationMarker>,
+    bot_id: Id<UserMarker>,
+    /// Requests signed by any of these are accepted. Swapped out at runtime by
+    /// `/refresh-appinfo`.
+    keys: verify::Keys,
+    forms: Arc<config::Forms>,
+    /// Permissions requested by `/invite` and checked by `/diagnose`.
+    required_permissions: Permissions,
+    messages: Arc<messages::Languages>,
+    /// Where reports go if their modmail channel was deleted.
+    fallback_channel: Option<Id<ChannelMarker>>,
+    report_channels: Arc<HashMap<Id<GuildMarker>, Id<ChannelMarker>>>,
+    pending_reports: Arc<confirm::PendingReports>,
+    brigades: Arc<brigade::Brigades>,
+    ping_batches: Arc<ping_batch::PingBatches>,
+    open_forms: Arc<open_forms::OpenForms>,
+    submit_limits: Arc<ratelimit::SubmitLimits>,
+    /// Permissions needed to click "Resolve" on reports.
+    resolve_permissions: Permissions,
+    /// Users who may not send reports, managed with `/blocklist`.
+    blocklist: Arc<blocklist::Blocklist>,
+    /// Features servers turned on or off, managed with `/features`.
+    features: Arc<features::Features>,
+    /// Moderators who opted out of assignment, managed with `/available` and
+    /// `/unavailable`.
+    availability: Arc<availability::Availability>,
+    metrics: Arc<metrics::Metrics>,
+    plugin: Option<Arc<plugin::Plugin>>,
+    audit: Option<audit::AuditLog>,
+    /// Keeps posted reports when `AGHAST_DB_PATH` is set.
+    store: Option<store::Store>,
+    report_queue: Option<queue::ReportQueue>,
+    /// Acknowledge interactions before calling the Discord API, see
+    /// [`progress::respond_early`].
+    early_defer: bool,
+    /// Enables development-only commands such as `/loadtest`.
+    dev: bool,
 }
 
 enum RequestError {
     BadSignature,
+    StaleTimestamp,
+    BadBody,
     BadJson,
 }
 
@@ -128,8 +698,195 @@ impl IntoResponse for RequestError {
                 StatusCode::UNAUTHORIZED,
                 "Bad signature or headers, discord check, bug or misconfiguration",
             ),
+            Self::StaleTimestamp => (
+                StatusCode::UNAUTHORIZED,
+                "Signature timestamp is too far from the current time",
+            ),
+            Self::BadBody => (StatusCode::BAD_REQUEST, "Could not read body"),
             Self::BadJson => (StatusCode::BAD_REQUEST, "Bad JSON body"),
         }
         .into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv6Addr, SocketAddrV6},
+        sync::Mutex,
+    };
+
+    use axum::{body::Body, extract::Request, response::Response};
+    use tower_service::Service;
+
+    use super::*;
+
+    #[test]
+    fn listens_on_every_ipv4_interface_by_default() {
+        assert_eq!(parse_bind(None), Ok(SocketAddr::from(([0, 0, 0, 0], 8080))));
+    }
+
+    #[test]
+    fn accepts_ipv4_and_ipv6_addresses() {
+        assert_eq!(
+            parse_bind(Some("127.0.0.1:9000")),
+            Ok(SocketAddr::from(([127, 0, 0, 1], 9000)))
+        );
+        assert_eq!(
+            parse_bind(Some("[::1]:9000")),
+            Ok(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::LOCALHOST,
+                9000,
+                0,
+                0
+            )))
+        );
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        for bind in [
+            "",
+            "garbage",
+            "127.0.0.1",
+            "::1:9000",
+            "localhost:8080",
+            "127.0.0.1:99999",
+        ] {
+            assert!(parse_bind(Some(bind)).is_err(), "{bind:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn parses_log_formats() {
+        assert_eq!(parse_log_format(None), Ok(LogFormat::Text));
+        assert_eq!(parse_log_format(Some("text")), Ok(LogFormat::Text));
+        assert_eq!(parse_log_format(Some("json")), Ok(LogFormat::Json));
+        assert_eq!(parse_log_format(Some("JSON")), Err(()));
+        assert_eq!(parse_log_format(Some("")), Err(()));
+    }
+
+    /// Everything logged while running `f`, through the same layers as
+    /// [`init_logging`] sets up.
+    fn logs(rust_log: Option<&str>, format: LogFormat, f: impl FnOnce()) -> String {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = {
+            let output = output.clone();
+            move || Output(output.clone())
+        };
+        let subscriber = tracing_subscriber::registry()
+            .with(log_filter(rust_log))
+            .with(fmt_layer(format, writer));
+        tracing::subscriber::with_default(subscriber, f);
+        let output = output.lock().unwrap();
+        String::from_utf8(output.clone()).unwrap()
+    }
+
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn debug_and_info() {
+        tracing::debug!("debug message");
+        tracing::info!("info message");
+    }
+
+    #[test]
+    fn filters_by_rust_log() {
+        for rust_log in [None, Some("not a [directive")] {
+            let output = logs(rust_log, LogFormat::Text, debug_and_info);
+            assert!(output.contains("info message"), "{output}");
+            assert!(!output.contains("debug message"), "{output}");
+        }
+        let output = logs(Some("aghast=debug"), LogFormat::Text, debug_and_info);
+        assert!(output.contains("debug message"), "{output}");
+        let output = logs(Some("warn"), LogFormat::Text, debug_and_info);
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        let output = logs(None, LogFormat::Json, || {
+            tracing::info_span!("outer", guild_id = 10).in_scope(|| {
+                tracing::warn!(user_id = 30, "json message");
+            });
+        });
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["fields"]["message"], "json message");
+        assert_eq!(lines[0]["fields"]["user_id"], 30);
+        assert_eq!(lines[0]["span"]["name"], "outer");
+        assert_eq!(lines[0]["span"]["guild_id"], 10);
+    }
+
+    #[test]
+    fn logs_failed_interactions_in_their_span() {
+        let interaction = testing::button("resolve_report:40");
+        let body = Bytes::from(serde_json::to_vec(&interaction).unwrap());
+        let output = logs(None, LogFormat::Json, || {
+            let handled = testing::runtime()
+                .block_on(interaction_handler(State(testing::offline_state()), body));
+            assert!(handled.is_ok());
+        });
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "ERROR");
+        assert_eq!(line["span"]["name"], "interaction");
+        assert_eq!(line["span"]["id"], "100");
+        assert_eq!(line["span"]["guild_id"], "Some(Id<GuildMarker>(10))");
+        assert_eq!(line["fields"]["error"], "ExtractMessageError");
+    }
+
+    async fn send(router: &mut Router, method: &str, path: &str) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        router.call(request).await.unwrap()
+    }
+
+    #[test]
+    fn healthz_needs_no_signature() {
+        testing::runtime().block_on(async {
+            let (mut router, _) = routes(testing::offline_state(), 5);
+            let response = send(&mut router, "GET", "/healthz").await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, "ok");
+            // Unlike interactions.
+            let interactions = send(&mut router, "POST", "/api/interactions").await;
+            assert_eq!(interactions.status(), StatusCode::UNAUTHORIZED);
+        });
+    }
+
+    #[test]
+    fn health_and_metrics_are_never_cached() {
+        testing::runtime().block_on(async {
+            let (mut router, mut metrics) = routes(testing::offline_state(), 5);
+            for response in [
+                send(&mut router, "GET", "/healthz").await,
+                send(&mut metrics, "GET", "/metrics").await,
+            ] {
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+            }
+            let interactions = send(&mut router, "POST", "/api/interactions").await;
+            assert!(!interactions.headers().contains_key(header::CACHE_CONTROL));
+        });
+    }
+}
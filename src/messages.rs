@@ -0,0 +1,367 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use twilight_interactions::command::{CommandOption, CreateOption};
+use twilight_model::id::{marker::GuildMarker, Id};
+
+use crate::{
+    config::{Config, ConfigError},
+    store::Store,
+};
+
+/// [`Messages`] in every configured language, and which servers use which.
+#[derive(Debug, Default)]
+pub struct Languages {
+    /// `[messages]`, for when no translation applies.
+    fallback: Arc<Messages>,
+    /// `[translations]`, by Discord locale like `de` or `pt-BR`.
+    translations: HashMap<String, Arc<Messages>>,
+    /// `[guild_languages]`, which override the locale of each user.
+    by_guild: HashMap<Id<GuildMarker>, String>,
+    /// Messages servers changed with `/set-message`, in every language.
+    overrides: Mutex<HashMap<Id<GuildMarker>, HashMap<MessageKey, String>>>,
+}
+
+/// A language to answer in, see [`Languages::resolve_language`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang<'a> {
+    /// One of the `[translations]`, by its locale.
+    Translation(&'a str),
+    /// `[messages]`.
+    Fallback,
+}
+
+impl Languages {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            fallback: Arc::new(config.messages.clone()),
+            translations: config
+                .translations
+                .iter()
+                .map(|(locale, messages)| (locale.clone(), Arc::new(messages.clone())))
+                .collect(),
+            by_guild: config.guild_languages.clone(),
+            overrides: Mutex::default(),
+        }
+    }
+
+    /// Add the messages servers changed with `/set-message`. Keys which
+    /// aren't overridable anymore are skipped.
+    pub async fn load_overrides(&self, store: &Store) -> Result<(), sqlx::Error> {
+        for (guild, key, value) in store.message_overrides().await? {
+            if let Some(key) = MessageKey::from_name(&key) {
+                self.set_override(guild, key, Some(value));
+            } else {
+                tracing::warn!("Skipping unknown message {key:?} of {guild}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Change `key` for every interaction from `guild`, or go back to the
+    /// configured message if `value` is `None`.
+    pub fn set_override(&self, guild: Id<GuildMarker>, key: MessageKey, value: Option<String>) {
+        let mut overrides = self
+            .overrides
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let changed = overrides.entry(guild).or_default();
+        match value {
+            Some(value) => changed.insert(key, value),
+            None => changed.remove(&key),
+        };
+        drop(overrides);
+    }
+
+    /// The messages for an interaction from `guild`, by a user with
+    /// `locale`.
+    pub fn get(&self, guild: Option<Id<GuildMarker>>, locale: Option<&str>) -> Arc<Messages> {
+        let guild_default = guild
+            .and_then(|guild| self.by_guild.get(&guild))
+            .map(String::as_str);
+        let messages = match self.resolve_language(guild_default, locale) {
+            Lang::Translation(locale) => &self.translations[locale],
+            Lang::Fallback => &self.fallback,
+        };
+        let overrides = self
+            .overrides
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match guild.and_then(|guild| overrides.get(&guild)) {
+            Some(changed) if !changed.is_empty() => {
+                let mut messages = Messages::clone(messages);
+                for (key, value) in changed {
+                    value.clone_into(key.field(&mut messages));
+                }
+                Arc::new(messages)
+            }
+            _ => messages.clone(),
+        }
+    }
+
+    /// The language of text which isn't an answer to anyone in particular,
+    /// like reports. Moderators may not share the reporter's locale, so
+    /// that's always `[messages]`.
+    pub fn reports(&self) -> &Messages {
+        &self.fallback
+    }
+
+    /// Pick a language, in this order:
+    ///
+    /// 1. The server's language from `[guild_languages]`, so servers which
+    ///    set one are answered consistently.
+    /// 2. The user's locale, if there is a translation for it. A translation
+    ///    for just the language, like `es`, also covers its regions, like
+    ///    `es-ES`.
+    /// 3. `[messages]`.
+    pub fn resolve_language(
+        &self,
+        guild_default: Option<&str>,
+        interaction_locale: Option<&str>,
+    ) -> Lang<'_> {
+        guild_default
+            .and_then(|locale| self.translation(locale))
+            .or_else(|| interaction_locale.and_then(|locale| self.translation(locale)))
+            .map_or(Lang::Fallback, Lang::Translation)
+    }
+
+    fn translation(&self, locale: &str) -> Option<&str> {
+        let language = locale
+            .split_once('-')
+            .map_or(locale, |(language, _)| language);
+        [locale, language].into_iter().find_map(|locale| {
+            self.translations
+                .get_key_value(locale)
+                .map(|(locale, _)| locale.as_str())
+        })
+    }
+}
+
+/// User-facing text, configurable through the `[messages]` table of the
+/// config file. Anything left out keeps its English default.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Messages {
+    /// Shown to reporters after their report was posted.
+    pub report_received: String,
+    /// Sent with the copy of their report to reporters, for setups with
+    /// `dm_copy`.
+    pub report_copy: String,
+    /// The title of the report form.
+    pub form_title: String,
+    /// Shown above the preview when a setup asks reporters to confirm.
+    pub confirm_report: String,
+    /// Shown when Discord sends a command we can't parse.
+    pub command_parse_failed: String,
+    /// Shown when something that only works in a server is used in DMs.
+    pub guild_only: String,
+    /// Ends a reason which was cut off to fit in the report embed.
+    pub truncated: String,
+    /// Like `truncated`, for setups which attach the full reason.
+    pub truncated_attached: String,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self {
+            report_received:
+                "Thanks for making a report. A moderator will handle it as soon as possible."
+                    .to_string(),
+            report_copy: "Here's a copy of your report. A moderator will follow up if they need \
+                          anything else."
+                .to_string(),
+            form_title: "ModMail Form".to_string(),
+            confirm_report: "Please check your report. It will only be sent once you confirm it."
+                .to_string(),
+            command_parse_failed: "Sorry, I couldn't understand that command.".to_string(),
+            guild_only: "This must be used in a server.".to_string(),
+            truncated: "…".to_string(),
+            truncated_attached: "… (truncated, see attachment)".to_string(),
+        }
+    }
+}
+
+impl Messages {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for key in MessageKey::ALL {
+            check_length(key.name(), key.current(self), key.max_length())?;
+        }
+        check_length("report_copy", &self.report_copy, 2000)?;
+        check_length("guild_only", &self.guild_only, 2000)?;
+        // These take space away from the reason itself.
+        check_length("truncated", &self.truncated, 100)?;
+        check_length("truncated_attached", &self.truncated_attached, 100)?;
+        Ok(())
+    }
+}
+
+/// The [`Messages`] servers may change with `/set-message`, the ones
+/// answering someone in a server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, CommandOption, CreateOption)]
+pub enum MessageKey {
+    #[option(name = "Report received", value = "report_received")]
+    ReportReceived,
+    #[option(name = "Form title", value = "form_title")]
+    FormTitle,
+    #[option(name = "Confirm report", value = "confirm_report")]
+    ConfirmReport,
+    #[option(name = "Command parse failed", value = "command_parse_failed")]
+    CommandParseFailed,
+}
+
+impl MessageKey {
+    const ALL: [Self; 4] = [
+        Self::ReportReceived,
+        Self::FormTitle,
+        Self::ConfirmReport,
+        Self::CommandParseFailed,
+    ];
+
+    /// The key in `[messages]`, which is also how the store keeps it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ReportReceived => "report_received",
+            Self::FormTitle => "form_title",
+            Self::ConfirmReport => "confirm_report",
+            Self::CommandParseFailed => "command_parse_failed",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|key| key.name() == name)
+    }
+
+    /// The longest the message may be, in characters.
+    pub const fn max_length(self) -> usize {
+        match self {
+            // Discord's limit for modal titles.
+            Self::FormTitle => 45,
+            Self::ReportReceived | Self::ConfirmReport | Self::CommandParseFailed => 2000,
+        }
+    }
+
+    /// Whether `value` is long enough and short enough to be this message.
+    pub fn fits(self, value: &str) -> bool {
+        fits(value, self.max_length())
+    }
+
+    fn current(self, messages: &Messages) -> &str {
+        match self {
+            Self::ReportReceived => &messages.report_received,
+            Self::FormTitle => &messages.form_title,
+            Self::ConfirmReport => &messages.confirm_report,
+            Self::CommandParseFailed => &messages.command_parse_failed,
+        }
+    }
+
+    const fn field(self, messages: &mut Messages) -> &mut String {
+        match self {
+            Self::ReportReceived => &mut messages.report_received,
+            Self::FormTitle => &mut messages.form_title,
+            Self::ConfirmReport => &mut messages.confirm_report,
+            Self::CommandParseFailed => &mut messages.command_parse_failed,
+        }
+    }
+}
+
+fn fits(value: &str, max: usize) -> bool {
+    (1..=max).contains(&value.chars().count())
+}
+
+fn check_length(key: &'static str, value: &str, max: usize) -> Result<(), ConfigError> {
+    if fits(value, max) {
+        Ok(())
+    } else {
+        Err(ConfigError::MessageLength { key, max })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interact::handle_interaction, testing, AppState};
+
+    const CONFIG: &str = r#"
+[messages]
+guild_only = "Servers only"
+
+[translations.de]
+guild_only = "Nur auf Servern"
+
+[translations.es]
+guild_only = "Solo en servidores"
+
+[translations.pt-BR]
+guild_only = "Apenas em servidores"
+
+[guild_languages]
+10 = "de"
+"#;
+
+    fn languages() -> Languages {
+        Languages::new(&toml::from_str(CONFIG).unwrap())
+    }
+
+    #[test]
+    fn server_languages_come_first() {
+        let languages = languages();
+        assert_eq!(
+            languages.resolve_language(Some("de"), Some("es-ES")),
+            Lang::Translation("de")
+        );
+        let guild = Some(Id::new(testing::GUILD));
+        assert_eq!(
+            languages.get(guild, Some("es-ES")).guild_only,
+            "Nur auf Servern"
+        );
+        assert_eq!(languages.get(guild, None).guild_only, "Nur auf Servern");
+    }
+
+    #[test]
+    fn users_get_their_own_locale_otherwise() {
+        let languages = languages();
+        assert_eq!(
+            languages.resolve_language(None, Some("pt-BR")),
+            Lang::Translation("pt-BR")
+        );
+        // Regions fall back to their language.
+        assert_eq!(
+            languages.resolve_language(None, Some("es-ES")),
+            Lang::Translation("es")
+        );
+        let other_guild = Some(Id::new(11));
+        assert_eq!(
+            languages.get(other_guild, Some("es-419")).guild_only,
+            "Solo en servidores"
+        );
+    }
+
+    #[test]
+    fn everything_else_gets_the_fallback() {
+        let languages = languages();
+        assert_eq!(languages.resolve_language(None, None), Lang::Fallback);
+        assert_eq!(languages.resolve_language(None, Some("fr")), Lang::Fallback);
+        // Only the language covers its regions, not the other way around.
+        assert_eq!(
+            languages.resolve_language(None, Some("pt-PT")),
+            Lang::Fallback
+        );
+        assert_eq!(languages.get(None, Some("fr")).guild_only, "Servers only");
+        assert_eq!(languages.reports().guild_only, "Servers only");
+    }
+
+    #[test]
+    fn answers_in_the_users_locale() {
+        let state = AppState {
+            messages: Arc::new(languages()),
+            ..testing::offline_state()
+        };
+        let mut interaction = testing::button("open_form:60");
+        interaction.guild_id = None;
+        interaction.locale = Some("de".to_string());
+        let response = testing::runtime().block_on(handle_interaction(state, interaction));
+        assert_eq!(testing::content(&response), Some("Nur auf Servern"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use twilight_model::application::interaction::InteractionType;
+
+use crate::AppState;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Counters served in the Prometheus text format on `/metrics`, or on
+/// `AGHAST_METRICS_BIND` if set.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    interactions: Mutex<BTreeMap<String, u64>>,
+    signature_failures: AtomicU64,
+    reports_submitted: AtomicU64,
+    queued_report_failures: AtomicU64,
+    create_message: Mutex<Histogram>,
+    create_forum_thread: Mutex<Histogram>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Not cumulative, unlike the exported buckets.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Metrics {
+    pub fn interaction(&self, kind: InteractionType) {
+        *self
+            .interactions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .entry(format!("{kind:?}"))
+            .or_default() += 1;
+    }
+
+    pub fn signature_failure(&self) {
+        self.signature_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report_submitted(&self) {
+        self.reports_submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn queued_report_failed(&self) {
+        self.queued_report_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record how long posting a report with `create_message` took.
+    pub fn create_message(&self, took: Duration) {
+        record(&self.create_message, took);
+    }
+
+    /// Record how long posting a report in a forum with
+    /// `create_forum_thread` took.
+    pub fn create_forum_thread(&self, took: Duration) {
+        record(&self.create_forum_thread, took);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP aghast_interactions_total Interactions received, by type.\n");
+        out.push_str("# TYPE aghast_interactions_total counter\n");
+        for (kind, count) in self
+            .interactions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .iter()
+        {
+            let _ = writeln!(out, "aghast_interactions_total{{kind=\"{kind}\"}} {count}");
+        }
+
+        out.push_str(
+            "# HELP aghast_signature_failures_total Requests rejected for their signature.\n",
+        );
+        out.push_str("# TYPE aghast_signature_failures_total counter\n");
+        let _ = writeln!(
+            out,
+            "aghast_signature_failures_total {}",
+            self.signature_failures.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP aghast_reports_submitted_total Reports posted or queued.\n");
+        out.push_str("# TYPE aghast_reports_submitted_total counter\n");
+        let _ = writeln!(
+            out,
+            "aghast_reports_submitted_total {}",
+            self.reports_submitted.load(Ordering::Relaxed)
+        );
+
+        out.push_str(
+            "# HELP aghast_queued_report_failures_total Queued reports which couldn't be \
+             posted, even after retrying.\n",
+        );
+        out.push_str("# TYPE aghast_queued_report_failures_total counter\n");
+        let _ = writeln!(
+            out,
+            "aghast_queued_report_failures_total {}",
+            self.queued_report_failures.load(Ordering::Relaxed)
+        );
+
+        render_histogram(
+            &mut out,
+            "aghast_create_message_seconds",
+            "How long Discord took to post a report.",
+            &self.create_message,
+        );
+        render_histogram(
+            &mut out,
+            "aghast_create_forum_thread_seconds",
+            "How long Discord took to post a report in a forum.",
+            &self.create_forum_thread,
+        );
+        out
+    }
+}
+
+fn record(histogram: &Mutex<Histogram>, took: Duration) {
+    let secs = took.as_secs_f64();
+    let mut histogram = histogram
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| secs <= *bound) {
+        histogram.buckets[bucket] += 1;
+    }
+    histogram.count += 1;
+    histogram.sum += secs;
+    drop(histogram);
+}
+
+fn render_histogram(out: &mut String, name: &str, help: &str, histogram: &Mutex<Histogram>) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    let histogram = histogram
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+        cumulative += count;
+        let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+    }
+    let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", histogram.count);
+    let _ = writeln!(out, "{name}_sum {}", histogram.sum);
+    let _ = writeln!(out, "{name}_count {}", histogram.count);
+    drop(histogram);
+}
+
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Bytes,
+        http::{Method, StatusCode},
+    };
+    use serde_json::{json, Value};
+    use twilight_model::id::Id;
+
+    use super::*;
+    use crate::testing::{self, MockDiscord};
+
+    fn submit() -> Bytes {
+        let submit = testing::modal(
+            "form_submit:60",
+            &[
+                ("user", "wumpus"),
+                ("channel", "#general"),
+                ("message_link", ""),
+                ("reason", "Being rude"),
+            ],
+        );
+        serde_json::to_vec(&submit).unwrap().into()
+    }
+
+    /// The value of the metric line starting with `name`.
+    fn value<'a>(rendered: &'a str, name: &str) -> &'a str {
+        rendered
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("{name} is missing from:\n{rendered}"))
+    }
+
+    async fn scrape(state: AppState) -> String {
+        let response = metrics(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn renders_counters_after_an_interaction() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => (StatusCode::OK, testing::message(60, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = discord.state();
+            let handled =
+                Box::pin(crate::interaction_handler(State(state.clone()), submit())).await;
+            assert!(handled.is_ok());
+
+            let rendered = scrape(state).await;
+            assert_eq!(
+                value(&rendered, "aghast_interactions_total{kind=\"ModalSubmit\"}"),
+                "1"
+            );
+            assert_eq!(value(&rendered, "aghast_reports_submitted_total"), "1");
+            assert_eq!(value(&rendered, "aghast_signature_failures_total"), "0");
+            assert_eq!(value(&rendered, "aghast_create_message_seconds_count"), "1");
+            assert_eq!(
+                value(
+                    &rendered,
+                    "aghast_create_message_seconds_bucket{le=\"+Inf\"}"
+                ),
+                "1"
+            );
+            assert_eq!(
+                value(&rendered, "aghast_create_forum_thread_seconds_count"),
+                "0"
+            );
+        });
+    }
+
+    #[test]
+    fn times_posts_in_forums() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/60/messages" => testing::api_error(StatusCode::BAD_REQUEST, 50008),
+                "/channels/60" => (
+                    StatusCode::OK,
+                    testing::channel(60, Some(Id::new(testing::GUILD)), 15),
+                ),
+                "/channels/60/threads" => {
+                    let mut thread = testing::channel(61, Some(Id::new(testing::GUILD)), 11);
+                    if let Value::Object(thread) = &mut thread {
+                        thread.insert("message".to_string(), testing::message(61, 61));
+                    }
+                    (StatusCode::CREATED, thread)
+                }
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = discord.state();
+            let handled =
+                Box::pin(crate::interaction_handler(State(state.clone()), submit())).await;
+            assert!(handled.is_ok());
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/threads")
+                    .len(),
+                1
+            );
+
+            let rendered = scrape(state).await;
+            assert_eq!(value(&rendered, "aghast_create_message_seconds_count"), "1");
+            assert_eq!(
+                value(&rendered, "aghast_create_forum_thread_seconds_count"),
+                "1"
+            );
+        });
+    }
+}
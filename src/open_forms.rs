@@ -0,0 +1,50 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use twilight_model::id::{marker::UserMarker, Id};
+
+/// Discord doesn't tell us when a form is closed without submitting, so
+/// opened forms stop counting after this long.
+const OPEN_FORM_TTL: Duration = Duration::from_mins(2);
+
+/// Report forms each user has opened but not submitted yet, for setups with
+/// `form.max_open_forms`.
+#[derive(Debug, Default)]
+pub struct OpenForms(Mutex<HashMap<Id<UserMarker>, Vec<Instant>>>);
+
+impl OpenForms {
+    /// Count a form as opened, unless the user already has `max` open.
+    pub fn try_open(&self, user: Id<UserMarker>, max: usize) -> bool {
+        let mut open = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        open.retain(|_, opened| {
+            opened.retain(|at| at.elapsed() < OPEN_FORM_TTL);
+            !opened.is_empty()
+        });
+        let opened = open.entry(user).or_default();
+        let allowed = opened.len() < max;
+        if allowed {
+            opened.push(Instant::now());
+        }
+        drop(open);
+        allowed
+    }
+
+    /// Stop counting the user's oldest open form, once one was submitted.
+    pub fn close(&self, user: Id<UserMarker>) {
+        let mut open = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(opened) = open.get_mut(&user) {
+            if !opened.is_empty() {
+                opened.remove(0);
+            }
+        }
+    }
+}
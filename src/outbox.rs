@@ -0,0 +1,340 @@
+use std::time::Duration;
+
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    interact::{post_report, InteractError, ModmailFormModal},
+    queue::is_transient,
+    store::{self, OutboxEntry, Store},
+    AppState,
+};
+
+/// How often the worker looks for reports in the outbox.
+const INTERVAL: Duration = Duration::from_secs(30);
+/// How long the worker leaves a report alone after it was added, while it's
+/// posted right away. It only takes over if that fails, or if the bot stopped
+/// before it was done.
+const GRACE_SECS: u64 = 60;
+/// How often a report is tried before it's given up on.
+const ATTEMPTS: i64 = 5;
+/// How long to wait before the first retry. Doubled for every retry after.
+const RETRY_DELAY_SECS: u64 = 30;
+
+/// Post a report, keeping it in the store's outbox until it's out. If posting
+/// fails in a way Discord might get over, the reporter is told it was sent,
+/// and [`run`] tries again later, even after a restart. Without a store,
+/// reports are only posted.
+pub async fn post_through_outbox(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    report: ModmailFormModal,
+) -> Result<(), InteractError> {
+    let Some(store) = &state.store else {
+        post_report(state, Some(guild_id), reporter, target_channel, report).await?;
+        return Ok(());
+    };
+    let added = store
+        .add_to_outbox(
+            guild_id,
+            reporter,
+            target_channel,
+            &report,
+            store::now() + GRACE_SECS,
+        )
+        .await;
+    let id = match added {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!(
+                "Failed to add a report from {reporter} to the outbox, posting it anyway: {e:?}"
+            );
+            post_report(state, Some(guild_id), reporter, target_channel, report).await?;
+            return Ok(());
+        }
+    };
+    match post_report(state, Some(guild_id), reporter, target_channel, report).await {
+        Ok(_) => {
+            remove(store, id).await;
+            Ok(())
+        }
+        Err(e) if is_transient(&e) => {
+            tracing::warn!(
+                "Failed to post report from {reporter} to {target_channel}, retrying from the \
+                 outbox in {RETRY_DELAY_SECS}s: {e:?}"
+            );
+            if let Err(e) = store
+                .retry_from_outbox(id, store::now() + RETRY_DELAY_SECS)
+                .await
+            {
+                tracing::error!("Failed to reschedule report {id} in the outbox: {e:?}");
+            }
+            Ok(())
+        }
+        Err(e) => {
+            remove(store, id).await;
+            Err(e)
+        }
+    }
+}
+
+/// Post reports left in the outbox every [`INTERVAL`], forever.
+pub async fn run(state: AppState, store: Store) {
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        interval.tick().await;
+        drain(&state, &store, store::now()).await;
+    }
+}
+
+/// Try every report which is due at `now` once.
+async fn drain(state: &AppState, store: &Store, now: u64) {
+    let due = match store.due_in_outbox(now).await {
+        Ok(due) => due,
+        Err(e) => {
+            tracing::error!("Failed to read the outbox: {e:?}");
+            return;
+        }
+    };
+    for entry in due {
+        retry(state, store, &entry, now).await;
+    }
+}
+
+async fn retry(state: &AppState, store: &Store, entry: &OutboxEntry, now: u64) {
+    let report: ModmailFormModal = match serde_json::from_str(&entry.report) {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::error!(
+                "Dropping unreadable report {} from the outbox: {e}",
+                entry.id
+            );
+            remove(store, entry.id).await;
+            return;
+        }
+    };
+    let (reporter, target_channel) = (entry.reporter(), entry.target_channel());
+    let result = post_report(state, Some(entry.guild()), reporter, target_channel, report).await;
+    match result {
+        Ok(_) => remove(store, entry.id).await,
+        Err(e) if entry.attempts + 1 < ATTEMPTS && is_transient(&e) => {
+            let delay = RETRY_DELAY_SECS << entry.attempts;
+            tracing::warn!(
+                "Failed to post report from {reporter} to {target_channel} from the outbox, \
+                 retrying in {delay}s: {e:?}"
+            );
+            if let Err(e) = store.retry_from_outbox(entry.id, now + delay).await {
+                tracing::error!(
+                    "Failed to reschedule report {} in the outbox: {e:?}",
+                    entry.id
+                );
+            }
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to post report from {reporter} to {target_channel} from the outbox: \
+                 {e:?}"
+            );
+            state.metrics.queued_report_failed();
+            remove(store, entry.id).await;
+        }
+    }
+}
+
+/// A report which is still in the outbox after this is posted again later,
+/// so failing here only gets logged.
+async fn remove(store: &Store, id: i64) {
+    if let Err(e) = store.remove_from_outbox(id).await {
+        tracing::error!("Failed to remove report {id} from the outbox: {e:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{self, MockDiscord};
+
+    const MODMAIL: u64 = 60;
+    /// Later than anything in the outbox is due.
+    const LATER: u64 = 1 << 40;
+
+    fn report() -> ModmailFormModal {
+        ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: None,
+            channel: None,
+            reason: "Being rude".to_string(),
+            custom: BTreeMap::from([("extra".to_string(), "details".to_string())]),
+        }
+    }
+
+    /// Discord, answering the first `failures` posts to the modmail channel
+    /// with `error`.
+    async fn discord(failures: usize, error: (StatusCode, Value)) -> MockDiscord {
+        let posts = AtomicUsize::new(0);
+        MockDiscord::start(move |request| match request.path.as_str() {
+            "/channels/60/messages" if posts.fetch_add(1, Ordering::Relaxed) < failures => {
+                error.clone()
+            }
+            "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await
+    }
+
+    async fn state(discord: &MockDiscord) -> (AppState, Store) {
+        let store = testing::store().await;
+        let state = AppState {
+            store: Some(store.clone()),
+            ..discord.state()
+        };
+        (state, store)
+    }
+
+    async fn post(state: &AppState) -> Result<(), InteractError> {
+        post_through_outbox(
+            state,
+            Id::new(testing::GUILD),
+            Id::new(30),
+            Id::new(MODMAIL),
+            report(),
+        )
+        .await
+    }
+
+    fn posts(discord: &MockDiscord) -> usize {
+        discord
+            .requests_to(&Method::POST, "/channels/60/messages")
+            .len()
+    }
+
+    fn server_error() -> (StatusCode, Value) {
+        testing::api_error(StatusCode::INTERNAL_SERVER_ERROR, 0)
+    }
+
+    #[test]
+    fn removes_posted_reports() {
+        testing::runtime().block_on(async {
+            let discord = discord(0, server_error()).await;
+            let (state, store) = state(&discord).await;
+            post(&state).await.unwrap();
+            assert_eq!(posts(&discord), 1);
+            assert!(store.due_in_outbox(LATER).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn retries_reports_discord_failed_to_take() {
+        testing::runtime().block_on(async {
+            let discord = discord(1, server_error()).await;
+            let (state, store) = state(&discord).await;
+            // The reporter isn't told about it, the outbox takes over.
+            post(&state).await.unwrap();
+            let waiting = store.due_in_outbox(LATER).await.unwrap();
+            assert_eq!(waiting.len(), 1);
+            assert_eq!(waiting[0].reporter(), Id::new(30));
+
+            // Not before the retry delay.
+            drain(&state, &store, store::now()).await;
+            assert_eq!(posts(&discord), 1);
+
+            drain(&state, &store, store::now() + RETRY_DELAY_SECS).await;
+            assert_eq!(posts(&discord), 2);
+            let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+            let fields = |post: usize| &posted[post].body["embeds"][0]["fields"];
+            assert_eq!(fields(0), fields(1));
+            assert!(store.due_in_outbox(LATER).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn leaves_reports_alone_while_theyre_posted_right_away() {
+        testing::runtime().block_on(async {
+            let discord = discord(0, server_error()).await;
+            let (state, store) = state(&discord).await;
+            let added = store::now();
+            store
+                .add_to_outbox(
+                    Id::new(testing::GUILD),
+                    Id::new(30),
+                    Id::new(MODMAIL),
+                    &report(),
+                    added + GRACE_SECS,
+                )
+                .await
+                .unwrap();
+            drain(&state, &store, added + GRACE_SECS - 1).await;
+            assert_eq!(posts(&discord), 0);
+            drain(&state, &store, added + GRACE_SECS).await;
+            assert_eq!(posts(&discord), 1);
+        });
+    }
+
+    #[test]
+    fn posts_reports_left_behind_by_a_restart() {
+        testing::runtime().block_on(async {
+            let discord = discord(0, server_error()).await;
+            let (state, store) = state(&discord).await;
+            store
+                .add_to_outbox(
+                    Id::new(testing::GUILD),
+                    Id::new(30),
+                    Id::new(MODMAIL),
+                    &report(),
+                    store::now(),
+                )
+                .await
+                .unwrap();
+            drain(&state, &store, store::now()).await;
+            let posted = discord.requests_to(&Method::POST, "/channels/60/messages");
+            assert_eq!(posted.len(), 1);
+            let fields = posted[0].body["embeds"][0]["fields"].to_string();
+            assert!(fields.contains("Being rude"), "{fields}");
+            assert!(store.due_in_outbox(LATER).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn gives_up_on_reports_which_keep_failing() {
+        testing::runtime().block_on(async {
+            let discord = discord(usize::MAX, server_error()).await;
+            let (state, store) = state(&discord).await;
+            post(&state).await.unwrap();
+            for attempt in 1..ATTEMPTS {
+                // Past the retry delay, which grows every time.
+                let now = LATER * attempt.cast_unsigned();
+                assert_eq!(store.due_in_outbox(now).await.unwrap().len(), 1);
+                drain(&state, &store, now).await;
+            }
+            assert_eq!(posts(&discord), usize::try_from(ATTEMPTS).unwrap());
+            assert!(store.due_in_outbox(LATER * 10).await.unwrap().is_empty());
+            assert!(state
+                .metrics
+                .render()
+                .contains("aghast_queued_report_failures_total 1\n"));
+        });
+    }
+
+    #[test]
+    fn doesnt_keep_what_cant_work() {
+        testing::runtime().block_on(async {
+            let discord =
+                discord(usize::MAX, testing::api_error(StatusCode::FORBIDDEN, 50013)).await;
+            let (state, store) = state(&discord).await;
+            assert!(post(&state).await.is_err());
+            assert!(store.due_in_outbox(LATER).await.unwrap().is_empty());
+        });
+    }
+}
@@ -0,0 +1,139 @@
+use twilight_model::channel::message::component::{ActionRow, Button, ButtonStyle, Component};
+
+use crate::extract::{custom_id, CustomIdTooLong};
+
+/// One page of a list which doesn't fit in a single message.
+///
+/// The page number travels in the custom IDs of the Previous and Next
+/// buttons, so list commands don't have to remember anything between clicks.
+pub struct Page<'a, T> {
+    pub items: &'a [T],
+    /// Counting from 0.
+    pub index: usize,
+    pub count: usize,
+}
+
+impl<'a, T> Page<'a, T> {
+    /// Page `index` of `items`. Pages past the end, like after the list got
+    /// shorter, show the last page instead. An empty list has one empty page.
+    pub fn new(items: &'a [T], per_page: usize, index: usize) -> Self {
+        let per_page = per_page.max(1);
+        let count = items.len().div_ceil(per_page).max(1);
+        let index = index.min(count - 1);
+        let start = index * per_page;
+        let end = items.len().min(start + per_page);
+        Self {
+            items: &items[start.min(end)..end],
+            index,
+            count,
+        }
+    }
+
+    /// Like "Page 2 of 5", for the embed footer.
+    pub fn footer(&self) -> String {
+        format!("Page {} of {}", self.index + 1, self.count)
+    }
+
+    /// Previous and Next buttons with custom IDs like `name:3`, so the
+    /// handler for `name` takes `CidArgs<(usize,)>`. Buttons leading past
+    /// either end are disabled.
+    pub fn buttons(&self, name: &str) -> Result<Component, CustomIdTooLong> {
+        // Custom IDs have to be unique within a message, even on disabled
+        // buttons, so Next isn't clamped to the last page. Out of range pages
+        // are clamped by `new` instead.
+        let previous = Self::button(
+            name,
+            "Previous",
+            self.index.saturating_sub(1),
+            self.index == 0,
+        )?;
+        let next = Self::button(name, "Next", self.index + 1, self.index + 1 >= self.count)?;
+        Ok(Component::ActionRow(ActionRow {
+            components: vec![previous, next],
+        }))
+    }
+
+    fn button(
+        name: &str,
+        label: &str,
+        target: usize,
+        disabled: bool,
+    ) -> Result<Component, CustomIdTooLong> {
+        Ok(Component::Button(Button {
+            custom_id: Some(custom_id(name, &[&target])?),
+            disabled,
+            emoji: None,
+            label: Some(label.to_string()),
+            style: ButtonStyle::Secondary,
+            url: None,
+            sku_id: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The custom IDs of the Previous and Next buttons, and whether they're
+    /// disabled.
+    fn buttons<T>(page: &Page<'_, T>) -> Vec<(String, bool)> {
+        let Component::ActionRow(row) = page.buttons("list").unwrap() else {
+            panic!("not an action row");
+        };
+        row.components
+            .into_iter()
+            .map(|button| match button {
+                Component::Button(button) => (button.custom_id.unwrap(), button.disabled),
+                _ => panic!("not a button"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_items_into_pages() {
+        let items: Vec<_> = (0..25).collect();
+        let first = Page::new(&items, 10, 0);
+        assert_eq!(first.items, &items[..10]);
+        assert_eq!(first.footer(), "Page 1 of 3");
+        let last = Page::new(&items, 10, 2);
+        assert_eq!(last.items, &items[20..]);
+        assert_eq!(last.footer(), "Page 3 of 3");
+    }
+
+    #[test]
+    fn shows_the_last_page_past_the_end() {
+        let items: Vec<_> = (0..25).collect();
+        let page = Page::new(&items, 10, 7);
+        assert_eq!(page.index, 2);
+        assert_eq!(page.items, &items[20..]);
+    }
+
+    #[test]
+    fn empty_lists_have_one_empty_page() {
+        let page = Page::<u8>::new(&[], 10, 3);
+        assert!(page.items.is_empty());
+        assert_eq!(page.footer(), "Page 1 of 1");
+        assert_eq!(
+            buttons(&page),
+            [("list:0".to_string(), true), ("list:1".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn disables_buttons_past_either_end() {
+        let items: Vec<_> = (0..25).collect();
+        assert_eq!(
+            buttons(&Page::new(&items, 10, 0)),
+            [("list:0".to_string(), true), ("list:1".to_string(), false)]
+        );
+        assert_eq!(
+            buttons(&Page::new(&items, 10, 1)),
+            [("list:0".to_string(), false), ("list:2".to_string(), false)]
+        );
+        assert_eq!(
+            buttons(&Page::new(&items, 10, 2)),
+            [("list:1".to_string(), false), ("list:3".to_string(), true)]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use twilight_http::Client;
+use twilight_model::{
+    channel::message::AllowedMentions,
+    id::{
+        marker::{ChannelMarker, MessageMarker, RoleMarker},
+        Id,
+    },
+};
+
+/// Reports posted since the last ping, per modmail channel, for setups with
+/// `form.ping_batch_secs` configured.
+#[derive(Debug, Default)]
+pub struct PingBatches(Mutex<HashMap<Id<ChannelMarker>, Vec<PostedReport>>>);
+
+/// Where a report ended up, which for forums is a thread of its own.
+type PostedReport = (Id<ChannelMarker>, Id<MessageMarker>);
+
+impl PingBatches {
+    /// Remember a posted report. The first report of a batch schedules the
+    /// ping, which then covers everything added until it is sent.
+    pub fn add(
+        self: &Arc<Self>,
+        client: &Arc<Client>,
+        channel: Id<ChannelMarker>,
+        role: Id<RoleMarker>,
+        window: Duration,
+        posted: PostedReport,
+    ) {
+        let mut batches = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match batches.entry(channel) {
+            Entry::Occupied(mut entry) => entry.get_mut().push(posted),
+            Entry::Vacant(entry) => {
+                entry.insert(vec![posted]);
+                let batches = self.clone();
+                let client = client.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(window).await;
+                    let reports = batches.take(channel);
+                    if let Err(e) = ping(&client, channel, role, &reports).await {
+                        tracing::warn!("Failed to send batched ping to {channel}: {e:?}");
+                    }
+                });
+            }
+        }
+        drop(batches);
+    }
+
+    fn take(&self, channel: Id<ChannelMarker>) -> Vec<PostedReport> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(&channel)
+            .unwrap_or_default()
+    }
+}
+
+/// As many links as surely fit in a message.
+const MAX_LINKS: usize = 20;
+
+/// Send one message mentioning `role`, linking every report of the batch.
+/// Forum channels can't take messages, so [`crate::config::apply`] only
+/// allows batches for text channels.
+async fn ping(
+    client: &Client,
+    channel: Id<ChannelMarker>,
+    role: Id<RoleMarker>,
+    reports: &[PostedReport],
+) -> Result<(), twilight_http::Error> {
+    // Message links need the guild, which message responses don't include.
+    let guild = match client.channel(channel).await {
+        Ok(response) => response.model().await.ok().and_then(|c| c.guild_id),
+        Err(_) => None,
+    };
+    let mut links: Vec<_> = reports
+        .iter()
+        .take(MAX_LINKS)
+        .map(|(channel, message)| {
+            guild.map_or_else(
+                || format!("<#{channel}>"),
+                |guild| format!("https://discord.com/channels/{guild}/{channel}/{message}"),
+            )
+        })
+        .collect();
+    if reports.len() > MAX_LINKS {
+        links.push(format!("…and {} more", reports.len() - MAX_LINKS));
+    }
+    let noun = if reports.len() == 1 {
+        "report"
+    } else {
+        "reports"
+    };
+    let content = format!(
+        "<@&{role}> {} new {noun}:\n{}",
+        reports.len(),
+        links.join("\n")
+    );
+    let allowed_mentions = AllowedMentions {
+        roles: vec![role],
+        ..AllowedMentions::default()
+    };
+    client
+        .create_message(channel)
+        .content(&content)
+        .allowed_mentions(Some(&allowed_mentions))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use sha2::Sha256;
+use twilight_model::{
+    application::interaction::Interaction, http::interaction::InteractionResponse,
+};
+
+/// An external service which gets to see every interaction before aghast
+/// does, configured with `AGHAST_PLUGIN_URL`.
+///
+/// The plugin answers with `200 OK` and an interaction response to handle the
+/// interaction itself, or anything else to leave it to aghast.
+///
+/// With `AGHAST_PLUGIN_SECRET` set, requests are signed like Discord signs
+/// its own: `X-Aghast-Timestamp` holds the current Unix time in seconds, and
+/// `X-Aghast-Signature` the hex-encoded HMAC-SHA256 of the timestamp followed
+/// by the raw body, keyed with the secret. Plugins should compute the same
+/// over the body exactly as received, compare in constant time, and reject
+/// old timestamps.
+#[derive(Debug)]
+pub struct Plugin {
+    http: reqwest::Client,
+    url: String,
+    secret: Option<Vec<u8>>,
+}
+
+impl Plugin {
+    /// Discord gives up on an interaction after three seconds, so the timeout
+    /// has to leave time for aghast's own handling.
+    pub fn new(url: String, timeout: Duration, secret: Option<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build plugin HTTP client");
+        Self {
+            http,
+            url,
+            secret: secret.map(String::into_bytes),
+        }
+    }
+
+    /// Ask the plugin for a response. Any failure falls through to the
+    /// built-in handling, so a broken plugin can't take the bot down with it.
+    pub async fn handle(&self, interaction: &Interaction) -> Option<InteractionResponse> {
+        let body = match serde_json::to_vec(interaction) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("Could not serialize interaction for plugin: {e:?}");
+                return None;
+            }
+        };
+        let mut request = self
+            .http
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.secret {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .to_string();
+            let signature = sign(secret, &timestamp, &body);
+            request = request
+                .header("X-Aghast-Timestamp", timestamp)
+                .header("X-Aghast-Signature", signature);
+        }
+        let response = match request.body(body).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("Plugin request failed: {e:?}");
+                return None;
+            }
+        };
+        if response.status() != StatusCode::OK {
+            return None;
+        }
+        match response.json().await {
+            Ok(response) => Some(response),
+            Err(e) => {
+                tracing::warn!("Plugin sent an invalid interaction response: {e:?}");
+                None
+            }
+        }
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of `timestamp` followed by `body`, for
+/// `X-Aghast-Signature`.
+fn sign(secret: &[u8], timestamp: &str, body: &[u8]) -> String {
+    // HMAC takes keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key");
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{body::Bytes, extract::State, http::HeaderMap, routing::post, Router};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn matches_rfc_4231() {
+        // Test cases 1, 2 and 6, the last with a key longer than a block.
+        let cases: [(&[u8], &str, &str, &str); 3] = [
+            (
+                &[0x0b; 20],
+                "Hi ",
+                "There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe",
+                "what do ya want ",
+                "for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                &[0xaa; 131],
+                "Test Using Larger Than ",
+                "Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+        for (key, timestamp, body, expected) in cases {
+            assert_eq!(sign(key, timestamp, body.as_bytes()), expected);
+        }
+    }
+
+    type Received = Arc<Mutex<Option<(HeaderMap, Bytes)>>>;
+
+    async fn receive(State(received): State<Received>, headers: HeaderMap, body: Bytes) {
+        *received.lock().unwrap() = Some((headers, body));
+    }
+
+    #[test]
+    fn receivers_can_verify_the_signature() {
+        crate::install_crypto_provider();
+        let (headers, body) = testing::runtime().block_on(async {
+            let received = Received::default();
+            let router = Router::new()
+                .route("/", post(receive))
+                .with_state(received.clone());
+            let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/", tcp.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(tcp, router).await });
+
+            let plugin = Plugin::new(url, Duration::from_secs(5), Some("secret".to_string()));
+            let interaction = testing::button("open_form:60");
+            // Answering without a response leaves the interaction to aghast.
+            assert!(plugin.handle(&interaction).await.is_none());
+            let received = received.lock().unwrap().take();
+            received.unwrap()
+        });
+
+        // What a receiver would do, per the scheme documented on `Plugin`.
+        let timestamp = headers["x-aghast-timestamp"].to_str().unwrap();
+        let signature = hex::decode(headers["x-aghast-signature"].as_bytes()).unwrap();
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(timestamp.as_bytes());
+        mac.update(&body);
+        assert!(mac.clone().verify_slice(&signature).is_ok());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(now.abs_diff(timestamp.parse().unwrap()) < 5);
+
+        let mut tampered = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        tampered.update(timestamp.as_bytes());
+        tampered.update(b"{}");
+        assert!(tampered.verify_slice(&signature).is_err());
+    }
+}
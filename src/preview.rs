@@ -0,0 +1,71 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::ChannelMarker, Id},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    config::ReportField,
+    extract::SlashCommand,
+    interact::{build_report_embed, ModmailFormModal},
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "preview-report",
+    desc = "Show what a report will look like, using sample data",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct PreviewReportCommand {
+    /// The modmail channel whose form settings to use
+    modmail_channel: Id<ChannelMarker>,
+}
+
+impl PreviewReportCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+pub async fn preview_report(
+    State(state): State<AppState>,
+    SlashCommand(cmd): SlashCommand<PreviewReportCommand>,
+) -> InteractionResponse {
+    let form = state.forms.get(cmd.modmail_channel);
+    let sample = |field, value: &str| form.is_enabled(field).then(|| value.to_string());
+    let report = ModmailFormModal {
+        user: sample(ReportField::User, "wumpus"),
+        message_link: sample(
+            ReportField::MessageLink,
+            "https://discord.com/channels/302094807046684672/768594508287311882/768594834231132222",
+        ),
+        channel: sample(ReportField::Channel, "#general"),
+        reason: "This is a sample report, showing how reports will look with the current settings."
+            .to_string(),
+        custom: form
+            .custom_fields
+            .iter()
+            .map(|field| (field.id.clone(), format!("Sample {}", field.label)))
+            .collect(),
+    };
+    let embed = build_report_embed(form, state.messages.reports(), None, report);
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(format!(
+            "Preview of a report sent to <#{}>",
+            cmd.modmail_channel
+        ))
+        .embeds([embed])
+        .build();
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    fmt::{Debug, Display},
+    future::Future,
+    sync::Arc,
+};
+
+use twilight_http::Client;
+use twilight_model::{
+    channel::message::{Embed, MessageFlags},
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{
+        marker::{ApplicationMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::{embed::EmbedBuilder, InteractionResponseDataBuilder};
+
+use crate::{
+    interact::{api_error_code, InteractError},
+    AppState,
+};
+
+/// The initial response for handlers that finish their work in the background.
+pub fn deferred_ephemeral() -> InteractionResponse {
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .build();
+    InteractionResponse {
+        kind: InteractionResponseType::DeferredChannelMessageWithSource,
+        data: Some(data),
+    }
+}
+
+/// The initial response for component handlers that finish their work in the
+/// background and then edit the message the component is on.
+pub const fn deferred_update() -> InteractionResponse {
+    InteractionResponse {
+        kind: InteractionResponseType::DeferredUpdateMessage,
+        data: None,
+    }
+}
+
+/// With `AGHAST_EARLY_DEFER` set, acknowledge the interaction with `deferred`
+/// straight away and edit in the real response once `response` finishes.
+/// Otherwise, just wait for `response`.
+///
+/// This keeps slow Discord API calls during bursts from pushing handlers past
+/// the three second deadline. The deferred response decides whether the
+/// final one is ephemeral, so it has to match what the handler would send.
+/// If the interaction expires before `response` finishes, it goes to `user`
+/// by DM instead.
+pub async fn respond_early<F>(
+    state: &AppState,
+    token: String,
+    user: Option<Id<UserMarker>>,
+    deferred: InteractionResponse,
+    response: F,
+) -> InteractionResponse
+where
+    F: Future<Output = InteractionResponse> + Send + 'static,
+{
+    if !state.early_defer {
+        return response.await;
+    }
+    let client = state.client.clone();
+    let application_id = state.application_id;
+    tokio::spawn(async move {
+        let data = response.await.data.unwrap_or_default();
+        let result = client
+            .interaction(application_id)
+            .update_response(&token)
+            .content(data.content.as_deref())
+            .embeds(data.embeds.as_deref())
+            .components(data.components.as_deref())
+            .await;
+        match result {
+            Ok(_) => {}
+            Err(e) if is_token_expired(&e) => {
+                tracing::warn!("Interaction token expired before the deferred response was ready");
+                if let Some(user) = user {
+                    send_dm(&client, user, &data).await;
+                }
+            }
+            Err(e) => tracing::error!("Failed to send deferred response: {e:?}"),
+        }
+    });
+    deferred
+}
+
+/// Discord's JSON error codes for webhooks which don't exist (anymore), which
+/// is what an interaction's token becomes after 15 minutes.
+const UNKNOWN_WEBHOOK: u64 = 10015;
+const INVALID_WEBHOOK_TOKEN: u64 = 50027;
+
+const fn is_token_expired(e: &twilight_http::Error) -> bool {
+    matches!(
+        api_error_code(e),
+        Some(UNKNOWN_WEBHOOK | INVALID_WEBHOOK_TOKEN)
+    )
+}
+
+/// Deliver a response which can't be sent to the interaction anymore. Its
+/// components are left out, because they only work in the server.
+async fn send_dm(client: &Client, user: Id<UserMarker>, data: &InteractionResponseData) {
+    let result = async {
+        let channel = client.create_private_channel(user).await?.model().await?;
+        client
+            .create_message(channel.id)
+            .content(data.content.as_deref().unwrap_or_default())
+            .embeds(data.embeds.as_deref().unwrap_or_default())
+            .await?;
+        Ok::<_, InteractError>(())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to send expired response to {user} by DM: {e:?}");
+    }
+}
+
+/// Edits a deferred ephemeral response to show what a multi-step handler is
+/// currently doing. Only worth it for handlers that make several API calls.
+pub struct Progress {
+    client: Arc<Client>,
+    application_id: Id<ApplicationMarker>,
+    token: String,
+    clear_components: bool,
+}
+
+impl Progress {
+    pub fn new(state: &AppState, token: String) -> Self {
+        Self {
+            client: state.client.clone(),
+            application_id: state.application_id,
+            token,
+            clear_components: false,
+        }
+    }
+
+    /// For component handlers which answered with [`deferred_update`], so
+    /// the progress replaces the message the component is on. Its
+    /// components are removed, so they can't be used again while the
+    /// handler runs.
+    pub fn for_component(state: &AppState, token: String) -> Self {
+        Self {
+            clear_components: true,
+            ..Self::new(state, token)
+        }
+    }
+
+    /// Replace the response with a short status line, like "Pinning message...".
+    pub async fn update(&self, status: &str) {
+        let client = self.client.interaction(self.application_id);
+        let mut request = client.update_response(&self.token).content(Some(status));
+        if self.clear_components {
+            request = request.components(Some(&[]));
+        }
+        if let Err(e) = request.await {
+            if is_token_expired(&e) {
+                tracing::warn!("Interaction token expired while updating progress");
+            } else {
+                tracing::warn!("Failed to update progress: {e:?}");
+            }
+        }
+    }
+
+    /// Replace the status line with the final result of the handler.
+    pub async fn finish<E: Debug + Display>(&self, result: Result<Embed, E>) {
+        let embed = result.unwrap_or_else(|e| {
+            tracing::error!(error = ?e, "{e}");
+            EmbedBuilder::new().description(e.to_string()).build()
+        });
+        let embeds = [embed];
+        let client = self.client.interaction(self.application_id);
+        let mut request = client
+            .update_response(&self.token)
+            .content(None)
+            .embeds(Some(&embeds));
+        if self.clear_components {
+            request = request.components(Some(&[]));
+        }
+        if let Err(e) = request.await {
+            if is_token_expired(&e) {
+                tracing::warn!("Interaction token expired before the final response was ready");
+            } else {
+                tracing::error!("Failed to send final response: {e:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use twilight_http::error::ErrorType;
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    interact::{post_report, InteractError, ModmailFormModal},
+    outbox::post_through_outbox,
+    AppState,
+};
+
+/// A bounded queue of reports waiting to be posted, enabled by setting
+/// `AGHAST_QUEUE_SIZE`. Reporters get their answer as soon as the report is
+/// queued, and a single worker posts reports one at a time, so bursts are
+/// spread out instead of all hitting Discord's rate limits at once.
+#[derive(Debug, Clone)]
+pub struct ReportQueue(Sender<QueuedReport>);
+
+#[derive(Debug)]
+pub struct QueuedReport {
+    guild_id: Id<GuildMarker>,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    report: ModmailFormModal,
+}
+
+impl ReportQueue {
+    pub fn new(size: usize) -> (Self, Receiver<QueuedReport>) {
+        let (tx, rx) = mpsc::channel(size);
+        (Self(tx), rx)
+    }
+}
+
+/// Queue a report if the queue is enabled, or post it right away through the
+/// outbox if not.
+pub async fn submit_report(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    reporter: Id<UserMarker>,
+    target_channel: Id<ChannelMarker>,
+    report: ModmailFormModal,
+) -> Result<(), InteractError> {
+    let Some(ReportQueue(queue)) = &state.report_queue else {
+        post_through_outbox(state, guild_id, reporter, target_channel, report).await?;
+        state.metrics.report_submitted();
+        return Ok(());
+    };
+    let queued = QueuedReport {
+        guild_id,
+        reporter,
+        target_channel,
+        report,
+    };
+    match queue.try_send(queued) {
+        Ok(()) => {
+            state.metrics.report_submitted();
+            Ok(())
+        }
+        Err(TrySendError::Full(_)) => Err(InteractError::Busy),
+        Err(TrySendError::Closed(_)) => Err(InteractError::QueueClosed),
+    }
+}
+
+/// Post queued reports until the queue is dropped.
+pub async fn run(state: AppState, mut queue: Receiver<QueuedReport>) {
+    while let Some(queued) = queue.recv().await {
+        post_queued(&state, &queued, RETRY_DELAY).await;
+    }
+}
+
+/// How often a queued report is tried before it's given up on.
+const ATTEMPTS: u32 = 3;
+/// How long to wait before the first retry. Doubled for every retry after.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// The reporter was told their report is on its way, so failures Discord
+/// might get over are retried. Reports which still can't be posted are
+/// counted in the metrics.
+async fn post_queued(state: &AppState, queued: &QueuedReport, mut retry_delay: Duration) {
+    for attempt in 1..=ATTEMPTS {
+        let result = post_report(
+            state,
+            Some(queued.guild_id),
+            queued.reporter,
+            queued.target_channel,
+            queued.report.clone(),
+        )
+        .await;
+        match result {
+            Ok(_) => return,
+            Err(e) if attempt < ATTEMPTS && is_transient(&e) => {
+                tracing::warn!(
+                    "Failed to post queued report from {} to {}, retrying in {retry_delay:?}: \
+                     {e:?}",
+                    queued.reporter,
+                    queued.target_channel
+                );
+                tokio::time::sleep(retry_delay).await;
+                retry_delay *= 2;
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to post queued report from {} to {}: {e:?}",
+                    queued.reporter,
+                    queued.target_channel
+                );
+                state.metrics.queued_report_failed();
+                return;
+            }
+        }
+    }
+}
+
+/// Whether trying again later might work, like when Discord had an outage.
+pub const fn is_transient(error: &InteractError) -> bool {
+    let InteractError::Http(error) = error else {
+        return false;
+    };
+    match error.kind() {
+        ErrorType::RequestError
+        | ErrorType::RequestTimedOut
+        | ErrorType::ServiceUnavailable { .. } => true,
+        ErrorType::Response { status, .. } => status.is_server_error(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{self, MockDiscord};
+
+    const MODMAIL: u64 = 60;
+
+    fn queued() -> QueuedReport {
+        QueuedReport {
+            guild_id: Id::new(testing::GUILD),
+            reporter: Id::new(30),
+            target_channel: Id::new(MODMAIL),
+            report: ModmailFormModal {
+                user: Some("wumpus".to_string()),
+                message_link: None,
+                channel: None,
+                reason: "Being rude".to_string(),
+                custom: BTreeMap::new(),
+            },
+        }
+    }
+
+    /// Post a queued report to a modmail channel which answers the first
+    /// `failures` posts with `error`. Returns how often it was tried, and the
+    /// metrics after.
+    fn post_failing(failures: usize, error: (StatusCode, Value)) -> (usize, String) {
+        testing::runtime().block_on(async {
+            let tries = Arc::new(AtomicUsize::new(0));
+            let counter = tries.clone();
+            let discord = MockDiscord::start(move |request| match request.path.as_str() {
+                "/channels/60/messages" if counter.fetch_add(1, Ordering::Relaxed) < failures => {
+                    error.clone()
+                }
+                "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let state = discord.state();
+            post_queued(&state, &queued(), Duration::from_millis(1)).await;
+            let posts = discord
+                .requests_to(&Method::POST, "/channels/60/messages")
+                .len();
+            (posts, state.metrics.render())
+        })
+    }
+
+    fn server_error() -> (StatusCode, Value) {
+        testing::api_error(StatusCode::INTERNAL_SERVER_ERROR, 0)
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (tries, metrics) = post_failing(2, server_error());
+        assert_eq!(tries, 3);
+        assert!(metrics.contains("aghast_queued_report_failures_total 0\n"));
+    }
+
+    #[test]
+    fn counts_reports_which_keep_failing() {
+        let (tries, metrics) = post_failing(usize::MAX, server_error());
+        assert_eq!(tries, ATTEMPTS as usize);
+        assert!(metrics.contains("aghast_queued_report_failures_total 1\n"));
+    }
+
+    #[test]
+    fn does_not_retry_what_cant_work() {
+        let error = testing::api_error(StatusCode::FORBIDDEN, 50013);
+        let (tries, metrics) = post_failing(usize::MAX, error);
+        assert_eq!(tries, 1);
+        assert!(metrics.contains("aghast_queued_report_failures_total 1\n"));
+    }
+}
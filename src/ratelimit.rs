@@ -0,0 +1,212 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+use crate::store::Store;
+
+/// Recent submissions of each user in each server.
+type Submitted = HashMap<(Id<GuildMarker>, Id<UserMarker>), VecDeque<Instant>>;
+
+/// Limits how many report forms each user may submit in a server within a
+/// sliding window, configured with `AGHAST_SUBMIT_LIMIT` and
+/// `AGHAST_SUBMIT_WINDOW_SECS`. Servers may choose their own window with
+/// `/cooldown`.
+#[derive(Debug)]
+pub struct SubmitLimits {
+    /// 0 doesn't limit submissions at all.
+    max: usize,
+    window: Duration,
+    cooldowns: Mutex<HashMap<Id<GuildMarker>, Duration>>,
+    submitted: Mutex<Submitted>,
+}
+
+impl SubmitLimits {
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            cooldowns: Mutex::default(),
+            submitted: Mutex::default(),
+        }
+    }
+
+    /// Apply the cooldowns servers chose before a restart.
+    pub async fn load_cooldowns(&self, store: &Store) -> Result<(), sqlx::Error> {
+        for (guild, secs) in store.cooldowns().await? {
+            self.set_cooldown(guild, Duration::from_secs(secs));
+        }
+        Ok(())
+    }
+
+    /// How many reports a user may submit within [`Self::window`].
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+
+    /// The window of `guild`: its cooldown, or `AGHAST_SUBMIT_WINDOW_SECS`
+    /// if it didn't choose one.
+    pub fn window(&self, guild: Id<GuildMarker>) -> Duration {
+        self.cooldowns
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&guild)
+            .copied()
+            .unwrap_or(self.window)
+    }
+
+    pub fn set_cooldown(&self, guild: Id<GuildMarker>, window: Duration) {
+        self.cooldowns
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(guild, window);
+    }
+
+    /// Count a submission, unless the user already submitted `max` in
+    /// `guild` within its window. Then returns how long until they may
+    /// submit again.
+    pub fn try_submit(&self, guild: Id<GuildMarker>, user: Id<UserMarker>) -> Result<(), Duration> {
+        self.try_submit_at(guild, user, Instant::now())
+    }
+
+    fn try_submit_at(
+        &self,
+        guild: Id<GuildMarker>,
+        user: Id<UserMarker>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        if self.max == 0 {
+            return Ok(());
+        }
+        let cooldowns = self
+            .cooldowns
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let window_of = |guild| cooldowns.get(&guild).copied().unwrap_or(self.window);
+        let mut submitted = self
+            .submitted
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        submitted.retain(|(guild, _), at| {
+            let window = window_of(*guild);
+            while at
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= window)
+            {
+                at.pop_front();
+            }
+            !at.is_empty()
+        });
+        let window = window_of(guild);
+        drop(cooldowns);
+        let at = submitted.entry((guild, user)).or_default();
+        let result = if at.len() < self.max {
+            at.push_back(now);
+            Ok(())
+        } else {
+            Err(at.front().map_or(Duration::ZERO, |oldest| {
+                window.saturating_sub(now.duration_since(*oldest))
+            }))
+        };
+        drop(submitted);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_mins(1);
+    const GUILD: Id<GuildMarker> = Id::new(10);
+
+    #[test]
+    fn allows_max_submissions_per_window() {
+        let limits = SubmitLimits::new(3, WINDOW);
+        let (user, start) = (Id::new(30), Instant::now());
+        let at = |secs| start + Duration::from_secs(secs);
+        assert_eq!(limits.try_submit_at(GUILD, user, at(0)), Ok(()));
+        assert_eq!(limits.try_submit_at(GUILD, user, at(1)), Ok(()));
+        assert_eq!(limits.try_submit_at(GUILD, user, at(2)), Ok(()));
+        // Until the first submission leaves the window.
+        assert_eq!(
+            limits.try_submit_at(GUILD, user, at(3)),
+            Err(Duration::from_secs(57))
+        );
+        assert_eq!(
+            limits.try_submit_at(GUILD, user, at(59)),
+            Err(Duration::from_secs(1))
+        );
+        // Rejected submissions don't count.
+        assert_eq!(limits.try_submit_at(GUILD, user, at(60)), Ok(()));
+        assert_eq!(
+            limits.try_submit_at(GUILD, user, at(60)),
+            Err(Duration::from_secs(1))
+        );
+        assert_eq!(limits.try_submit_at(GUILD, user, at(61)), Ok(()));
+    }
+
+    #[test]
+    fn limits_each_user_separately() {
+        let limits = SubmitLimits::new(1, WINDOW);
+        let now = Instant::now();
+        assert_eq!(limits.try_submit_at(GUILD, Id::new(30), now), Ok(()));
+        assert_eq!(limits.try_submit_at(GUILD, Id::new(31), now), Ok(()));
+        assert!(limits.try_submit_at(GUILD, Id::new(30), now).is_err());
+    }
+
+    #[test]
+    fn forgets_users_once_their_window_passed() {
+        let limits = SubmitLimits::new(1, WINDOW);
+        let now = Instant::now();
+        for user in 1..=100 {
+            assert_eq!(limits.try_submit_at(GUILD, Id::new(user), now), Ok(()));
+        }
+        assert_eq!(
+            limits.try_submit_at(GUILD, Id::new(30), now + WINDOW),
+            Ok(())
+        );
+        let users: Vec<_> = limits.submitted.lock().unwrap().keys().copied().collect();
+        assert_eq!(users, [(GUILD, Id::new(30))]);
+    }
+
+    #[test]
+    fn servers_choose_their_own_window() {
+        let limits = SubmitLimits::new(1, WINDOW);
+        let (user, start) = (Id::new(30), Instant::now());
+        let other = Id::new(11);
+        limits.set_cooldown(GUILD, Duration::from_secs(10));
+        assert_eq!(limits.window(GUILD), Duration::from_secs(10));
+        assert_eq!(limits.window(other), WINDOW);
+
+        assert_eq!(limits.try_submit_at(GUILD, user, start), Ok(()));
+        assert_eq!(limits.try_submit_at(other, user, start), Ok(()));
+        let later = start + Duration::from_secs(4);
+        assert_eq!(
+            limits.try_submit_at(GUILD, user, later),
+            Err(Duration::from_secs(6))
+        );
+        assert_eq!(
+            limits.try_submit_at(other, user, later),
+            Err(Duration::from_secs(56))
+        );
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limits.try_submit_at(GUILD, user, later), Ok(()));
+        assert!(limits.try_submit_at(other, user, later).is_err());
+    }
+
+    #[test]
+    fn zero_doesnt_limit() {
+        let limits = SubmitLimits::new(0, WINDOW);
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(limits.try_submit_at(GUILD, Id::new(30), now), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::{
+        message::{AllowedMentions, MessageFlags},
+        Channel,
+    },
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{MessageMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    assign::CLAIMED,
+    extract::{ExtractChannel, ExtractMember, SlashCommand},
+    interact::{truncate, InteractError},
+    resolve::CONTENT_MAX_CHARS,
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "reassign",
+    desc = "Hand a claimed report over to another moderator",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct ReassignCommand {
+    /// The moderator to hand the report over to
+    moderator: Id<UserMarker>,
+    /// A link to the report, if this isn't used in its thread or forum post
+    #[command(max_length = 200)]
+    report: Option<String>,
+}
+
+impl ReassignCommand {
+    const fn permissions() -> Permissions {
+        Permissions::MANAGE_MESSAGES
+    }
+}
+
+/// Hand a claimed report over, for shift handovers. Only the moderator who
+/// claimed it, or an administrator, may do that.
+pub async fn reassign(
+    State(state): State<AppState>,
+    ExtractMember(member): ExtractMember,
+    ExtractChannel(channel): ExtractChannel,
+    SlashCommand(cmd): SlashCommand<ReassignCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let user = member.user.as_ref().ok_or(InteractError::NoUser)?.id;
+    let message_id =
+        report_id(cmd.report.as_deref(), &channel).ok_or(InteractError::NoReportGiven)?;
+    let store = state.store.as_ref().ok_or(InteractError::NotClaimed)?;
+    let (channel_id, claimer) = store
+        .assignment(message_id)
+        .await?
+        .ok_or(InteractError::NotClaimed)?;
+    let is_admin = member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(Permissions::ADMINISTRATOR));
+    if user != claimer && !is_admin {
+        return Err(InteractError::CannotReassign(claimer));
+    }
+
+    let message = state
+        .client
+        .message(channel_id, message_id)
+        .await?
+        .model()
+        .await?;
+    // Replace the line saying who claimed it, keeping the rest of the report.
+    let report = message
+        .content
+        .strip_prefix(CLAIMED)
+        .and_then(|claimed| claimed.split_once('\n'))
+        .map_or(message.content.as_str(), |(_, report)| report);
+    let content = truncate(
+        format!("{CLAIMED}<@{}> (reassigned)\n{report}", cmd.moderator),
+        CONTENT_MAX_CHARS,
+        "…",
+    );
+    state
+        .client
+        .update_message(channel_id, message_id)
+        .content(Some(&content))
+        .allowed_mentions(Some(&AllowedMentions::default()))
+        .await?;
+    store
+        .assign_report(channel_id, message_id, cmd.moderator)
+        .await?;
+    // Someone is handling it, so it doesn't need to be escalated anymore.
+    store.untrack_report(channel_id, message_id).await?;
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .allowed_mentions(AllowedMentions::default())
+        .content(format!("Reassigned the report to <@{}>.", cmd.moderator))
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+/// The report a link or ID points to, or else the one whose thread or forum
+/// post `channel` is. Both have the ID of the report's message.
+fn report_id(report: Option<&str>, channel: &Channel) -> Option<Id<MessageMarker>> {
+    match report {
+        Some(report) => report.trim().rsplit('/').next()?.parse().ok(),
+        None => channel.kind.is_thread().then(|| channel.id.cast()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::{
+        store::Store,
+        testing::{self, MockDiscord},
+    };
+
+    const MODMAIL: u64 = 60;
+    const REPORT: u64 = 70;
+
+    async fn discord() -> MockDiscord {
+        MockDiscord::start(|request| match (&request.method, request.path.as_str()) {
+            (&Method::GET, "/channels/60/messages/70") => {
+                let mut message = testing::message(MODMAIL, REPORT);
+                message["content"] = json!("👋 Claimed by <@31>\nReport from <@40>");
+                (StatusCode::OK, message)
+            }
+            (&Method::PATCH, "/channels/60/messages/70") => {
+                (StatusCode::OK, testing::message(MODMAIL, REPORT))
+            }
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await
+    }
+
+    /// A state with the report claimed by `claimer`.
+    async fn claimed_by(discord: &MockDiscord, claimer: u64) -> (AppState, Store) {
+        let store = testing::store().await;
+        store
+            .assign_report(Id::new(MODMAIL), Id::new(REPORT), Id::new(claimer))
+            .await
+            .unwrap();
+        let state = AppState {
+            store: Some(store.clone()),
+            ..discord.state()
+        };
+        (state, store)
+    }
+
+    fn reassign_command(report: Option<&str>) -> Interaction {
+        let mut options = vec![json!({ "name": "moderator", "type": 6, "value": "50" })];
+        if let Some(report) = report {
+            options.push(json!({ "name": "report", "type": 3, "value": report }));
+        }
+        testing::command("reassign", &Value::Array(options))
+    }
+
+    fn edits(discord: &MockDiscord) -> Vec<Value> {
+        discord
+            .requests_to(&Method::PATCH, "/channels/60/messages/70")
+            .into_iter()
+            .map(|request| request.body)
+            .collect()
+    }
+
+    #[test]
+    fn the_claimer_can_hand_a_report_over() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let (state, store) = claimed_by(&discord, 30).await;
+            store
+                .track_report(Id::new(MODMAIL), Id::new(REPORT), Id::new(MODMAIL), 0)
+                .await
+                .unwrap();
+            let link = "https://discord.com/channels/10/60/70";
+            let response = Box::pin(niloecl::make_handler(reassign)(
+                reassign_command(Some(link)),
+                state,
+            ))
+            .await;
+            assert_eq!(
+                testing::content(&response),
+                Some("Reassigned the report to <@50>.")
+            );
+            assert_eq!(
+                edits(&discord)[0]["content"],
+                "👋 Claimed by <@50> (reassigned)\nReport from <@40>"
+            );
+            assert_eq!(
+                store.assignment(Id::new(REPORT)).await.unwrap(),
+                Some((Id::new(MODMAIL), Id::new(50)))
+            );
+            assert!(store.overdue_reports(u64::MAX).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn finds_the_report_of_the_thread_its_used_in() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let (state, _store) = claimed_by(&discord, 30).await;
+            let mut command = reassign_command(None);
+            command.channel = Some(
+                serde_json::from_value(testing::channel(REPORT, Some(Id::new(testing::GUILD)), 11))
+                    .unwrap(),
+            );
+            let response = Box::pin(niloecl::make_handler(reassign)(command, state)).await;
+            assert_eq!(testing::error_message(&response), None);
+            assert_eq!(edits(&discord).len(), 1);
+
+            let discord = self::discord().await;
+            let (state, _store) = claimed_by(&discord, 30).await;
+            let response = Box::pin(niloecl::make_handler(reassign)(
+                reassign_command(None),
+                state,
+            ))
+            .await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some(InteractError::NoReportGiven.to_string().as_str())
+            );
+        });
+    }
+
+    #[test]
+    fn only_the_claimer_or_an_admin_can_reassign() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let (state, store) = claimed_by(&discord, 31).await;
+            let mut command = reassign_command(Some("70"));
+            if let Some(member) = &mut command.member {
+                member.permissions = Some(Permissions::MANAGE_MESSAGES);
+            }
+            let response = Box::pin(niloecl::make_handler(reassign)(command, state.clone())).await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some(
+                    InteractError::CannotReassign(Id::new(31))
+                        .to_string()
+                        .as_str()
+                )
+            );
+            assert!(edits(&discord).is_empty());
+            assert_eq!(
+                store.assignment(Id::new(REPORT)).await.unwrap(),
+                Some((Id::new(MODMAIL), Id::new(31)))
+            );
+
+            // The test member is an administrator otherwise.
+            let response = Box::pin(niloecl::make_handler(reassign)(
+                reassign_command(Some("70")),
+                state,
+            ))
+            .await;
+            assert_eq!(testing::error_message(&response), None);
+            assert_eq!(edits(&discord).len(), 1);
+        });
+    }
+
+    #[test]
+    fn unclaimed_reports_cant_be_reassigned() {
+        testing::runtime().block_on(async {
+            let discord = discord().await;
+            let (state, _store) = claimed_by(&discord, 30).await;
+            let response = Box::pin(niloecl::make_handler(reassign)(
+                reassign_command(Some("71")),
+                state,
+            ))
+            .await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some(InteractError::NotClaimed.to_string().as_str())
+            );
+            assert!(discord.requests().is_empty());
+        });
+    }
+}
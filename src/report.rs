@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    config::ReportField,
+    confirm,
+    extract::{ExtractGuildId, ExtractMember, InteractionId, Localized, SlashCommand},
+    features::Feature,
+    interact::{
+        check_channel_ref, check_submit_limit, reason_max_length, report_received_components,
+        InteractError, ModmailFormModal,
+    },
+    queue::submit_report,
+    AppState,
+};
+
+/// The report form in a single command. Only registered when the config file
+/// has `report_channels`, because that's the only way to know where reports
+/// from a guild should go.
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "report",
+    desc = "Report a user to the moderators",
+    dm_permission = false
+)]
+pub struct ReportCommand {
+    /// Reason for reporting (what happened, in detail)
+    #[command(min_length = 1, max_length = 4000)]
+    reason: String,
+    /// The user you wish to report
+    user: Option<Id<UserMarker>>,
+    /// The channel it happened in
+    channel: Option<Id<ChannelMarker>>,
+    /// Link to the message you are reporting
+    #[command(max_length = 128)]
+    message_link: Option<String>,
+}
+
+pub async fn report(
+    State(state): State<AppState>,
+    InteractionId(interaction_id): InteractionId,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    Localized(messages): Localized,
+    SlashCommand(cmd): SlashCommand<ReportCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let user = member.user.as_ref().ok_or(InteractError::NoUser)?;
+    let reporter = user.id;
+    if state.blocklist.is_blocked(guild_id, reporter) {
+        return Err(InteractError::Blocked);
+    }
+    let target_channel = *state
+        .report_channels
+        .get(&guild_id)
+        .ok_or(InteractError::NoReportChannel)?;
+    // Members without the setup's role never see its form, so /report can't
+    // be a way around it either.
+    let required_role = state.forms.required_role(target_channel);
+    if let Some(role) = required_role.filter(|role| !member.roles.contains(role)) {
+        return Err(InteractError::MissingRequiredRole(role));
+    }
+    let form = state.forms.get(target_channel);
+    if user.bot && !form.allow_bot_reporters {
+        tracing::warn!("Rejected report from bot {reporter} in {target_channel}");
+        return Err(InteractError::BotReporter);
+    }
+
+    // The form enforces these through its text inputs, but command options
+    // can't change per setup, so check them here instead.
+    let max = reason_max_length(form);
+    if cmd.reason.chars().count() > usize::from(max) {
+        return Err(InteractError::ReasonTooLong(max));
+    }
+    let user = option_field(form.is_enabled(ReportField::User), "user", cmd.user)
+        .map_err(InteractError::MissingOption)?;
+    let channel = option_field(
+        form.is_enabled(ReportField::Channel),
+        "channel",
+        cmd.channel,
+    )
+    .map_err(InteractError::MissingOption)?;
+    let message_link = cmd
+        .message_link
+        .filter(|_| form.is_enabled(ReportField::MessageLink));
+
+    let mut report = ModmailFormModal {
+        user: user.map(|user| format!("<@{user}>")),
+        message_link,
+        channel: channel.map(|channel| format!("<#{channel}>")),
+        reason: cmd.reason,
+        // Command options are fixed, so /report can't ask for custom fields.
+        custom: BTreeMap::new(),
+    };
+    check_channel_ref(
+        &state,
+        form,
+        guild_id,
+        reporter,
+        &member.roles,
+        &mut report.channel,
+    )
+    .await?;
+    check_submit_limit(&state, guild_id, reporter, target_channel)
+        .map_err(InteractError::RateLimited)?;
+
+    let confirm = form.confirm_before_submit;
+    if state
+        .features
+        .enabled(Some(guild_id), Feature::ConfirmBeforeSubmit, confirm)
+    {
+        return Ok(confirm::ask_confirmation(
+            &state,
+            &messages,
+            interaction_id,
+            reporter,
+            target_channel,
+            required_role,
+            report,
+        )?);
+    }
+
+    submit_report(&state, guild_id, reporter, target_channel, report).await?;
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(form.response_flags())
+        .content(&messages.report_received)
+        .components(report_received_components(
+            &state,
+            target_channel,
+            required_role,
+        )?)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+/// Fields which are enabled are required, like they are in the form. Values
+/// for disabled fields are dropped. Returns the name of a missing option.
+fn option_field<T>(
+    enabled: bool,
+    name: &'static str,
+    value: Option<T>,
+) -> Result<Option<T>, &'static str> {
+    match (enabled, value) {
+        (false, _) => Ok(None),
+        (true, Some(value)) => Ok(Some(value)),
+        (true, None) => Err(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use twilight_model::application::interaction::Interaction;
+
+    use super::*;
+    use crate::{
+        config::{FormConfig, Forms},
+        interact::handle_interaction,
+        messages::Messages,
+        testing::{self, MockDiscord},
+    };
+
+    const MODMAIL: u64 = 60;
+    const ROLE: u64 = 80;
+
+    /// `/report` in a server whose setup requires [`ROLE`].
+    fn state(discord: &MockDiscord) -> AppState {
+        let mut config = testing::config(Id::new(MODMAIL), FormConfig::default());
+        config.setups[0].required_role = Some(Id::new(ROLE));
+        AppState {
+            forms: Arc::new(Forms::new(&config)),
+            report_channels: Arc::new(HashMap::from([(Id::new(testing::GUILD), Id::new(MODMAIL))])),
+            ..discord.state()
+        }
+    }
+
+    fn report_command(roles: &[u64]) -> Interaction {
+        testing::interaction(json!({
+            "type": 2,
+            "data": {
+                "id": "50",
+                "name": "report",
+                "type": 1,
+                "options": [
+                    { "name": "reason", "type": 3, "value": "Being rude" },
+                    { "name": "user", "type": 6, "value": "31" },
+                    { "name": "channel", "type": 7, "value": "20" },
+                ],
+            },
+            "member": testing::member(30, roles),
+        }))
+    }
+
+    async fn modmail() -> MockDiscord {
+        MockDiscord::start(|request| match request.path.as_str() {
+            "/channels/60/messages" => (StatusCode::OK, testing::message(MODMAIL, 70)),
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await
+    }
+
+    #[test]
+    fn accepts_members_with_the_required_role() {
+        testing::runtime().block_on(async {
+            let discord = modmail().await;
+            let response =
+                Box::pin(handle_interaction(state(&discord), report_command(&[ROLE]))).await;
+            assert_eq!(
+                testing::content(&response),
+                Some(Messages::default().report_received.as_str())
+            );
+            assert_eq!(
+                discord
+                    .requests_to(&Method::POST, "/channels/60/messages")
+                    .len(),
+                1
+            );
+        });
+    }
+
+    #[test]
+    fn rejects_members_without_the_required_role() {
+        testing::runtime().block_on(async {
+            let discord = modmail().await;
+            let response =
+                Box::pin(handle_interaction(state(&discord), report_command(&[81]))).await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some("Only members with the <@&80> role can send reports here.")
+            );
+            assert!(discord
+                .requests_to(&Method::POST, "/channels/60/messages")
+                .is_empty());
+        });
+    }
+}
@@ -0,0 +1,417 @@
+use niloecl::{IntoResponse, ModalSubmit, State};
+use twilight_model::{
+    application::interaction::Interaction,
+    channel::{
+        message::{
+            component::{ActionRow, Button, ButtonStyle, TextInput, TextInputStyle},
+            AllowedMentions, Component, MessageFlags,
+        },
+        Channel, Message,
+    },
+    guild::PartialMember,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    assign,
+    config::FormConfig,
+    escalate,
+    extract::{
+        custom_id, get_custom_id_rpc, CidArgs, CustomIdTooLong, ExtractChannel, ExtractMember,
+        ExtractMessage,
+    },
+    interact::{truncate, InteractError, ModalResponse},
+    AppState,
+};
+
+/// Custom ID name of the "Resolve" button on reports.
+pub const RESOLVE_REPORT: &str = "resolve_report";
+
+/// Custom ID name of the modal asking for a close reason.
+pub const RESOLVE_REASON: &str = "resolve_reason";
+
+/// Discord's limit on the length of message content.
+pub const CONTENT_MAX_CHARS: usize = 2000;
+/// Close reasons are a short note, the report itself has the details.
+const REASON_MAX_CHARS: u16 = 200;
+
+/// The "Resolve" button for a report from `reporter`, if the form has one.
+pub fn components(
+    form: &FormConfig,
+    reporter: Id<UserMarker>,
+) -> Result<Vec<Component>, CustomIdTooLong> {
+    if !form.resolve_button {
+        return Ok(Vec::new());
+    }
+    Ok(vec![action_row(reporter, false)?])
+}
+
+fn action_row(reporter: Id<UserMarker>, resolved: bool) -> Result<Component, CustomIdTooLong> {
+    let label = if resolved { "Resolved" } else { "Resolve" };
+    let button = Component::Button(Button {
+        custom_id: Some(custom_id(RESOLVE_REPORT, &[&reporter])?),
+        disabled: resolved,
+        emoji: None,
+        label: Some(label.to_string()),
+        style: ButtonStyle::Success,
+        url: None,
+        sku_id: None,
+    });
+    Ok(Component::ActionRow(ActionRow {
+        components: vec![button],
+    }))
+}
+
+/// Who sent the report in `message`, if it still has an enabled "Resolve"
+/// button.
+pub fn open_report(message: &Message) -> Option<Id<UserMarker>> {
+    message
+        .components
+        .iter()
+        .filter_map(|row| match row {
+            Component::ActionRow(row) => Some(&row.components),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|component| match component {
+            Component::Button(Button {
+                custom_id: Some(custom_id),
+                disabled: false,
+                ..
+            }) => match get_custom_id_rpc(custom_id) {
+                Ok((RESOLVE_REPORT, args)) => args.first()?.parse().ok(),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// Whether clicking "Resolve" in this interaction's channel asks for a
+/// reason first.
+pub fn asks_reason(state: &AppState, interaction: &Interaction) -> bool {
+    interaction
+        .channel
+        .as_ref()
+        .is_some_and(|channel| state.forms.get(modmail_channel(channel)).resolve_reason)
+}
+
+/// The channel reports in `channel` were sent to. Reports in forums are
+/// posts of their own, so that's the forum.
+fn modmail_channel(channel: &Channel) -> Id<ChannelMarker> {
+    channel
+        .parent_id
+        .filter(|_| channel.kind.is_thread())
+        .unwrap_or(channel.id)
+}
+
+/// Mark a report as handled, for moderators with `AGHAST_RESOLVE_PERMISSIONS`
+/// in its channel. Asks for a reason first if the form wants one.
+pub async fn resolve_report(
+    State(state): State<AppState>,
+    ExtractMember(member): ExtractMember,
+    ExtractChannel(channel): ExtractChannel,
+    ExtractMessage(message): ExtractMessage,
+    CidArgs((reporter,)): CidArgs<(Id<UserMarker>,)>,
+) -> Result<InteractionResponse, InteractError> {
+    let moderator = member.user.as_ref().ok_or(InteractError::NoUser)?.id;
+    if !can_resolve(&state, &member) {
+        return Err(InteractError::CannotResolve);
+    }
+    if state.forms.get(modmail_channel(&channel)).resolve_reason {
+        return Ok(reason_modal(reporter)?.into_response());
+    }
+    resolve(&state, moderator, &channel, &message, reporter, None).await
+}
+
+fn reason_modal(reporter: Id<UserMarker>) -> Result<ModalResponse, CustomIdTooLong> {
+    let input = TextInput {
+        custom_id: "reason".into(),
+        label: "Why is this report resolved?".into(),
+        max_length: Some(REASON_MAX_CHARS),
+        min_length: None,
+        placeholder: Some("e.g. Warned the user".into()),
+        required: Some(true),
+        style: TextInputStyle::Short,
+        value: None,
+    };
+    Ok(ModalResponse {
+        title: "Resolve report".to_string(),
+        custom_id: custom_id(RESOLVE_REASON, &[&reporter])?,
+        components: vec![Component::ActionRow(ActionRow {
+            components: vec![Component::TextInput(input)],
+        })],
+    })
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CloseReason {
+    reason: String,
+}
+
+/// Resolve a report with the reason from [`reason_modal`].
+pub async fn resolve_with_reason(
+    State(state): State<AppState>,
+    ExtractMember(member): ExtractMember,
+    ExtractChannel(channel): ExtractChannel,
+    ExtractMessage(message): ExtractMessage,
+    CidArgs((reporter,)): CidArgs<(Id<UserMarker>,)>,
+    modal: ModalSubmit<CloseReason>,
+) -> Result<InteractionResponse, InteractError> {
+    // Checked again in case the permissions were taken away while the modal
+    // was open.
+    let moderator = member.user.as_ref().ok_or(InteractError::NoUser)?.id;
+    if !can_resolve(&state, &member) {
+        return Err(InteractError::CannotResolve);
+    }
+    let reason = modal.data.reason.trim();
+    let reason = (!reason.is_empty()).then_some(reason);
+    resolve(&state, moderator, &channel, &message, reporter, reason).await
+}
+
+pub fn can_resolve(state: &AppState, member: &PartialMember) -> bool {
+    member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(state.resolve_permissions))
+}
+
+async fn resolve(
+    state: &AppState,
+    moderator: Id<UserMarker>,
+    channel: &Channel,
+    message: &Message,
+    reporter: Id<UserMarker>,
+    reason: Option<&str>,
+) -> Result<InteractionResponse, InteractError> {
+    mark_resolved(state, moderator, channel, message, reporter, reason).await?;
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(format!("Marked the report from <@{reporter}> as resolved."))
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+/// Edit a report in `channel` to show who resolved it, and disable its
+/// "Resolve" button.
+pub async fn mark_resolved(
+    state: &AppState,
+    moderator: Id<UserMarker>,
+    channel: &Channel,
+    message: &Message,
+    reporter: Id<UserMarker>,
+    reason: Option<&str>,
+) -> Result<(), InteractError> {
+    let reason = reason
+        .map(|reason| format!(": {reason}"))
+        .unwrap_or_default();
+    // It's not overdue anymore.
+    let report = message
+        .content
+        .strip_prefix(escalate::OVERDUE)
+        .unwrap_or(&message.content);
+    let content = truncate(
+        format!("✅ Resolved by <@{moderator}>{reason}\n{report}"),
+        CONTENT_MAX_CHARS,
+        "…",
+    );
+    // Nobody should be pinged again, the ping was for the report itself.
+    state
+        .client
+        .update_message(message.channel_id, message.id)
+        .content(Some(&content))
+        .components(Some(&[action_row(reporter, true)?]))
+        .allowed_mentions(Some(&AllowedMentions::default()))
+        .await?;
+    escalate::untrack(state, message).await;
+    assign::unassign(state, message).await;
+    swap_forum_tags(state, channel).await;
+    Ok(())
+}
+
+/// Replace the form's `forum_tags` on a resolved forum post with its
+/// `resolved_forum_tag`. The report says it's resolved already, so failing
+/// here only gets logged.
+async fn swap_forum_tags(state: &AppState, channel: &Channel) {
+    let form = state.forms.get(modmail_channel(channel));
+    let Some(resolved) = form.resolved_forum_tag.filter(|_| channel.kind.is_thread()) else {
+        return;
+    };
+    // Tags moderators added themselves stay.
+    let mut tags: Vec<_> = channel
+        .applied_tags
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|tag| *tag != resolved && !form.forum_tags.contains(tag))
+        .collect();
+    tags.push(resolved);
+    let result = state
+        .client
+        .update_thread(channel.id)
+        .applied_tags(Some(&tags))
+        .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to tag resolved report post {}: {e:?}", channel.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use twilight_model::channel::ChannelType;
+
+    use super::*;
+    use crate::testing::{self, MockDiscord};
+
+    const REPORT: u64 = 70;
+
+    fn report_message() -> Message {
+        let mut message = testing::message(testing::CHANNEL, REPORT);
+        message["content"] = json!("Report from <@40>");
+        serde_json::from_value(message).unwrap()
+    }
+
+    /// Discord, with reports in the interaction's channel asking for a reason
+    /// or not.
+    async fn discord(resolve_reason: bool) -> (MockDiscord, AppState) {
+        let discord = MockDiscord::start(|request| match request.path.as_str() {
+            "/channels/20/messages/70" => {
+                (StatusCode::OK, testing::message(testing::CHANNEL, REPORT))
+            }
+            _ => (StatusCode::NOT_FOUND, json!({})),
+        })
+        .await;
+        let form = FormConfig {
+            resolve_button: true,
+            resolve_reason,
+            ..FormConfig::default()
+        };
+        let state = AppState {
+            forms: testing::forms(Id::new(testing::CHANNEL), form),
+            ..discord.state()
+        };
+        (discord, state)
+    }
+
+    fn click() -> Interaction {
+        let mut interaction = testing::button("resolve_report:40");
+        interaction.message = Some(report_message());
+        interaction
+    }
+
+    fn updated_content(discord: &MockDiscord) -> Vec<String> {
+        discord
+            .requests_to(&Method::PATCH, "/channels/20/messages/70")
+            .into_iter()
+            .map(|request| request.body["content"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn resolves_in_one_click_without_a_reason_prompt() {
+        testing::runtime().block_on(async {
+            let (discord, state) = discord(false).await;
+            assert!(!asks_reason(&state, &click()));
+            let response = Box::pin(niloecl::make_handler(resolve_report)(click(), state)).await;
+            assert_eq!(
+                testing::content(&response),
+                Some("Marked the report from <@40> as resolved.")
+            );
+            assert_eq!(
+                updated_content(&discord),
+                ["✅ Resolved by <@30>\nReport from <@40>"]
+            );
+        });
+    }
+
+    #[test]
+    fn asks_for_a_reason_if_the_form_wants_one() {
+        testing::runtime().block_on(async {
+            let (discord, state) = discord(true).await;
+            assert!(asks_reason(&state, &click()));
+            let response = Box::pin(niloecl::make_handler(resolve_report)(click(), state)).await;
+            assert_eq!(response.kind, InteractionResponseType::Modal);
+            let data = response.data.unwrap();
+            assert_eq!(data.custom_id.as_deref(), Some("resolve_reason:40"));
+            assert!(updated_content(&discord).is_empty());
+        });
+    }
+
+    #[test]
+    fn shows_the_reason_on_the_report() {
+        testing::runtime().block_on(async {
+            let (discord, state) = discord(true).await;
+            let mut submit = testing::modal("resolve_reason:40", &[("reason", " Warned them ")]);
+            submit.message = Some(report_message());
+            let response =
+                Box::pin(niloecl::make_handler(resolve_with_reason)(submit, state)).await;
+            assert_eq!(
+                testing::content(&response),
+                Some("Marked the report from <@40> as resolved.")
+            );
+            assert_eq!(
+                updated_content(&discord),
+                ["✅ Resolved by <@30>: Warned them\nReport from <@40>"]
+            );
+        });
+    }
+
+    #[test]
+    fn swaps_forum_tags_on_resolved_posts() {
+        testing::runtime().block_on(async {
+            let discord = MockDiscord::start(|request| match request.path.as_str() {
+                "/channels/90/messages/70" => (StatusCode::OK, testing::message(90, REPORT)),
+                "/channels/90" => (
+                    StatusCode::OK,
+                    testing::channel(90, Some(Id::new(testing::GUILD)), 11),
+                ),
+                _ => (StatusCode::NOT_FOUND, json!({})),
+            })
+            .await;
+            let form = FormConfig {
+                resolve_button: true,
+                forum_tags: vec![Id::new(1)],
+                resolved_forum_tag: Some(Id::new(2)),
+                ..FormConfig::default()
+            };
+            let state = AppState {
+                forms: testing::forms(Id::new(60), form),
+                ..discord.state()
+            };
+            let mut post = testing::channel(90, Some(Id::new(testing::GUILD)), 11);
+            post["parent_id"] = json!("60");
+            post["applied_tags"] = json!(["1", "3"]);
+            let mut click = click();
+            click.channel = Some(serde_json::from_value(post).unwrap());
+            if let Some(message) = &mut click.message {
+                message.channel_id = Id::new(90);
+            }
+            Box::pin(niloecl::make_handler(resolve_report)(click, state)).await;
+
+            let tagged = discord.requests_to(&Method::PATCH, "/channels/90");
+            assert_eq!(tagged.len(), 2);
+            assert_eq!(tagged[1].path, "/channels/90");
+            assert_eq!(tagged[1].body["applied_tags"], json!(["3", "2"]));
+        });
+    }
+
+    #[test]
+    fn forum_posts_use_the_forum_form() {
+        let post: Channel = serde_json::from_value(json!({
+            "id": "90",
+            "parent_id": "60",
+            "type": ChannelType::PublicThread,
+        }))
+        .unwrap();
+        assert_eq!(modmail_channel(&post), Id::new(60));
+    }
+}
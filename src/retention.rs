@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use crate::store::{self, Store};
+
+/// How often stored reports are checked for ones past the retention period.
+const INTERVAL: Duration = Duration::from_hours(1);
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Delete stored reports older than `days` every [`INTERVAL`], forever.
+pub async fn run(store: Store, days: u64) {
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        interval.tick().await;
+        purge(&store, store::now(), days).await;
+    }
+}
+
+/// Delete the reports which are older than `days` at `now`, returning how
+/// many there were. Failing is only logged, the next cycle catches up.
+async fn purge(store: &Store, now: u64, days: u64) -> u64 {
+    let before = now.saturating_sub(days.saturating_mul(SECS_PER_DAY));
+    match store.purge_reports(before).await {
+        Ok(purged) => {
+            tracing::info!("Purged {purged} stored reports older than {days} days");
+            purged
+        }
+        Err(e) => {
+            tracing::error!("Failed to purge stored reports older than {days} days: {e:?}");
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use twilight_model::id::Id;
+
+    use super::*;
+    use crate::{interact::ModmailFormModal, testing};
+
+    fn report(reason: &str) -> ModmailFormModal {
+        ModmailFormModal {
+            user: None,
+            message_link: None,
+            channel: None,
+            reason: reason.to_string(),
+            custom: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn purges_reports_past_the_retention_period() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let (reporter, channel) = (Id::new(30), Id::new(60));
+            store
+                .record_report(reporter, channel, &report("Being rude"))
+                .await
+                .unwrap();
+
+            // Still within 30 days, 29 days later.
+            let now = store::now();
+            assert_eq!(purge(&store, now + 29 * SECS_PER_DAY, 30).await, 0);
+            assert_eq!(store.reports_by_user(reporter).await.unwrap().len(), 1);
+
+            assert_eq!(purge(&store, now + 31 * SECS_PER_DAY, 30).await, 1);
+            assert!(store.reports_by_user(reporter).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn failing_to_purge_is_only_logged() {
+        testing::runtime().block_on(async {
+            let store = testing::broken_store().await;
+            assert_eq!(purge(&store, store::now(), 30).await, 0);
+        });
+    }
+}
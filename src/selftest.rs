@@ -0,0 +1,182 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::Embed,
+    guild::Permissions,
+    http::interaction::InteractionResponse,
+    id::{
+        marker::{ChannelMarker, GuildMarker, MessageMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+
+use crate::{
+    config::{EntryPoints, ReportField},
+    extract::{ExtractGuildId, ExtractMember, InteractionToken, SlashCommand},
+    interact::{
+        form_fields, post_report, setup_message, validate_report, InteractError, ModmailFormModal,
+    },
+    progress::{deferred_ephemeral, Progress},
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "selftest",
+    desc = "Go through the whole report flow in a test channel",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct SelftestCommand {
+    /// The channel to test in, used as both button and modmail channel
+    channel: Id<ChannelMarker>,
+}
+
+impl SelftestCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+pub async fn selftest(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    ExtractMember(member): ExtractMember,
+    InteractionToken(token): InteractionToken,
+    SlashCommand(cmd): SlashCommand<SelftestCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let reporter = member.user.ok_or(InteractError::NoUser)?.id;
+    tokio::spawn(async move {
+        let progress = Progress::new(&state, token);
+        let result = Box::pin(run(&state, &progress, guild_id, reporter, cmd.channel)).await;
+        progress.finish(result).await;
+    });
+    Ok(deferred_ephemeral())
+}
+
+/// Post a setup message, build the form it opens and submit a report through
+/// it, like a user would. Everything posted is deleted again at the end. The
+/// report uses the form settings of `channel`, so it pings like a real one.
+async fn run(
+    state: &AppState,
+    progress: &Progress,
+    guild_id: Id<GuildMarker>,
+    reporter: Id<UserMarker>,
+    channel: Id<ChannelMarker>,
+) -> Result<Embed, InteractError> {
+    progress.update("Checking channel...").await;
+    let channel_info = state.client.channel(channel).await?.model().await?;
+    if channel_info.guild_id != Some(guild_id) {
+        return Err(InteractError::ChannelNotInGuild);
+    }
+    let mut steps = Vec::new();
+
+    progress.update("Posting setup message...").await;
+    let (embed, components) = setup_message(
+        "Self test, this message will be deleted shortly.".to_string(),
+        "Select a user to report".to_string(),
+        "Report".to_string(),
+        None,
+        channel,
+        EntryPoints::Both,
+        None,
+    )?;
+    let setup = async {
+        let message = state
+            .client
+            .create_message(channel)
+            .embeds(&[embed])
+            .components(&components)
+            .await?
+            .model()
+            .await?;
+        Ok::<_, InteractError>(message)
+    }
+    .await;
+    steps.push(step("Setup message", setup.as_ref().map(|_| "Posted")));
+
+    progress.update("Building form...").await;
+    let form = state.forms.get(channel);
+    let fields = form_fields(form, false).count();
+    steps.push(step(
+        "Form",
+        Ok::<_, &InteractError>(format!("{fields} fields")),
+    ));
+
+    progress.update("Submitting report...").await;
+    let sample = |field, value: &str| form.is_enabled(field).then(|| value.to_string());
+    let report = ModmailFormModal {
+        user: sample(ReportField::User, "selftest"),
+        message_link: None,
+        channel: sample(ReportField::Channel, &format!("<#{channel}>")),
+        reason: "Self test, this report will be deleted shortly.".to_string(),
+        custom: form
+            .custom_fields
+            .iter()
+            .map(|field| (field.id.clone(), "selftest".to_string()))
+            .collect(),
+    };
+    let posted = match validate_report(form, &report, false) {
+        Ok(()) => post_report(state, None, reporter, channel, report).await,
+        Err(e) => Err(e.into()),
+    };
+    steps.push(step("Report", posted.as_ref().map(|_| "Posted")));
+
+    progress.update("Cleaning up...").await;
+    let mut cleanup = Ok(());
+    if let Ok(message) = &setup {
+        cleanup = cleanup.and(delete_message(state, message.channel_id, message.id).await);
+    }
+    if let Ok(message) = &posted {
+        // Reports in forums are posts of their own, so remove the whole post.
+        cleanup = cleanup.and(if message.channel_id == channel {
+            delete_message(state, message.channel_id, message.id).await
+        } else {
+            delete_channel(state, message.channel_id).await
+        });
+    }
+    steps.push(step(
+        "Cleanup",
+        cleanup.as_ref().map(|()| "Deleted test messages"),
+    ));
+
+    let passed = steps.iter().all(|(passed, _)| *passed);
+    let title = if passed {
+        "✅ Self test passed"
+    } else {
+        "❌ Self test failed"
+    };
+    let mut result = EmbedBuilder::new().title(title);
+    for (_, field) in steps {
+        result = result.field(field);
+    }
+    Ok(result.build())
+}
+
+fn step<T: std::fmt::Display>(
+    name: &str,
+    result: Result<T, &InteractError>,
+) -> (bool, EmbedFieldBuilder) {
+    match result {
+        Ok(detail) => (true, EmbedFieldBuilder::new(name, format!("✅ {detail}"))),
+        Err(e) => {
+            tracing::warn!("Self test step {name} failed: {e:?}");
+            (false, EmbedFieldBuilder::new(name, format!("❌ {e}")))
+        }
+    }
+}
+
+async fn delete_message(
+    state: &AppState,
+    channel: Id<ChannelMarker>,
+    message: Id<MessageMarker>,
+) -> Result<(), InteractError> {
+    state.client.delete_message(channel, message).await?;
+    Ok(())
+}
+
+async fn delete_channel(state: &AppState, channel: Id<ChannelMarker>) -> Result<(), InteractError> {
+    state.client.delete_channel(channel).await?;
+    Ok(())
+}
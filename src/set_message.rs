@@ -0,0 +1,222 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::{AllowedMentions, MessageFlags},
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{ExtractGuildId, SlashCommand},
+    interact::InteractError,
+    messages::MessageKey,
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "set-message",
+    desc = "Change a message the bot answers with in this server",
+    dm_permission = false,
+    default_permissions = "Self::permissions"
+)]
+pub struct SetMessageCommand {
+    /// The message to change
+    key: MessageKey,
+    /// The new text, or leave it out to go back to the default
+    #[command(max_length = 2000)]
+    value: Option<String>,
+}
+
+impl SetMessageCommand {
+    const fn permissions() -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+}
+
+/// Messages are changed in memory even if the store is down, like the
+/// blocklist, so the change applies right away either way.
+pub async fn set_message(
+    State(state): State<AppState>,
+    ExtractGuildId(guild_id): ExtractGuildId,
+    SlashCommand(cmd): SlashCommand<SetMessageCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let SetMessageCommand { key, value } = cmd;
+    if let Some(value) = &value {
+        if !key.fits(value) {
+            return Err(InteractError::MessageLength(key.max_length()));
+        }
+    }
+
+    let saved = match (&state.store, &value) {
+        (None, _) => Ok(()),
+        (Some(store), Some(value)) => store.set_message(guild_id, key.name(), value).await,
+        (Some(store), None) => store.reset_message(guild_id, key.name()).await,
+    };
+    let mut content = if value.is_some() {
+        format!("Changed the `{}` message in this server.", key.name())
+    } else {
+        format!("The `{}` message is back to its default.", key.name())
+    };
+    state.messages.set_override(guild_id, key, value);
+
+    if state.store.is_none() {
+        content.push_str(
+            "\nMessages aren't saved because `AGHAST_DB_PATH` isn't set, \
+             so this change is lost when the bot restarts.",
+        );
+    } else if let Err(e) = saved {
+        tracing::error!(
+            "Failed to save the {} message of {guild_id}, the change only lasts until a \
+             restart: {e:?}",
+            key.name()
+        );
+        content.push_str(
+            "\nSaving messages is temporarily unavailable, \
+             so this change is lost if the bot restarts before you make it again.",
+        );
+    }
+
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .allowed_mentions(AllowedMentions::default())
+        .content(content)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use twilight_model::{
+        application::interaction::Interaction,
+        id::{marker::GuildMarker, Id},
+    };
+
+    use super::*;
+    use crate::{interact::handle_interaction, messages::Languages, testing};
+
+    fn set_message_command(key: &str, value: Option<&str>) -> Interaction {
+        let mut options = vec![json!({ "name": "key", "type": 3, "value": key })];
+        if let Some(value) = value {
+            options.push(json!({ "name": "value", "type": 3, "value": value }));
+        }
+        testing::command("set-message", &json!(options))
+    }
+
+    fn form_title(state: &AppState, guild: Option<Id<GuildMarker>>) -> String {
+        state.messages.get(guild, None).form_title.clone()
+    }
+
+    #[test]
+    fn changes_take_effect_immediately() {
+        testing::runtime().block_on(async {
+            let state = testing::offline_state();
+            let response = Box::pin(handle_interaction(
+                state.clone(),
+                set_message_command("form_title", Some("Talk to the mods")),
+            ))
+            .await;
+            let content = testing::content(&response).unwrap();
+            assert!(
+                content.starts_with("Changed the `form_title` message in this server."),
+                "{content}"
+            );
+
+            let response = Box::pin(handle_interaction(
+                state.clone(),
+                testing::button("open_form:60"),
+            ))
+            .await;
+            assert_eq!(
+                response.data.unwrap().title.as_deref(),
+                Some("Talk to the mods")
+            );
+            // Only in this server.
+            assert_eq!(form_title(&state, Some(Id::new(11))), "ModMail Form");
+
+            Box::pin(handle_interaction(
+                state.clone(),
+                set_message_command("form_title", None),
+            ))
+            .await;
+            assert_eq!(
+                form_title(&state, Some(Id::new(testing::GUILD))),
+                "ModMail Form"
+            );
+        });
+    }
+
+    #[test]
+    fn rejects_messages_which_dont_fit() {
+        testing::runtime().block_on(async {
+            let state = testing::offline_state();
+            let title = "x".repeat(46);
+            let response = niloecl::make_handler(set_message)(
+                set_message_command("form_title", Some(&title)),
+                state.clone(),
+            )
+            .await;
+            assert_eq!(
+                testing::error_message(&response),
+                Some("That message can be at most 45 characters long")
+            );
+            assert_eq!(
+                form_title(&state, Some(Id::new(testing::GUILD))),
+                "ModMail Form"
+            );
+        });
+    }
+
+    #[test]
+    fn changes_are_saved_in_the_store() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let state = AppState {
+                store: Some(store.clone()),
+                ..testing::offline_state()
+            };
+            let handler = niloecl::make_handler(set_message);
+            handler(
+                set_message_command("report_received", Some("Got it!")),
+                state.clone(),
+            )
+            .await;
+
+            let reloaded = Languages::default();
+            reloaded.load_overrides(&store).await.unwrap();
+            let guild = Some(Id::new(testing::GUILD));
+            assert_eq!(reloaded.get(guild, None).report_received, "Got it!");
+
+            handler(set_message_command("report_received", None), state).await;
+            let reloaded = Languages::default();
+            reloaded.load_overrides(&store).await.unwrap();
+            assert!(reloaded
+                .get(guild, None)
+                .report_received
+                .starts_with("Thanks for making a report."));
+        });
+    }
+
+    #[test]
+    fn changes_are_kept_in_memory_while_the_store_is_down() {
+        testing::runtime().block_on(async {
+            let state = AppState {
+                store: Some(testing::broken_store().await),
+                ..testing::offline_state()
+            };
+            let response = niloecl::make_handler(set_message)(
+                set_message_command("form_title", Some("Report")),
+                state.clone(),
+            )
+            .await;
+            let content = testing::content(&response).unwrap();
+            assert!(content.contains("temporarily unavailable"), "{content}");
+            assert_eq!(form_title(&state, Some(Id::new(testing::GUILD))), "Report");
+        });
+    }
+}
@@ -0,0 +1,877 @@
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool},
+    FromRow,
+};
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, MessageMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    backup::{Cell, Table},
+    interact::ModmailFormModal,
+};
+
+/// Reports posted since the store was created, the servers' blocklists,
+/// report counters, changed messages and features, and which reports are
+/// still open, kept in the database at `AGHAST_DB_PATH` so they survive
+/// restarts.
+#[derive(Debug, Clone)]
+pub struct Store(SqlitePool);
+
+/// A report as it was submitted, as far as its reporter gets to see it. The
+/// database has no unsigned integers, so IDs are stored as the same bits in a
+/// signed one.
+#[derive(Debug, FromRow)]
+pub struct StoredReport {
+    target_channel: i64,
+    pub user: Option<String>,
+    pub message_link: Option<String>,
+    pub channel: Option<String>,
+    pub reason: String,
+    /// Unix time in seconds.
+    pub created_at: i64,
+}
+
+impl StoredReport {
+    pub const fn target_channel(&self) -> Id<ChannelMarker> {
+        Id::new(self.target_channel.cast_unsigned())
+    }
+}
+
+/// A report waiting in the outbox to be posted, see [`crate::outbox`].
+#[derive(Debug, FromRow)]
+pub struct OutboxEntry {
+    pub id: i64,
+    guild: i64,
+    reporter: i64,
+    target_channel: i64,
+    /// The [`ModmailFormModal`] as JSON.
+    pub report: String,
+    /// How often posting it failed already.
+    pub attempts: i64,
+}
+
+impl OutboxEntry {
+    pub const fn guild(&self) -> Id<GuildMarker> {
+        Id::new(self.guild.cast_unsigned())
+    }
+
+    pub const fn reporter(&self) -> Id<UserMarker> {
+        Id::new(self.reporter.cast_unsigned())
+    }
+
+    pub const fn target_channel(&self) -> Id<ChannelMarker> {
+        Id::new(self.target_channel.cast_unsigned())
+    }
+}
+
+/// A report which is escalated if it's still open when it's due, see
+/// [`crate::escalate`].
+#[derive(Debug, FromRow)]
+pub struct OpenReport {
+    channel: i64,
+    message: i64,
+    target_channel: i64,
+}
+
+impl OpenReport {
+    /// Where the report was posted, which is a thread for forum posts.
+    pub const fn channel(&self) -> Id<ChannelMarker> {
+        Id::new(self.channel.cast_unsigned())
+    }
+
+    pub const fn message(&self) -> Id<MessageMarker> {
+        Id::new(self.message.cast_unsigned())
+    }
+
+    /// The modmail channel of the setup the report was sent through.
+    pub const fn target_channel(&self) -> Id<ChannelMarker> {
+        Id::new(self.target_channel.cast_unsigned())
+    }
+}
+
+/// The current Unix time in seconds, as the store keeps times.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Run on every startup, so it has to be safe to run again.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    reporter INTEGER NOT NULL,
+    target_channel INTEGER NOT NULL,
+    user TEXT,
+    message_link TEXT,
+    channel TEXT,
+    reason TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS reports_by_reporter ON reports (reporter);
+CREATE TABLE IF NOT EXISTS blocked_users (
+    guild INTEGER NOT NULL,
+    user INTEGER NOT NULL,
+    PRIMARY KEY (guild, user)
+);
+CREATE TABLE IF NOT EXISTS report_numbers (
+    guild INTEGER PRIMARY KEY,
+    last INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS outbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild INTEGER NOT NULL,
+    reporter INTEGER NOT NULL,
+    target_channel INTEGER NOT NULL,
+    report TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS message_overrides (
+    guild INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (guild, key)
+);
+CREATE TABLE IF NOT EXISTS feature_flags (
+    guild INTEGER NOT NULL,
+    feature TEXT NOT NULL,
+    enabled INTEGER NOT NULL,
+    PRIMARY KEY (guild, feature)
+);
+CREATE TABLE IF NOT EXISTS open_reports (
+    channel INTEGER NOT NULL,
+    message INTEGER NOT NULL,
+    target_channel INTEGER NOT NULL,
+    due INTEGER NOT NULL,
+    PRIMARY KEY (channel, message)
+);
+CREATE TABLE IF NOT EXISTS cooldowns (
+    guild INTEGER PRIMARY KEY,
+    seconds INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS rotations (
+    channel INTEGER PRIMARY KEY,
+    turn INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS assignments (
+    channel INTEGER NOT NULL,
+    message INTEGER NOT NULL,
+    moderator INTEGER NOT NULL,
+    PRIMARY KEY (channel, message)
+);
+CREATE TABLE IF NOT EXISTS unavailable_moderators (
+    guild INTEGER NOT NULL,
+    user INTEGER NOT NULL,
+    PRIMARY KEY (guild, user)
+);
+";
+
+impl Store {
+    /// Open the database at `path`, creating it and its tables if needed.
+    pub async fn open(path: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(path)?.create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await?;
+        sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+        Ok(Self(pool))
+    }
+
+    pub async fn record_report(
+        &self,
+        reporter: Id<UserMarker>,
+        target_channel: Id<ChannelMarker>,
+        report: &ModmailFormModal,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO reports \
+             (reporter, target_channel, user, message_link, channel, reason, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(reporter.get().cast_signed())
+        .bind(target_channel.get().cast_signed())
+        .bind(&report.user)
+        .bind(&report.message_link)
+        .bind(&report.channel)
+        .bind(&report.reason)
+        .bind(now().cast_signed())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Take the next number of `guild`'s reports, starting at 1. The counter
+    /// is incremented and read in a single autocommitted statement, so
+    /// concurrent reports never get the same number, and nothing stays locked
+    /// while the report is posted. Numbers of reports which fail to post
+    /// aren't given back, which leaves a gap.
+    pub async fn next_report_number(&self, guild: Id<GuildMarker>) -> Result<u64, sqlx::Error> {
+        let (number,): (i64,) = sqlx::query_as(
+            "INSERT INTO report_numbers (guild, last) VALUES (?, 1) \
+             ON CONFLICT (guild) DO UPDATE SET last = last + 1 \
+             RETURNING last",
+        )
+        .bind(guild.get().cast_signed())
+        .fetch_one(&self.0)
+        .await?;
+        Ok(number.cast_unsigned())
+    }
+
+    /// Keep a report until [`Self::remove_from_outbox`] is called, returning
+    /// its ID in the outbox. It's due to be posted by the outbox at
+    /// `next_attempt`, in Unix seconds.
+    pub async fn add_to_outbox(
+        &self,
+        guild: Id<GuildMarker>,
+        reporter: Id<UserMarker>,
+        target_channel: Id<ChannelMarker>,
+        report: &ModmailFormModal,
+        next_attempt: u64,
+    ) -> Result<i64, sqlx::Error> {
+        let report = serde_json::to_string(report).map_err(|e| sqlx::Error::Encode(e.into()))?;
+        let (id,): (i64,) = sqlx::query_as(
+            "INSERT INTO outbox (guild, reporter, target_channel, report, next_attempt) \
+             VALUES (?, ?, ?, ?, ?) RETURNING id",
+        )
+        .bind(guild.get().cast_signed())
+        .bind(reporter.get().cast_signed())
+        .bind(target_channel.get().cast_signed())
+        .bind(report)
+        .bind(next_attempt.cast_signed())
+        .fetch_one(&self.0)
+        .await?;
+        Ok(id)
+    }
+
+    /// Reports in the outbox which are due at `now`, oldest first.
+    pub async fn due_in_outbox(&self, now: u64) -> Result<Vec<OutboxEntry>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, guild, reporter, target_channel, report, attempts FROM outbox \
+             WHERE next_attempt <= ? ORDER BY id",
+        )
+        .bind(now.cast_signed())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Count a failed attempt at posting a report from the outbox, and try
+    /// again at `next_attempt`.
+    pub async fn retry_from_outbox(&self, id: i64, next_attempt: u64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE outbox SET attempts = attempts + 1, next_attempt = ? WHERE id = ?")
+            .bind(next_attempt.cast_signed())
+            .bind(id)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Forget a report in the outbox, once it's posted or given up on.
+    pub async fn remove_from_outbox(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM outbox WHERE id = ?")
+            .bind(id)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Escalate the report `message` in `channel` at `due`, in Unix seconds,
+    /// unless [`Self::untrack_report`] is called before.
+    pub async fn track_report(
+        &self,
+        channel: Id<ChannelMarker>,
+        message: Id<MessageMarker>,
+        target_channel: Id<ChannelMarker>,
+        due: u64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO open_reports (channel, message, target_channel, due) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(channel.get().cast_signed())
+        .bind(message.get().cast_signed())
+        .bind(target_channel.get().cast_signed())
+        .bind(due.cast_signed())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Tracked reports which are due at `now`.
+    pub async fn overdue_reports(&self, now: u64) -> Result<Vec<OpenReport>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT channel, message, target_channel FROM open_reports WHERE due <= ? \
+             ORDER BY due",
+        )
+        .bind(now.cast_signed())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    pub async fn untrack_report(
+        &self,
+        channel: Id<ChannelMarker>,
+        message: Id<MessageMarker>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM open_reports WHERE channel = ? AND message = ?")
+            .bind(channel.get().cast_signed())
+            .bind(message.get().cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Take the next turn of the assignment rotation of the modmail channel
+    /// `channel`, counting up from 0. Like report numbers, every turn is
+    /// only taken once.
+    pub async fn next_turn(&self, channel: Id<ChannelMarker>) -> Result<u64, sqlx::Error> {
+        let (turn,): (i64,) = sqlx::query_as(
+            "INSERT INTO rotations (channel, turn) VALUES (?, 0) \
+             ON CONFLICT (channel) DO UPDATE SET turn = turn + 1 \
+             RETURNING turn",
+        )
+        .bind(channel.get().cast_signed())
+        .fetch_one(&self.0)
+        .await?;
+        Ok(turn.cast_unsigned())
+    }
+
+    /// Remember who the report `message` in `channel` is assigned to.
+    pub async fn assign_report(
+        &self,
+        channel: Id<ChannelMarker>,
+        message: Id<MessageMarker>,
+        moderator: Id<UserMarker>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO assignments (channel, message, moderator) VALUES (?, ?, ?) \
+             ON CONFLICT (channel, message) DO UPDATE SET moderator = excluded.moderator",
+        )
+        .bind(channel.get().cast_signed())
+        .bind(message.get().cast_signed())
+        .bind(moderator.get().cast_signed())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// The channel of the report `message`, and who it's assigned to, if
+    /// anyone. Message IDs are unique across channels.
+    pub async fn assignment(
+        &self,
+        message: Id<MessageMarker>,
+    ) -> Result<Option<(Id<ChannelMarker>, Id<UserMarker>)>, sqlx::Error> {
+        let row: Option<(i64, i64)> =
+            sqlx::query_as("SELECT channel, moderator FROM assignments WHERE message = ?")
+                .bind(message.get().cast_signed())
+                .fetch_optional(&self.0)
+                .await?;
+        Ok(row.map(|(channel, moderator)| {
+            (
+                Id::new(channel.cast_unsigned()),
+                Id::new(moderator.cast_unsigned()),
+            )
+        }))
+    }
+
+    pub async fn unassign_report(
+        &self,
+        channel: Id<ChannelMarker>,
+        message: Id<MessageMarker>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM assignments WHERE channel = ? AND message = ?")
+            .bind(channel.get().cast_signed())
+            .bind(message.get().cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Every user on the blocklist of any server.
+    pub async fn blocked_users(
+        &self,
+    ) -> Result<Vec<(Id<GuildMarker>, Id<UserMarker>)>, sqlx::Error> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as("SELECT guild, user FROM blocked_users")
+            .fetch_all(&self.0)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(guild, user)| {
+                (
+                    Id::new(guild.cast_unsigned()),
+                    Id::new(user.cast_unsigned()),
+                )
+            })
+            .collect())
+    }
+
+    pub async fn block_user(
+        &self,
+        guild: Id<GuildMarker>,
+        user: Id<UserMarker>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT OR IGNORE INTO blocked_users (guild, user) VALUES (?, ?)")
+            .bind(guild.get().cast_signed())
+            .bind(user.get().cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn unblock_user(
+        &self,
+        guild: Id<GuildMarker>,
+        user: Id<UserMarker>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM blocked_users WHERE guild = ? AND user = ?")
+            .bind(guild.get().cast_signed())
+            .bind(user.get().cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Unlink the reports `user` sent from them, returning how many there
+    /// were. The reports themselves are kept, with 0 as their reporter.
+    pub async fn forget_reporter(&self, user: Id<UserMarker>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE reports SET reporter = 0 WHERE reporter = ?")
+            .bind(user.get().cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Delete the reports created before `before`, in Unix seconds, returning
+    /// how many there were.
+    pub async fn purge_reports(&self, before: u64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM reports WHERE created_at < ?")
+            .bind(before.cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Every message any server changed with `/set-message`, by its key in
+    /// `[messages]`.
+    pub async fn message_overrides(
+        &self,
+    ) -> Result<Vec<(Id<GuildMarker>, String, String)>, sqlx::Error> {
+        let rows: Vec<(i64, String, String)> =
+            sqlx::query_as("SELECT guild, key, value FROM message_overrides")
+                .fetch_all(&self.0)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(guild, key, value)| (Id::new(guild.cast_unsigned()), key, value))
+            .collect())
+    }
+
+    pub async fn set_message(
+        &self,
+        guild: Id<GuildMarker>,
+        key: &str,
+        value: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO message_overrides (guild, key, value) VALUES (?, ?, ?) \
+             ON CONFLICT (guild, key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(guild.get().cast_signed())
+        .bind(key)
+        .bind(value)
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn reset_message(
+        &self,
+        guild: Id<GuildMarker>,
+        key: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM message_overrides WHERE guild = ? AND key = ?")
+            .bind(guild.get().cast_signed())
+            .bind(key)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Every feature any server turned on or off with `/features`, by its key
+    /// in `[form]`.
+    pub async fn feature_flags(&self) -> Result<Vec<(Id<GuildMarker>, String, bool)>, sqlx::Error> {
+        let rows: Vec<(i64, String, bool)> =
+            sqlx::query_as("SELECT guild, feature, enabled FROM feature_flags")
+                .fetch_all(&self.0)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(guild, feature, enabled)| (Id::new(guild.cast_unsigned()), feature, enabled))
+            .collect())
+    }
+
+    pub async fn set_feature(
+        &self,
+        guild: Id<GuildMarker>,
+        feature: &str,
+        enabled: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO feature_flags (guild, feature, enabled) VALUES (?, ?, ?) \
+             ON CONFLICT (guild, feature) DO UPDATE SET enabled = excluded.enabled",
+        )
+        .bind(guild.get().cast_signed())
+        .bind(feature)
+        .bind(enabled)
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn reset_feature(
+        &self,
+        guild: Id<GuildMarker>,
+        feature: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM feature_flags WHERE guild = ? AND feature = ?")
+            .bind(guild.get().cast_signed())
+            .bind(feature)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// The submit windows servers chose with `/cooldown`, in seconds.
+    pub async fn cooldowns(&self) -> Result<Vec<(Id<GuildMarker>, u64)>, sqlx::Error> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as("SELECT guild, seconds FROM cooldowns")
+            .fetch_all(&self.0)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(guild, secs)| (Id::new(guild.cast_unsigned()), secs.cast_unsigned()))
+            .collect())
+    }
+
+    pub async fn set_cooldown(&self, guild: Id<GuildMarker>, secs: u64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO cooldowns (guild, seconds) VALUES (?, ?) \
+             ON CONFLICT (guild) DO UPDATE SET seconds = excluded.seconds",
+        )
+        .bind(guild.get().cast_signed())
+        .bind(secs.cast_signed())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unavailable_moderators(
+        &self,
+    ) -> Result<Vec<(Id<GuildMarker>, Id<UserMarker>)>, sqlx::Error> {
+        let rows: Vec<(i64, i64)> =
+            sqlx::query_as("SELECT guild, user FROM unavailable_moderators")
+                .fetch_all(&self.0)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(guild, user)| {
+                (
+                    Id::new(guild.cast_unsigned()),
+                    Id::new(user.cast_unsigned()),
+                )
+            })
+            .collect())
+    }
+
+    pub async fn set_available(
+        &self,
+        guild: Id<GuildMarker>,
+        user: Id<UserMarker>,
+        available: bool,
+    ) -> Result<(), sqlx::Error> {
+        let query = if available {
+            "DELETE FROM unavailable_moderators WHERE guild = ? AND user = ?"
+        } else {
+            "INSERT OR IGNORE INTO unavailable_moderators (guild, user) VALUES (?, ?)"
+        };
+        sqlx::query(query)
+            .bind(guild.get().cast_signed())
+            .bind(user.get().cast_signed())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// The columns of every table, in order, for [`crate::backup`].
+    pub async fn schema(&self) -> Result<BTreeMap<String, Vec<String>>, sqlx::Error> {
+        let mut conn = self.0.acquire().await?;
+        schema(&mut conn).await
+    }
+
+    /// Every row of every table, all read in one transaction.
+    pub async fn dump(&self) -> Result<BTreeMap<String, Table>, sqlx::Error> {
+        let mut tx = self.0.begin().await?;
+        let mut tables = BTreeMap::new();
+        for (name, columns) in schema(&mut tx).await? {
+            // Names come from the schema itself, and SQLite turns each row
+            // into a JSON array of integers, text and nulls.
+            let values = columns
+                .iter()
+                .map(|column| format!("\"{column}\""))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let rows: Vec<(String,)> =
+                sqlx::query_as(&format!("SELECT json_array({values}) FROM \"{name}\""))
+                    .fetch_all(&mut *tx)
+                    .await?;
+            let rows = rows
+                .into_iter()
+                .map(|(row,)| serde_json::from_str(&row).map_err(|e| sqlx::Error::Decode(e.into())))
+                .collect::<Result<_, _>>()?;
+            tables.insert(name, Table { columns, rows });
+        }
+        tx.commit().await?;
+        Ok(tables)
+    }
+
+    /// Delete everything in the tables and insert `tables` instead, in one
+    /// transaction. Their names and columns have to be checked against
+    /// [`Self::schema`] first.
+    pub async fn replace_all(&self, tables: &BTreeMap<String, Table>) -> Result<(), sqlx::Error> {
+        let mut tx = self.0.begin().await?;
+        for (name, table) in tables {
+            sqlx::query(&format!("DELETE FROM \"{name}\""))
+                .execute(&mut *tx)
+                .await?;
+            let columns = table
+                .columns
+                .iter()
+                .map(|column| format!("\"{column}\""))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let params = vec!["?"; table.columns.len()].join(", ");
+            let insert = format!("INSERT INTO \"{name}\" ({columns}) VALUES ({params})");
+            for row in &table.rows {
+                let mut query = sqlx::query(&insert);
+                for value in row {
+                    query = match value {
+                        Some(Cell::Integer(value)) => query.bind(value),
+                        Some(Cell::Text(value)) => query.bind(value),
+                        None => query.bind(None::<i64>),
+                    };
+                }
+                query.execute(&mut *tx).await?;
+            }
+        }
+        tx.commit().await
+    }
+
+    /// Stop answering queries, like when the database is gone.
+    #[cfg(test)]
+    pub async fn close(&self) {
+        self.0.close().await;
+    }
+
+    /// Everything `user` reported, oldest first.
+    pub async fn reports_by_user(
+        &self,
+        user: Id<UserMarker>,
+    ) -> Result<Vec<StoredReport>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM reports WHERE reporter = ? ORDER BY id")
+            .bind(user.get().cast_signed())
+            .fetch_all(&self.0)
+            .await
+    }
+}
+
+/// The columns of every table on `conn`, leaving out the ones the database
+/// keeps for itself.
+async fn schema(conn: &mut SqliteConnection) -> Result<BTreeMap<String, Vec<String>>, sqlx::Error> {
+    let names: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut schema = BTreeMap::new();
+    for (name,) in names {
+        let columns: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+                .bind(&name)
+                .fetch_all(&mut *conn)
+                .await?;
+        schema.insert(name, columns.into_iter().map(|(column,)| column).collect());
+    }
+    Ok(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use tokio::task::JoinSet;
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn numbers_reports_per_server_without_duplicates() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let mut tasks = JoinSet::new();
+            for i in 0..20 {
+                let store = store.clone();
+                let guild = Id::new(10 + i % 2);
+                tasks.spawn(async move { (guild, store.next_report_number(guild).await.unwrap()) });
+            }
+            let mut numbers: [BTreeSet<u64>; 2] = Default::default();
+            while let Some(joined) = tasks.join_next().await {
+                let (guild, number) = joined.unwrap();
+                let guild = usize::try_from(guild.get() - 10).unwrap();
+                assert!(numbers[guild].insert(number), "{number} was taken twice");
+            }
+            let expected: BTreeSet<u64> = (1..=10).collect();
+            assert_eq!(numbers, [expected.clone(), expected]);
+        });
+    }
+
+    #[test]
+    fn doesnt_lock_the_store_while_a_number_is_used() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let guild = Id::new(testing::GUILD);
+            assert_eq!(store.next_report_number(guild).await.unwrap(), 1);
+            // Other writes would wait for a transaction that was still open.
+            tokio::time::timeout(
+                std::time::Duration::from_secs(1),
+                store.block_user(guild, Id::new(30)),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            assert_eq!(store.next_report_number(guild).await.unwrap(), 2);
+        });
+    }
+
+    fn report(reason: &str) -> ModmailFormModal {
+        ModmailFormModal {
+            user: Some("wumpus".to_string()),
+            message_link: Some("https://discord.com/channels/10/20/30".to_string()),
+            channel: None,
+            reason: reason.to_string(),
+            custom: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn finds_reports_by_their_reporter() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let (reporter, other) = (Id::new(30), Id::new(31));
+            let channel = Id::new(60);
+            let before = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            store
+                .record_report(reporter, channel, &report("first"))
+                .await
+                .unwrap();
+            store
+                .record_report(other, channel, &report("someone else's"))
+                .await
+                .unwrap();
+            store
+                .record_report(reporter, channel, &report("second"))
+                .await
+                .unwrap();
+
+            let reports = store.reports_by_user(reporter).await.unwrap();
+            let reasons: Vec<_> = reports.iter().map(|r| r.reason.as_str()).collect();
+            assert_eq!(reasons, ["first", "second"]);
+            let first = &reports[0];
+            assert_eq!(first.target_channel(), channel);
+            assert_eq!(first.user.as_deref(), Some("wumpus"));
+            assert_eq!(
+                first.message_link.as_deref(),
+                Some("https://discord.com/channels/10/20/30")
+            );
+            assert_eq!(first.channel, None);
+            assert!(first.created_at.cast_unsigned() >= before);
+
+            assert!(store.reports_by_user(Id::new(32)).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn purges_old_reports_and_keeps_new_ones() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let (reporter, channel) = (Id::new(30), Id::new(60));
+            for reason in ["old", "new"] {
+                store
+                    .record_report(reporter, channel, &report(reason))
+                    .await
+                    .unwrap();
+            }
+            sqlx::query("UPDATE reports SET created_at = 1000 WHERE reason = 'old'")
+                .execute(&store.0)
+                .await
+                .unwrap();
+
+            assert_eq!(store.purge_reports(2000).await.unwrap(), 1);
+            let reports = store.reports_by_user(reporter).await.unwrap();
+            let reasons: Vec<_> = reports.iter().map(|r| r.reason.as_str()).collect();
+            assert_eq!(reasons, ["new"]);
+        });
+    }
+
+    #[test]
+    fn forgets_reporters_but_keeps_their_reports() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let (reporter, other) = (Id::new(30), Id::new(31));
+            let channel = Id::new(60);
+            for user in [reporter, reporter, other] {
+                store
+                    .record_report(user, channel, &report("Being rude"))
+                    .await
+                    .unwrap();
+            }
+
+            assert_eq!(store.forget_reporter(reporter).await.unwrap(), 2);
+            assert!(store.reports_by_user(reporter).await.unwrap().is_empty());
+            assert_eq!(store.reports_by_user(other).await.unwrap().len(), 1);
+            let forgotten: Vec<StoredReport> =
+                sqlx::query_as("SELECT * FROM reports WHERE reporter = 0")
+                    .fetch_all(&store.0)
+                    .await
+                    .unwrap();
+            assert_eq!(forgotten.len(), 2);
+            assert_eq!(forgotten[0].reason, "Being rude");
+
+            assert_eq!(store.forget_reporter(reporter).await.unwrap(), 0);
+        });
+    }
+
+    #[test]
+    fn keeps_ids_which_dont_fit_in_a_signed_integer() {
+        testing::runtime().block_on(async {
+            let store = testing::store().await;
+            let reporter = Id::new(u64::MAX);
+            let channel = Id::new(1 << 63);
+            store
+                .record_report(reporter, channel, &report("big"))
+                .await
+                .unwrap();
+            let reports = store.reports_by_user(reporter).await.unwrap();
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].target_channel(), channel);
+        });
+    }
+}
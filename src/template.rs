@@ -0,0 +1,55 @@
+/// The `{name}` placeholders in `template`, in order.
+pub fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    Parts(template).filter_map(|part| match part {
+        Part::Text(_) => None,
+        Part::Placeholder(name) => Some(name),
+    })
+}
+
+/// Replace every placeholder with its value. Placeholders without a value are
+/// left out.
+pub fn render<'a>(template: &'a str, value: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut out = String::with_capacity(template.len());
+    for part in Parts(template) {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Placeholder(name) => out.push_str(value(name).unwrap_or_default()),
+        }
+    }
+    out
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits a template into text and placeholders. A `{` without a matching
+/// `}` is just text.
+struct Parts<'a>(&'a str);
+
+impl<'a> Iterator for Parts<'a> {
+    type Item = Part<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let placeholder = self.0.strip_prefix('{').and_then(|rest| {
+            let end = rest.find('}')?;
+            let name = &rest[..end];
+            name.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+                .then_some((name, &rest[end + 1..]))
+        });
+        if let Some((name, rest)) = placeholder {
+            self.0 = rest;
+            return Some(Part::Placeholder(name));
+        }
+        // Always take at least one character, so a lone `{` moves on.
+        let end = self.0[1..].find('{').map_or(self.0.len(), |i| i + 1);
+        let (text, rest) = self.0.split_at(end);
+        self.0 = rest;
+        Some(Part::Text(text))
+    }
+}
@@ -0,0 +1,52 @@
+use niloecl::State;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    extract::{ExtractUserId, SlashCommand},
+    interact::{api_error_code, InteractError, CANNOT_MESSAGE_USER},
+    AppState,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "testdm", desc = "Check whether I can send you direct messages")]
+pub struct TestDmCommand;
+
+pub async fn testdm(
+    State(state): State<AppState>,
+    ExtractUserId(user_id): ExtractUserId,
+    SlashCommand(TestDmCommand): SlashCommand<TestDmCommand>,
+) -> Result<InteractionResponse, InteractError> {
+    let channel = state
+        .client
+        .create_private_channel(user_id)
+        .await?
+        .model()
+        .await?;
+    let result = state
+        .client
+        .create_message(channel.id)
+        .content("This is a test message. Your DMs are open to me!")
+        .await;
+
+    let content = match result {
+        Ok(_) => "Sent you a DM, so your DMs are open.",
+        Err(e) if api_error_code(&e) == Some(CANNOT_MESSAGE_USER) => {
+            "Your DMs appear to be closed. Allow direct messages from server members in \
+             this server's privacy settings, and make sure you haven't blocked me."
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let data = InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .content(content)
+        .build();
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data),
+    })
+}
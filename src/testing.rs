@@ -0,0 +1,339 @@
+//! Helpers for tests which need an [`AppState`], interactions, or something
+//! pretending to be Discord's API.
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{Method, StatusCode, Uri},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use twilight_http::Client;
+use twilight_model::{
+    application::interaction::Interaction,
+    guild::Permissions,
+    http::interaction::InteractionResponse,
+    id::{
+        marker::{ChannelMarker, GuildMarker},
+        Id,
+    },
+};
+
+use crate::{
+    config::{Config, EntryPoints, FormConfig, Forms, SetupConfig},
+    ratelimit::SubmitLimits,
+    store::Store,
+    AppState,
+};
+
+/// The guild test interactions come from.
+pub const GUILD: u64 = 10;
+/// The channel test interactions are used in.
+pub const CHANNEL: u64 = 20;
+
+/// A runtime for a single test, since tokio's test macros aren't enabled.
+pub fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// A request the bot sent to [`MockDiscord`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    /// Without the `/api/v10` prefix, like `/channels/20/messages`.
+    pub path: String,
+    /// `Null` for requests without a JSON body.
+    pub body: Value,
+}
+
+type Respond = dyn Fn(&Request) -> (StatusCode, Value) + Send + Sync;
+
+#[derive(Clone)]
+struct Mock {
+    requests: Arc<Mutex<Vec<Request>>>,
+    respond: Arc<Respond>,
+}
+
+/// Answers the bot's API requests like Discord would, and remembers them.
+pub struct MockDiscord {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockDiscord {
+    /// Start answering requests on a free port. Has to be called inside the
+    /// runtime the test runs on.
+    pub async fn start(
+        respond: impl Fn(&Request) -> (StatusCode, Value) + Send + Sync + 'static,
+    ) -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let mock = Mock {
+            requests: requests.clone(),
+            respond: Arc::new(respond),
+        };
+        let router = Router::new().fallback(record).with_state(mock);
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = tcp.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(tcp, router).await });
+        Self { addr, requests }
+    }
+
+    pub fn client(&self) -> Client {
+        client(&self.addr.to_string())
+    }
+
+    pub fn state(&self) -> AppState {
+        state(self.client())
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Requests with this method to paths starting with `path`.
+    pub fn requests_to(&self, method: &Method, path: &str) -> Vec<Request> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.method == *method && request.path.starts_with(path))
+            .collect()
+    }
+}
+
+async fn record(
+    State(mock): State<Mock>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    let request = Request {
+        method,
+        path: uri.path().trim_start_matches("/api/v10").to_string(),
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    };
+    let (status, response) = (mock.respond)(&request);
+    mock.requests.lock().unwrap().push(request);
+    (status, Json(response))
+}
+
+/// A client which sends everything to `host` over plain HTTP.
+fn client(host: &str) -> Client {
+    Client::builder()
+        .proxy(host.to_string(), true)
+        .ratelimiter(None)
+        .timeout(Duration::from_secs(5))
+        .token("test".to_string())
+        .build()
+}
+
+fn state(client: Client) -> AppState {
+    AppState {
+        client: Arc::new(client),
+        application_id: Id::new(1),
+        bot_id: Id::new(2),
+        keys: Arc::new(ArcSwap::from_pointee(Vec::new())),
+        forms: Arc::default(),
+        required_permissions: Permissions::empty(),
+        messages: Arc::default(),
+        fallback_channel: None,
+        report_channels: Arc::default(),
+        pending_reports: Arc::default(),
+        brigades: Arc::default(),
+        ping_batches: Arc::default(),
+        open_forms: Arc::default(),
+        submit_limits: Arc::new(SubmitLimits::new(0, Duration::ZERO)),
+        resolve_permissions: Permissions::MANAGE_MESSAGES,
+        blocklist: Arc::default(),
+        features: Arc::default(),
+        availability: Arc::default(),
+        metrics: Arc::default(),
+        plugin: None,
+        audit: None,
+        store: None,
+        report_queue: None,
+        early_defer: false,
+        dev: false,
+    }
+}
+
+/// State for handlers which don't talk to Discord. Requests fail if they try.
+pub fn offline_state() -> AppState {
+    state(client("127.0.0.1:9"))
+}
+
+/// Form settings for reports to `modmail_channel`, as if set up in the config
+/// file.
+pub fn forms(modmail_channel: Id<ChannelMarker>, form: FormConfig) -> Arc<Forms> {
+    Arc::new(Forms::new(&config(modmail_channel, form)))
+}
+
+/// A config file with a single setup in [`CHANNEL`].
+pub fn config(modmail_channel: Id<ChannelMarker>, form: FormConfig) -> Config {
+    Config {
+        setups: vec![SetupConfig {
+            message: "Report".to_string(),
+            select_placeholder: "User".to_string(),
+            button_msg: "Report".to_string(),
+            button_emoji: None,
+            button_channel: Id::new(CHANNEL),
+            modmail_channel,
+            pin: false,
+            entry_points: EntryPoints::Both,
+            required_role: None,
+            form,
+        }],
+        ..Config::default()
+    }
+}
+
+/// An interaction from [`member`] in [`GUILD`], with `fields` added or
+/// replaced.
+pub fn interaction(fields: Value) -> Interaction {
+    let mut interaction = json!({
+        "id": "100",
+        "application_id": "1",
+        "type": 1,
+        "token": "token",
+        "version": 1,
+        "authorizing_integration_owners": {},
+        "guild_id": GUILD.to_string(),
+        "channel_id": CHANNEL.to_string(),
+        "channel": channel(CHANNEL, Some(Id::new(GUILD)), 0),
+        "member": member(30, &[]),
+    });
+    if let (Value::Object(interaction), Value::Object(fields)) = (&mut interaction, fields) {
+        interaction.extend(fields);
+    }
+    serde_json::from_value(interaction).unwrap()
+}
+
+/// A slash command with these options.
+pub fn command(name: &str, options: &Value) -> Interaction {
+    interaction(json!({
+        "type": 2,
+        "data": { "id": "50", "name": name, "type": 1, "options": options },
+    }))
+}
+
+/// A click on a button with this custom ID.
+pub fn button(custom_id: &str) -> Interaction {
+    interaction(json!({
+        "type": 3,
+        "data": { "custom_id": custom_id, "component_type": 2 },
+    }))
+}
+
+/// A submitted modal, with the values of its text inputs by custom ID.
+pub fn modal(custom_id: &str, values: &[(&str, &str)]) -> Interaction {
+    let rows: Vec<_> = values
+        .iter()
+        .map(|(id, value)| {
+            json!({
+                "type": 1,
+                "components": [{ "type": 4, "custom_id": id, "value": value }],
+            })
+        })
+        .collect();
+    interaction(json!({
+        "type": 5,
+        "data": { "custom_id": custom_id, "components": rows },
+    }))
+}
+
+/// A server member with these roles and every permission.
+pub fn member(user: u64, roles: &[u64]) -> Value {
+    let roles: Vec<_> = roles.iter().map(ToString::to_string).collect();
+    json!({
+        "deaf": false,
+        "mute": false,
+        "flags": 0,
+        "joined_at": null,
+        "nick": null,
+        "roles": roles,
+        "permissions": Permissions::all().bits().to_string(),
+        "user": user_json(user),
+    })
+}
+
+pub fn user_json(id: u64) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": format!("user{id}"),
+        "discriminator": "0",
+        "avatar": null,
+    })
+}
+
+/// A message as Discord returns it after posting.
+pub fn message(channel: u64, id: u64) -> Value {
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel.to_string(),
+        "author": user_json(2),
+        "content": "",
+        "timestamp": "2025-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+/// A channel of `guild`, with a `kind` like 0 for text or 15 for forums.
+pub fn channel(id: u64, guild: Option<Id<GuildMarker>>, kind: u8) -> Value {
+    json!({
+        "id": id.to_string(),
+        "guild_id": guild.map(|guild| guild.to_string()),
+        "type": kind,
+        "name": "channel",
+    })
+}
+
+/// The message of an error response, see [`crate::interact::ErrorReport`].
+pub fn error_message(response: &InteractionResponse) -> Option<&str> {
+    response
+        .data
+        .as_ref()?
+        .embeds
+        .as_ref()?
+        .first()?
+        .description
+        .as_deref()
+}
+
+/// The text of a message response.
+pub fn content(response: &InteractionResponse) -> Option<&str> {
+    response.data.as_ref()?.content.as_deref()
+}
+
+/// A database only this test uses.
+pub async fn store() -> Store {
+    Store::open("sqlite::memory:").await.unwrap()
+}
+
+/// A store that fails every query.
+pub async fn broken_store() -> Store {
+    let store = store().await;
+    store.close().await;
+    store
+}
+
+/// A JSON error like Discord sends them, with one of its error codes.
+pub fn api_error(status: StatusCode, code: u64) -> (StatusCode, Value) {
+    (status, json!({ "code": code, "message": "error" }))
+}
@@ -0,0 +1,306 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use arc_swap::ArcSwap;
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{metrics::Metrics, RequestError};
+
+/// Keys requests may be signed with, swapped out at runtime by
+/// `/refresh-appinfo`.
+pub type Keys = Arc<ArcSwap<Vec<VerifyingKey>>>;
+
+/// Rejects requests which aren't signed by Discord before the wrapped
+/// handler runs, so handlers only ever see verified bodies.
+#[derive(Clone)]
+pub struct SignatureVerificationLayer {
+    keys: Keys,
+    /// How far the signed timestamp may be from now, so captured requests
+    /// can't be replayed later.
+    tolerance_secs: u64,
+    metrics: Arc<Metrics>,
+}
+
+impl SignatureVerificationLayer {
+    pub const fn new(keys: Keys, tolerance_secs: u64, metrics: Arc<Metrics>) -> Self {
+        Self {
+            keys,
+            tolerance_secs,
+            metrics,
+        }
+    }
+}
+
+impl<S> Layer<S> for SignatureVerificationLayer {
+    type Service = SignatureVerification<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SignatureVerification {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SignatureVerification<S> {
+    inner: S,
+    layer: SignatureVerificationLayer,
+}
+
+/// Interactions are a few kilobytes at most, this is axum's default limit.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+type ResponseFuture = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+impl<S> Service<Request> for SignatureVerification<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Error = Infallible;
+    type Future = ResponseFuture;
+    type Response = Response;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // The clone isn't necessarily ready, so keep it and call the one
+        // poll_ready was called on.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let Ok(body) = axum::body::to_bytes(body, MAX_BODY_BYTES).await else {
+                return Ok(RequestError::BadBody.into_response());
+            };
+            if let Err(e) = verify(&layer, &parts.headers, &body) {
+                layer.metrics.signature_failure();
+                return Ok(e.into_response());
+            }
+            inner
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await
+        })
+    }
+}
+
+fn verify(
+    layer: &SignatureVerificationLayer,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Result<(), RequestError> {
+    // Extract the timestamp header for use later to check the signature.
+    let timestamp = headers
+        .get("x-signature-timestamp")
+        .ok_or(RequestError::BadSignature)?;
+
+    // Extract the signature to check against.
+    let signature: Signature = headers
+        .get("x-signature-ed25519")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(RequestError::BadSignature)?
+        .parse()
+        .map_err(|_| RequestError::BadSignature)?;
+
+    let whole_body = [timestamp.as_bytes(), body].concat();
+
+    let keys = layer.keys.load();
+    if !keys
+        .iter()
+        .any(|key| key.verify(&whole_body, &signature).is_ok())
+    {
+        return Err(RequestError::BadSignature);
+    }
+
+    // Only checked once the request is known to come from Discord, so the
+    // response doesn't tell anyone else what our clock says.
+    let timestamp: u64 = timestamp
+        .to_str()
+        .ok()
+        .and_then(|timestamp| timestamp.parse().ok())
+        .ok_or(RequestError::BadSignature)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if !is_fresh(timestamp, now, layer.tolerance_secs) {
+        return Err(RequestError::StaleTimestamp);
+    }
+    Ok(())
+}
+
+/// Whether `timestamp` is at most `tolerance_secs` before or after `now`.
+/// Discord's clock may be a little ahead of ours, so both are allowed.
+const fn is_fresh(timestamp: u64, now: u64, tolerance_secs: u64) -> bool {
+    now.abs_diff(timestamp) <= tolerance_secs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{
+        http::{HeaderValue, StatusCode},
+        routing::post,
+        Router,
+    };
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+    use crate::testing;
+
+    const BODY: &[u8] = br#"{"type":1}"#;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    /// Headers like Discord sends them, signing `body` at `timestamp`.
+    fn signed(key: &SigningKey, timestamp: u64, body: &[u8]) -> HeaderMap {
+        let timestamp = timestamp.to_string();
+        let signature = key.sign(&[timestamp.as_bytes(), body].concat());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-signature-ed25519",
+            HeaderValue::from_str(&hex::encode(signature.to_bytes())).unwrap(),
+        );
+        headers.insert(
+            "x-signature-timestamp",
+            HeaderValue::from_str(&timestamp).unwrap(),
+        );
+        headers
+    }
+
+    fn layer(keys: &[&SigningKey], tolerance_secs: u64) -> SignatureVerificationLayer {
+        let keys = keys.iter().map(|key| key.verifying_key()).collect();
+        SignatureVerificationLayer::new(
+            Arc::new(ArcSwap::from_pointee(keys)),
+            tolerance_secs,
+            Arc::default(),
+        )
+    }
+
+    #[test]
+    fn uses_keys_swapped_in_while_running() {
+        let old = signing_key(1);
+        let new = signing_key(2);
+        let layer = layer(&[&old], 5);
+        let body = Bytes::from_static(BODY);
+        let from_old = signed(&old, now(), BODY);
+        let from_new = signed(&new, now(), BODY);
+        assert!(verify(&layer, &from_old, &body).is_ok());
+        assert!(matches!(
+            verify(&layer, &from_new, &body),
+            Err(RequestError::BadSignature)
+        ));
+
+        // While rotating, both are accepted.
+        layer
+            .keys
+            .store(Arc::new(vec![old.verifying_key(), new.verifying_key()]));
+        assert!(verify(&layer, &from_old, &body).is_ok());
+        assert!(verify(&layer, &from_new, &body).is_ok());
+
+        layer.keys.store(Arc::new(vec![new.verifying_key()]));
+        assert!(matches!(
+            verify(&layer, &from_old, &body),
+            Err(RequestError::BadSignature)
+        ));
+        assert!(verify(&layer, &from_new, &body).is_ok());
+    }
+
+    #[test]
+    fn accepts_timestamps_within_the_tolerance() {
+        assert!(is_fresh(1000, 1000, 5));
+        assert!(is_fresh(995, 1000, 5));
+        assert!(is_fresh(1005, 1000, 5));
+        assert!(!is_fresh(994, 1000, 5));
+        assert!(!is_fresh(1006, 1000, 5));
+    }
+
+    #[test]
+    fn zero_tolerance_only_accepts_the_current_second() {
+        assert!(is_fresh(1000, 1000, 0));
+        assert!(!is_fresh(999, 1000, 0));
+        assert!(!is_fresh(1001, 1000, 0));
+    }
+
+    #[test]
+    fn rejects_expired_and_future_timestamps() {
+        let key = signing_key(1);
+        let layer = layer(&[&key], 5);
+        let body = Bytes::from_static(BODY);
+        assert!(verify(&layer, &signed(&key, now(), BODY), &body).is_ok());
+        for timestamp in [now() - 60, now() + 60] {
+            assert!(matches!(
+                verify(&layer, &signed(&key, timestamp, BODY), &body),
+                Err(RequestError::StaleTimestamp)
+            ));
+        }
+    }
+
+    #[test]
+    fn checks_the_signature_before_the_timestamp() {
+        let layer = layer(&[&signing_key(1)], 5);
+        let expired = signed(&signing_key(2), now() - 60, BODY);
+        assert!(matches!(
+            verify(&layer, &expired, &Bytes::from_static(BODY)),
+            Err(RequestError::BadSignature)
+        ));
+    }
+
+    #[test]
+    fn only_passes_on_signed_requests() {
+        testing::runtime().block_on(async {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let handler = {
+                let calls = calls.clone();
+                move || async move {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    "handled"
+                }
+            };
+            let key = signing_key(1);
+            let mut service = layer(&[&key], 5).layer(Router::new().route("/", post(handler)));
+            // Always signed for BODY, so anything else was tampered with.
+            let request = |body: &'static [u8]| {
+                let mut request = Request::post("/").body(Body::from(body)).unwrap();
+                *request.headers_mut() = signed(&key, now(), BODY);
+                request
+            };
+
+            let response = service.call(request(BODY)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+            let response = service.call(request(br#"{"type":2}"#)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(calls.load(Ordering::Relaxed), 1);
+        });
+    }
+}
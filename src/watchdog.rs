@@ -0,0 +1,52 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::runtime::Runtime;
+
+/// Warn when the runtime stops making progress for longer than `threshold`.
+///
+/// A task on the runtime records a heartbeat, and a plain thread checks it.
+/// On the default single-threaded runtime, a single blocking call anywhere
+/// stalls every interaction; this at least makes that visible in the logs.
+pub fn start(rt: &Runtime, threshold: Duration) {
+    let start = Instant::now();
+    let heartbeat = Arc::new(AtomicU64::new(0));
+    let beat_every = threshold / 4;
+
+    let beat = heartbeat.clone();
+    rt.spawn(async move {
+        let mut interval = tokio::time::interval(beat_every);
+        loop {
+            interval.tick().await;
+            beat.store(millis_since(start), Ordering::Relaxed);
+        }
+    });
+
+    std::thread::Builder::new()
+        .name("aghast-watchdog".to_string())
+        .spawn(move || {
+            let mut stalled = false;
+            loop {
+                std::thread::sleep(beat_every);
+                let last = heartbeat.load(Ordering::Relaxed);
+                let silent = Duration::from_millis(millis_since(start).saturating_sub(last));
+                if silent > threshold && !stalled {
+                    tracing::warn!("Event loop has not made progress for {silent:.1?}");
+                    stalled = true;
+                } else if silent <= threshold && stalled {
+                    tracing::warn!("Event loop recovered");
+                    stalled = false;
+                }
+            }
+        })
+        .expect("Failed to start watchdog thread");
+}
+
+fn millis_since(start: Instant) -> u64 {
+    u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX)
+}